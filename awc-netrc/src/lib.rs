@@ -0,0 +1,170 @@
+use awc::http::header::AUTHORIZATION;
+use awc::http::Uri;
+use awc::ClientRequest;
+use netrc::{Authenticator, CredentialSource, Netrc, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Netrc credentials of the requests of `awc`, the client of Actix Web.
+///
+/// The credentials of the host of the request URI are added to the requests
+/// without `Authorization` header, over HTTPS or to a loopback host only
+/// unless [`https_only`](NetrcAuth::https_only) is disabled. The value is
+/// cheap to clone, to be shared by the workers with `web::Data`.
+///
+/// ```no_run
+/// use awc::Client;
+/// use awc_netrc::NetrcAuth;
+///
+/// # async fn call() {
+/// let netrc = NetrcAuth::new().unwrap();
+/// let client = Client::default();
+/// let res = netrc
+///     .apply(client.get("https://domain.com/api"))
+///     .send()
+///     .await;
+/// # }
+/// ```
+pub struct NetrcAuth<S = Netrc> {
+    source: Arc<S>,
+    https_only: bool,
+    auth_scheme: AuthScheme,
+}
+
+/// Authentication scheme of the netrc credentials.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum AuthScheme {
+    /// `Basic` authentication with the login and the password.
+    #[default]
+    Basic,
+
+    /// `Bearer` authentication with the password as token.
+    Bearer,
+}
+
+impl<S> Clone for NetrcAuth<S> {
+    fn clone(&self) -> Self {
+        NetrcAuth {
+            source: self.source.clone(),
+            https_only: self.https_only,
+            auth_scheme: self.auth_scheme,
+        }
+    }
+}
+
+impl NetrcAuth {
+    pub fn new() -> Result<Self> {
+        Netrc::new().map(NetrcAuth::from_source)
+    }
+
+    pub fn from_file(file: &Path) -> Result<Self> {
+        Netrc::from_file(file).map(NetrcAuth::from_source)
+    }
+}
+
+impl<S: CredentialSource> NetrcAuth<S> {
+    /// Create the credentials of any source, like a chain of a netrc and
+    /// other backends.
+    pub fn from_source(source: S) -> Self {
+        NetrcAuth::from_arc(Arc::new(source))
+    }
+
+    /// Create the credentials of a source shared with the rest of the
+    /// application.
+    pub fn from_arc(source: Arc<S>) -> Self {
+        NetrcAuth {
+            source,
+            https_only: true,
+            auth_scheme: AuthScheme::default(),
+        }
+    }
+
+    /// Send the netrc credentials only over HTTPS, or to a loopback address
+    /// like `localhost` (enabled by default).
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.https_only = enabled;
+        self
+    }
+
+    /// Set the authentication scheme of the netrc credentials (`Basic` by
+    /// default).
+    pub fn auth_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.auth_scheme = scheme;
+        self
+    }
+
+    /// Add the credentials of the host of the request URI to a request.
+    pub fn apply(&self, req: ClientRequest) -> ClientRequest {
+        if req.headers().contains_key(AUTHORIZATION) {
+            return req;
+        }
+        let Some(auth) = self.authenticator(req.get_uri()) else {
+            return req;
+        };
+        match self.auth_scheme {
+            AuthScheme::Basic => req.basic_auth(&auth.login, &auth.password),
+            AuthScheme::Bearer => req.bearer_auth(&auth.password),
+        }
+    }
+
+    /// Authenticator of a request to `uri`.
+    fn authenticator(&self, uri: &Uri) -> Option<Authenticator> {
+        let host = uri.host()?;
+        let scheme = uri.scheme_str();
+        // The `Uri` of `awc` is the one of `http` 0.2, not the one of
+        // `netrc::http::is_secure`.
+        if self.https_only && !scheme.is_some_and(|scheme| netrc::is_secure(scheme, host)) {
+            return None;
+        }
+        let port = uri.port_u16().or(match scheme {
+            Some("https") => Some(443),
+            Some("http") => Some(80),
+            _ => None,
+        });
+        self.source.find(host, port, scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authenticator() {
+        let nrc: Netrc = "\
+            machine host.domain.com login log password pass
+            machine host.domain.com:8443 login port password pass
+            machine localhost:8080 login local password pass
+            "
+        .parse()
+        .unwrap();
+        let netrc = NetrcAuth::from_source(nrc);
+        let login = |netrc: &NetrcAuth, uri: &str| {
+            netrc
+                .authenticator(&uri.parse().unwrap())
+                .map(|auth| auth.login)
+        };
+
+        assert_eq!(
+            login(&netrc, "https://host.domain.com/api"),
+            Some(String::from("log"))
+        );
+        assert_eq!(
+            login(&netrc, "https://host.domain.com:8443/api"),
+            Some(String::from("port"))
+        );
+        assert_eq!(login(&netrc, "http://host.domain.com/api"), None);
+        assert_eq!(
+            login(&netrc, "http://localhost:8080/api"),
+            Some(String::from("local"))
+        );
+        assert_eq!(login(&netrc, "https://other.com/api"), None);
+        assert_eq!(login(&netrc, "ftp://host.domain.com/file"), None);
+
+        let netrc = netrc.https_only(false);
+        assert_eq!(
+            login(&netrc, "http://host.domain.com/api"),
+            Some(String::from("log"))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Cargo credential provider reading the registry tokens from the netrc
+//! file.
+//!
+//! The token of a registry is the password of the entry of the host of its
+//! index, like `machine crates.mycorp.com login token password <value>`.
+//!
+//! ```toml
+//! # ~/.cargo/config.toml
+//! [registry]
+//! global-credential-providers = ["cargo:token", "cargo-credential-netrc"]
+//!
+//! [registries.mycorp]
+//! index = "sparse+https://crates.mycorp.com/index/"
+//! credential-provider = ["cargo-credential-netrc", "-f", "/etc/netrc.d/cargo"]
+//! ```
+//!
+//! The tokens are read only: `cargo login` and `cargo logout` are not
+//! supported.
+
+use cargo_credential::{
+    Action, CacheControl, Credential, CredentialResponse, Error, RegistryInfo, Secret,
+};
+use netrc::{CredentialSource, MergeStrategy, Netrc};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use url::Url;
+
+struct NetrcCredential;
+
+impl Credential for NetrcCredential {
+    fn perform(
+        &self,
+        registry: &RegistryInfo<'_>,
+        action: &Action<'_>,
+        args: &[&str],
+    ) -> Result<CredentialResponse, Error> {
+        if !matches!(action, Action::Get(_)) {
+            return Err(Error::OperationNotSupported);
+        }
+        let nrc = load(&files(args)?).map_err(|e| Error::Other(e.into()))?;
+        let token = token(&nrc, registry.index_url).ok_or(Error::NotFound)?;
+        Ok(CredentialResponse::Get {
+            token: Secret::from(token),
+            cache: CacheControl::Session,
+            operation_independent: true,
+        })
+    }
+}
+
+/// Files of the `-f <file>` arguments of the provider.
+fn files(args: &[&str]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "-f" | "--file" => match args.next() {
+                Some(file) => files.push(PathBuf::from(file)),
+                None => return Err(Error::from(format!("missing file after '{}'", arg))),
+            },
+            _ => return Err(Error::from(format!("unexpected argument '{}'", arg))),
+        }
+    }
+    Ok(files)
+}
+
+/// Netrc of the files, the first ones having the precedence, or the default
+/// netrc without file. A missing netrc is empty.
+fn load(files: &[PathBuf]) -> netrc::Result<Netrc> {
+    if files.is_empty() {
+        return match Netrc::new() {
+            Err(netrc::Error::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(Netrc::default()),
+            res => res,
+        };
+    }
+    let mut nrc = Netrc::default();
+    for file in files {
+        nrc.merge(Netrc::from_file(file)?, MergeStrategy::PreferSelf)?;
+    }
+    Ok(nrc)
+}
+
+/// Token of the registry of `index_url`, like
+/// `sparse+https://crates.mycorp.com/index/`.
+fn token<S: CredentialSource>(source: &S, index_url: &str) -> Option<String> {
+    let url = index_url.strip_prefix("sparse+").unwrap_or(index_url);
+    let url = Url::parse(url).ok()?;
+    let auth = source.find(
+        url.host_str()?,
+        url.port_or_known_default(),
+        Some(url.scheme()),
+    )?;
+    Some(auth.password.clone()).filter(|token| !token.is_empty())
+}
+
+fn main() {
+    cargo_credential::main(NetrcCredential);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let nrc: Netrc = "\
+            machine crates.mycorp.com login token password secret
+            machine localhost:8080 login token password local
+            machine empty.mycorp.com login token
+            "
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            token(&nrc, "sparse+https://crates.mycorp.com/index/"),
+            Some(String::from("secret"))
+        );
+        assert_eq!(
+            token(&nrc, "https://crates.mycorp.com/git/index"),
+            Some(String::from("secret"))
+        );
+        assert_eq!(
+            token(&nrc, "sparse+http://localhost:8080/"),
+            Some(String::from("local"))
+        );
+        assert_eq!(token(&nrc, "sparse+https://empty.mycorp.com/"), None);
+        assert_eq!(token(&nrc, "sparse+https://other.com/"), None);
+        assert_eq!(token(&nrc, "not a url"), None);
+    }
+
+    #[test]
+    fn test_files() {
+        assert_eq!(
+            files(&["-f", "a", "--file", "b"]).unwrap(),
+            vec![PathBuf::from("a"), PathBuf::from("b")]
+        );
+        assert!(files(&["-f"]).is_err());
+        assert!(files(&["other"]).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! The JSON objects of the protocol, whose values are all strings.
+
+use std::fmt::Write;
+
+/// Parse an object of strings, like `{"Username": "log", "Secret": "pass"}`.
+pub fn parse_object(s: &str) -> Result<Vec<(String, String)>, String> {
+    let mut parser = Parser {
+        chars: s.chars().peekable(),
+    };
+    let mut fields = Vec::new();
+    parser.expect('{')?;
+    if parser.peek() == Some('}') {
+        parser.next();
+    } else {
+        loop {
+            let key = parser.string()?;
+            parser.expect(':')?;
+            let value = parser.string()?;
+            fields.push((key, value));
+            match parser.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(String::from("expected ',' or '}'")),
+            }
+        }
+    }
+    match parser.peek() {
+        None => Ok(fields),
+        Some(_) => Err(String::from("unexpected characters after the object")),
+    }
+}
+
+/// Object of strings, in the order of the fields.
+pub fn object<'a, I>(fields: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let fields: Vec<_> = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}:{}", string(key), string(value)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Quoted and escaped string.
+pub fn string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(res, "\\u{:04x}", c as u32);
+            }
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    /// Next character, after the whitespaces.
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.peek()?;
+        self.chars.next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(format!("expected '{}'", expected)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut res = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(res),
+                Some('\\') => match self.chars.next() {
+                    Some('"') => res.push('"'),
+                    Some('\\') => res.push('\\'),
+                    Some('/') => res.push('/'),
+                    Some('b') => res.push('\u{8}'),
+                    Some('f') => res.push('\u{c}'),
+                    Some('n') => res.push('\n'),
+                    Some('r') => res.push('\r'),
+                    Some('t') => res.push('\t'),
+                    Some('u') => res.push(self.unicode()?),
+                    _ => return Err(String::from("invalid escape sequence")),
+                },
+                Some(c) => res.push(c),
+                None => return Err(String::from("unterminated string")),
+            }
+        }
+    }
+
+    /// Character of a `\uXXXX` sequence, or of a surrogate pair.
+    fn unicode(&mut self) -> Result<char, String> {
+        let high = self.hex()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                    return Err(String::from("invalid surrogate pair"));
+                }
+                let low = self.hex()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(String::from("invalid surrogate pair"));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| String::from("invalid unicode escape"))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.by_ref().take(4).collect();
+        match digits.len() {
+            4 => {
+                u32::from_str_radix(&digits, 16).map_err(|_| String::from("invalid unicode escape"))
+            }
+            _ => Err(String::from("invalid unicode escape")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object() {
+        let fields = |s: &str| {
+            parse_object(s).map(|fields| {
+                fields
+                    .into_iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            fields(r#" { "ServerURL" : "https://host.com", "Secret":"a\"b\\cé😀" } "#),
+            Ok(vec![
+                String::from("ServerURL=https://host.com"),
+                String::from("Secret=a\"b\\cé😀")
+            ])
+        );
+        assert_eq!(
+            fields(r#"{"k": "\u00e9\ud83d\ude00"}"#),
+            Ok(vec![String::from("k=é😀")])
+        );
+        assert_eq!(fields("{}"), Ok(vec![]));
+        assert!(fields(r#"{"k": "\ud83d"}"#).is_err());
+        assert!(fields(r#"{"a": 1}"#).is_err());
+        assert!(fields(r#"{"a": "b""#).is_err());
+        assert!(fields(r#"{"a": "b"} x"#).is_err());
+        assert!(fields(r#"{"a": "\x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_object() {
+        assert_eq!(
+            object([("Username", "log"), ("Secret", "a\"b\n\u{1}")]),
+            r#"{"Username":"log","Secret":"a\"b\n\u0001"}"#
+        );
+        assert_eq!(
+            parse_object(&object([("k", "\t\"é")])),
+            Ok(vec![(String::from("k"), String::from("\t\"é"))])
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Docker credential helper storing the credentials in the netrc file.
+//!
+//! ```text
+//! $ cat ~/.docker/config.json
+//! { "credsStore": "netrc" }
+//! ```
+//!
+//! The entry of a registry is the machine of its host, with its port if any,
+//! like `machine registry.mycorp.com:5000 login log password pass`. The
+//! `default` entry is never used. The file is updated in place, keeping its
+//! comments and the other entries.
+
+use netrc::{Authenticator, DiscoveryOptions, LookupOptions, Netrc, NetrcDocument};
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+mod json;
+
+const USAGE: &str = "usage: docker-credential-netrc [-f <file>] <get|store|erase|list|version>";
+
+/// Message of a missing registry, recognized by Docker.
+const NOT_FOUND: &str = "credentials not found in native keychain";
+
+/// Machine name of a server URL, like `registry.mycorp.com:5000` for
+/// `https://registry.mycorp.com:5000/v2/`.
+fn machine_name(server_url: &str) -> Option<String> {
+    let server = server_url.trim();
+    let server = server.split_once("://").map_or(server, |(_, rest)| rest);
+    let server = server.split('/').next().unwrap_or_default();
+    let server = server.rsplit_once('@').map_or(server, |(_, host)| host);
+    Some(server.to_lowercase()).filter(|s| !s.is_empty())
+}
+
+/// Host and port of a machine name.
+fn host_port(name: &str) -> (&str, Option<u16>) {
+    match name.rsplit_once(':') {
+        Some((host, port)) if !name.ends_with(']') => match port.parse() {
+            Ok(port) => (host, Some(port)),
+            Err(_) => (name, None),
+        },
+        _ => (name, None),
+    }
+}
+
+/// Netrc file of the helper, created if it does not exist.
+fn netrc_file(file: Option<PathBuf>) -> io::Result<PathBuf> {
+    let options = DiscoveryOptions {
+        require_exists: false,
+        ..Default::default()
+    };
+    file.or_else(|| Netrc::discover(&options))
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "no netrc file found"))
+}
+
+/// Document of a netrc file, empty if it does not exist.
+fn document(file: &Path) -> netrc::Result<NetrcDocument> {
+    match NetrcDocument::from_file(file) {
+        Err(netrc::Error::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(NetrcDocument::default()),
+        res => res,
+    }
+}
+
+/// Credentials of a server, as the JSON object of the protocol.
+fn get(nrc: &Netrc, server_url: &str) -> Option<String> {
+    let server_url = server_url.trim();
+    let name = machine_name(server_url)?;
+    let (host, port) = host_port(&name);
+    let options = LookupOptions {
+        default_entry: false,
+        ..Default::default()
+    };
+    let auth = nrc.lookup_with(host, port, &options)?;
+    Some(json::object([
+        ("ServerURL", server_url),
+        ("Username", auth.login.as_str()),
+        ("Secret", auth.password.as_str()),
+    ]))
+}
+
+/// Add or update the credentials of a server of the JSON object of the
+/// protocol.
+fn store(doc: &mut NetrcDocument, input: &str) -> Result<(), String> {
+    let fields = json::parse_object(input)?;
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .ok_or_else(|| format!("missing field '{}'", key))
+    };
+    let name = machine_name(field("ServerURL")?).ok_or("invalid server URL")?;
+    doc.insert(
+        &name,
+        &Authenticator::new(field("Username")?, "", field("Secret")?),
+    );
+    Ok(())
+}
+
+/// Machines of the netrc with their login, as the JSON object of the
+/// protocol.
+fn list(doc: &NetrcDocument) -> String {
+    json::object(
+        doc.machines()
+            .filter(|m| !m.is_default())
+            .map(|m| (m.name(), m.get("login").unwrap_or_default())),
+    )
+}
+
+fn run(file: Option<PathBuf>, action: &str) -> Result<(), String> {
+    let mut input = String::new();
+    if matches!(action, "get" | "store" | "erase") {
+        io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| e.to_string())?;
+    }
+    let file = netrc_file(file).map_err(|e| e.to_string())?;
+    match action {
+        "get" => {
+            let nrc = match Netrc::from_file(&file) {
+                Err(netrc::Error::Io(e)) if e.kind() == ErrorKind::NotFound => Netrc::default(),
+                res => res.map_err(|e| e.to_string())?,
+            };
+            println!("{}", get(&nrc, &input).ok_or(NOT_FOUND)?);
+        }
+        "store" => {
+            let mut doc = document(&file).map_err(|e| e.to_string())?;
+            store(&mut doc, &input)?;
+            doc.save(&file).map_err(|e| e.to_string())?;
+        }
+        "erase" => {
+            let mut doc = document(&file).map_err(|e| e.to_string())?;
+            let name = machine_name(&input).ok_or(NOT_FOUND)?;
+            doc.remove(&name).ok_or(NOT_FOUND)?;
+            doc.save(&file).map_err(|e| e.to_string())?;
+        }
+        "list" => {
+            let doc = document(&file).map_err(|e| e.to_string())?;
+            println!("{}", list(&doc));
+        }
+        _ => return Err(String::from(USAGE)),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut file = None;
+    let mut action = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--file" => file = args.next().map(PathBuf::from),
+            _ if action.is_none() => action = Some(arg),
+            _ => action = Some(String::new()),
+        }
+    }
+    match action.as_deref() {
+        Some("version") | Some("--version") => {
+            println!("docker-credential-netrc {}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        }
+        Some(action) => match run(file, action) {
+            Ok(()) => ExitCode::SUCCESS,
+            // The errors are read from the standard output by Docker.
+            Err(e) => {
+                println!("{}", e);
+                ExitCode::FAILURE
+            }
+        },
+        None => {
+            println!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_machine_name() {
+        assert_eq!(
+            machine_name("https://index.docker.io/v1/\n"),
+            Some(String::from("index.docker.io"))
+        );
+        assert_eq!(
+            machine_name("Registry.MyCorp.com:5000"),
+            Some(String::from("registry.mycorp.com:5000"))
+        );
+        assert_eq!(machine_name("https://"), None);
+        assert_eq!(
+            host_port("registry.mycorp.com:5000"),
+            ("registry.mycorp.com", Some(5000))
+        );
+        assert_eq!(host_port("[::1]"), ("[::1]", None));
+    }
+
+    #[test]
+    fn test_get() {
+        let nrc: Netrc = "\
+            machine registry.mycorp.com:5000 login log password pass
+            default login anonymous password anonymous
+            "
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            get(&nrc, "https://registry.mycorp.com:5000/v2/"),
+            Some(String::from(
+                r#"{"ServerURL":"https://registry.mycorp.com:5000/v2/","Username":"log","Secret":"pass"}"#
+            ))
+        );
+        assert_eq!(get(&nrc, "https://other.com\n"), None);
+    }
+
+    #[test]
+    fn test_store_list() {
+        let mut doc: NetrcDocument = "\
+            # registries
+            machine registry.mycorp.com login old password old
+            default login anonymous password anonymous
+            "
+        .parse()
+        .unwrap();
+        store(
+            &mut doc,
+            r#"{"ServerURL":"https://registry.mycorp.com","Username":"log","Secret":"pass"}"#,
+        )
+        .unwrap();
+        store(
+            &mut doc,
+            r#"{"ServerURL":"other.com:5000","Username":"other","Secret":"pass"}"#,
+        )
+        .unwrap();
+        assert!(store(&mut doc, r#"{"ServerURL":"other.com"}"#).is_err());
+
+        assert!(doc.to_string().contains("# registries"));
+        assert_eq!(
+            list(&doc),
+            r#"{"registry.mycorp.com":"log","other.com:5000":"other"}"#
+        );
+
+        doc.remove("other.com:5000");
+        assert_eq!(list(&doc), r#"{"registry.mycorp.com":"log"}"#);
+    }
+}
@@ -0,0 +1,235 @@
+//! git credential helper reading the netrc file.
+//!
+//! ```text
+//! $ git config --global credential.helper netrc
+//! $ git config --global credential.helper 'netrc -f ~/.netrc.work -f ~/.netrc'
+//! ```
+//!
+//! Only the `get` action is supported: the credentials are never stored or
+//! erased. With a path in the request (see `credential.useHttpPath`), a
+//! `machine host/path` entry is used first.
+
+use netrc::{CredentialSource, MergeStrategy, Netrc};
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: git-credential-netrc [-f <file>]... <get|store|erase>";
+
+/// Attributes of a request of git, see gitcredentials(7).
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Request {
+    protocol: Option<String>,
+    host: Option<String>,
+    path: Option<String>,
+    username: Option<String>,
+}
+
+impl Request {
+    /// Read the `key=value` lines until an empty line or the end of the input.
+    fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut req = Request::default();
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = Some(value.to_owned());
+            match key {
+                "protocol" => req.protocol = value,
+                "host" => req.host = value,
+                "path" => req.path = value,
+                "username" => req.username = value,
+                _ => {}
+            }
+        }
+        Ok(req)
+    }
+
+    /// Host and port of the request, with the default port of the protocol
+    /// if the host has no port.
+    fn host_port(&self) -> Option<(&str, Option<u16>)> {
+        let host = self.host.as_deref().filter(|h| !h.is_empty())?;
+        let (host, port) = match host.rsplit_once(':') {
+            Some((name, port)) if !host.ends_with(']') => match port.parse() {
+                Ok(port) => (name, Some(port)),
+                Err(_) => (host, None),
+            },
+            _ => (host, None),
+        };
+        let port = port.or(match self.protocol.as_deref() {
+            Some("https") => Some(443),
+            Some("http") => Some(80),
+            _ => None,
+        });
+        Some((host, port))
+    }
+
+    /// Login and password of the request.
+    ///
+    /// An entry with another login than the username of the request is not
+    /// used.
+    fn credentials(&self, nrc: &Netrc) -> Option<(String, String)> {
+        let (host, port) = self.host_port()?;
+        let auth = self
+            .path
+            .as_deref()
+            .filter(|path| !path.is_empty())
+            .and_then(|path| nrc.hosts.get(&format!("{}/{}", host, path)).cloned())
+            .or_else(|| nrc.find(host, port, self.protocol.as_deref()))?;
+        match &self.username {
+            Some(username) if !auth.login.is_empty() && &auth.login != username => None,
+            Some(username) if auth.login.is_empty() => {
+                Some((username.clone(), auth.password.clone()))
+            }
+            _ => Some((auth.login.clone(), auth.password.clone())),
+        }
+    }
+}
+
+/// Netrc of the files, the first ones having the precedence, or the default
+/// netrc without file. A missing netrc is empty.
+fn load(files: &[PathBuf]) -> netrc::Result<Netrc> {
+    if files.is_empty() {
+        return match Netrc::new() {
+            Err(netrc::Error::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(Netrc::default()),
+            res => res,
+        };
+    }
+    let mut nrc = Netrc::default();
+    for file in files {
+        nrc.merge(Netrc::from_file(file)?, MergeStrategy::PreferSelf)?;
+    }
+    Ok(nrc)
+}
+
+fn main() -> ExitCode {
+    let mut files = Vec::new();
+    let mut action = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--file" => match args.next() {
+                Some(file) => files.push(PathBuf::from(file)),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if action.is_none() => action = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    let req = match Request::read(io::stdin().lock()) {
+        Ok(req) => req,
+        Err(e) => {
+            eprintln!("git-credential-netrc: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match action.as_deref() {
+        Some("get") => {}
+        // The credentials are read only.
+        Some("store") | Some("erase") => return ExitCode::SUCCESS,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    }
+
+    let nrc = match load(&files) {
+        Ok(nrc) => nrc,
+        Err(e) => {
+            eprintln!("git-credential-netrc: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some((username, password)) = req.credentials(&nrc) {
+        let mut stdout = io::stdout().lock();
+        if writeln!(stdout, "username={}\npassword={}", username, password).is_err() {
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(input: &str) -> Request {
+        Request::read(input.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(
+            request("protocol=https\nhost=host.domain.com:8443\nwwwauth[]=Basic\n\nignored=1\n"),
+            Request {
+                protocol: Some(String::from("https")),
+                host: Some(String::from("host.domain.com:8443")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            request("host=[::1]:8080\n").host_port(),
+            Some(("[::1]", Some(8080)))
+        );
+        assert_eq!(
+            request("protocol=https\nhost=[::1]\n").host_port(),
+            Some(("[::1]", Some(443)))
+        );
+        assert_eq!(request("protocol=https\n").host_port(), None);
+    }
+
+    #[test]
+    fn test_credentials() {
+        let nrc: Netrc = "\
+            machine host.domain.com login log password pass
+            machine host.domain.com:8443 login port password pass
+            machine host.domain.com/org/repo.git login repo password pass
+            machine token.domain.com password token
+            "
+        .parse()
+        .unwrap();
+        let credentials = |input: &str| request(input).credentials(&nrc);
+        let pair = |login: &str, password: &str| Some((login.to_owned(), password.to_owned()));
+
+        assert_eq!(
+            credentials("protocol=https\nhost=host.domain.com\n"),
+            pair("log", "pass")
+        );
+        assert_eq!(
+            credentials("protocol=https\nhost=host.domain.com:8443\n"),
+            pair("port", "pass")
+        );
+        assert_eq!(
+            credentials("protocol=https\nhost=host.domain.com\npath=org/repo.git\n"),
+            pair("repo", "pass")
+        );
+        assert_eq!(
+            credentials("protocol=https\nhost=host.domain.com\npath=org/other.git\n"),
+            pair("log", "pass")
+        );
+        assert_eq!(
+            credentials("protocol=https\nhost=host.domain.com\nusername=other\n"),
+            None
+        );
+        assert_eq!(
+            credentials("protocol=https\nhost=token.domain.com\nusername=user\n"),
+            pair("user", "token")
+        );
+        assert_eq!(credentials("protocol=https\nhost=other.com\n"), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! netrc support for the credentials of gitoxide (`gix`).
+//!
+//! [`NetrcCredentials`] answers the credential requests of the HTTPS remotes
+//! with the netrc file, like `git-credential-netrc` does for git:
+//!
+//! ```ignore
+//! use gix_netrc::NetrcCredentials;
+//!
+//! let repo = gix::discover(".").unwrap();
+//! let remote = repo.find_remote("origin").unwrap();
+//! let connection = remote
+//!     .connect(gix::remote::Direction::Fetch)
+//!     .unwrap()
+//!     .with_credentials(NetrcCredentials::new().unwrap().helper());
+//! ```
+//!
+//! Only the `get` action is answered: the credentials are never stored or
+//! erased in the netrc.
+
+use gix_credentials::helper::{Action, NextAction};
+use gix_credentials::protocol::{self, Context, Outcome};
+use gix_sec::identity::Account;
+use netrc::{CredentialSource, Netrc, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Credential helper of `gix` reading the netrc.
+#[derive(Debug, Clone)]
+pub struct NetrcCredentials {
+    netrc: Arc<Netrc>,
+    use_http_path: bool,
+}
+
+impl NetrcCredentials {
+    pub fn new() -> Result<Self> {
+        Netrc::new().map(NetrcCredentials::from_netrc)
+    }
+
+    pub fn from_file(file: &Path) -> Result<Self> {
+        Netrc::from_file(file).map(NetrcCredentials::from_netrc)
+    }
+
+    pub fn from_netrc(netrc: Netrc) -> Self {
+        NetrcCredentials::from_arc(Arc::new(netrc))
+    }
+
+    /// Create a helper sharing its netrc with the rest of the application.
+    pub fn from_arc(netrc: Arc<Netrc>) -> Self {
+        NetrcCredentials {
+            netrc,
+            use_http_path: false,
+        }
+    }
+
+    /// Use the path of the HTTP URLs, like `credential.useHttpPath` of git,
+    /// so that a `machine host.com/org/repo.git` entry is used before the
+    /// entry of the host (disabled by default).
+    pub fn use_http_path(mut self, enabled: bool) -> Self {
+        self.use_http_path = enabled;
+        self
+    }
+
+    /// Answer an action of `gix`: the credentials of a `get` action, if the
+    /// netrc has them.
+    // The error type is the one of the helpers of `gix`.
+    #[allow(clippy::result_large_err)]
+    pub fn invoke(&self, action: Action) -> protocol::Result {
+        match action {
+            Action::Get(ctx) => Ok(self.get(ctx)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Helper to give to `gix`, like to `Connection::with_credentials`.
+    // The error type is the one of the helpers of `gix`.
+    #[allow(clippy::result_large_err)]
+    pub fn helper(self) -> impl FnMut(Action) -> protocol::Result {
+        move |action| self.invoke(action)
+    }
+
+    /// Helper trying the netrc before `other`, like the credential helpers
+    /// configured for git. The `store` and `erase` actions are passed to
+    /// `other`.
+    // The error type is the one of the helpers of `gix`.
+    #[allow(clippy::result_large_err)]
+    pub fn or_else<F>(self, mut other: F) -> impl FnMut(Action) -> protocol::Result
+    where
+        F: FnMut(Action) -> protocol::Result,
+    {
+        move |action| {
+            if let Action::Get(ctx) = &action {
+                if let Some(outcome) = self.get(ctx.clone()) {
+                    return Ok(Some(outcome));
+                }
+            }
+            other(action)
+        }
+    }
+
+    /// Outcome of a `get` action, with the URL of the context destructured
+    /// if its host is missing.
+    fn get(&self, mut ctx: Context) -> Option<Outcome> {
+        if ctx.host.is_none() {
+            ctx.destructure_url_in_place(self.use_http_path).ok()?;
+        }
+        let (username, password) = self.credentials(&ctx)?;
+        Some(Outcome {
+            identity: Account { username, password },
+            next: NextAction::from(ctx),
+        })
+    }
+
+    /// Login and password of a request.
+    ///
+    /// An entry with another login than the username of the request is not
+    /// used.
+    fn credentials(&self, ctx: &Context) -> Option<(String, String)> {
+        let (host, port) = host_port(ctx)?;
+        let path = ctx
+            .path
+            .as_ref()
+            .map(|path| String::from_utf8_lossy(path))
+            .filter(|path| !path.is_empty());
+        let auth = path
+            .and_then(|path| {
+                let name = format!("{}/{}", host, path.trim_start_matches('/'));
+                self.netrc.hosts.get(&name).cloned()
+            })
+            .or_else(|| self.netrc.find(host, port, ctx.protocol.as_deref()))?;
+        match &ctx.username {
+            Some(username) if !auth.login.is_empty() && &auth.login != username => None,
+            Some(username) if auth.login.is_empty() => {
+                Some((username.clone(), auth.password.clone()))
+            }
+            _ => Some((auth.login.clone(), auth.password.clone())),
+        }
+    }
+}
+
+/// Host and port of a request, with the default port of the protocol if the
+/// host has no port.
+fn host_port(ctx: &Context) -> Option<(&str, Option<u16>)> {
+    let host = ctx.host.as_deref().filter(|h| !h.is_empty())?;
+    let (host, port) = match host.rsplit_once(':') {
+        Some((name, port)) if !host.ends_with(']') => match port.parse() {
+            Ok(port) => (name, Some(port)),
+            Err(_) => (host, None),
+        },
+        _ => (host, None),
+    };
+    let port = port.or(match ctx.protocol.as_deref() {
+        Some("https") => Some(443),
+        Some("http") => Some(80),
+        _ => None,
+    });
+    Some((host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn helper() -> NetrcCredentials {
+        let nrc: Netrc = "\
+            machine host.domain.com login log password pass
+            machine host.domain.com:8443 login port password pass
+            machine host.domain.com/org/repo.git login repo password pass
+            machine token.domain.com password token
+            "
+        .parse()
+        .unwrap();
+        NetrcCredentials::from_netrc(nrc)
+    }
+
+    fn get(helper: &NetrcCredentials, url: &str) -> Option<(String, String)> {
+        helper
+            .invoke(Action::get_for_url(url))
+            .unwrap()
+            .map(|outcome| (outcome.identity.username, outcome.identity.password))
+    }
+
+    #[test]
+    fn test_invoke() {
+        let helper = helper();
+        let pair = |login: &str, password: &str| Some((login.to_owned(), password.to_owned()));
+
+        assert_eq!(
+            get(&helper, "https://host.domain.com/org/repo.git"),
+            pair("log", "pass")
+        );
+        assert_eq!(
+            get(&helper, "https://host.domain.com:8443/org/repo.git"),
+            pair("port", "pass")
+        );
+        assert_eq!(
+            get(&helper, "https://other@host.domain.com/org/repo.git"),
+            None
+        );
+        assert_eq!(
+            get(&helper, "https://user@token.domain.com/repo.git"),
+            pair("user", "token")
+        );
+        assert_eq!(get(&helper, "https://other.com/repo.git"), None);
+
+        let helper = helper.use_http_path(true);
+        assert_eq!(
+            get(&helper, "https://host.domain.com/org/repo.git"),
+            pair("repo", "pass")
+        );
+        assert_eq!(
+            get(&helper, "https://host.domain.com/org/other.git"),
+            pair("log", "pass")
+        );
+    }
+
+    #[test]
+    // The closure returns the result of the helpers of `gix`.
+    #[allow(clippy::result_large_err)]
+    fn test_or_else() {
+        let mut calls = 0;
+        let mut helper = helper().or_else(|_| {
+            calls += 1;
+            Ok(None)
+        });
+        assert!(
+            helper(Action::get_for_url("https://host.domain.com/repo.git"))
+                .unwrap()
+                .is_some()
+        );
+        assert!(helper(Action::get_for_url("https://other.com/repo.git"))
+            .unwrap()
+            .is_none());
+        drop(helper);
+        assert_eq!(calls, 1);
+    }
+}
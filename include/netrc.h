@@ -0,0 +1,94 @@
+#ifndef NETRC_H
+#define NETRC_H
+
+/* C API of src/capi.rs, see cbindgen.toml to regenerate it. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Parsed netrc, to free with `netrc_free`.
+ */
+typedef struct NetrcHandle NetrcHandle;
+
+/**
+ * Authenticator of a machine. Its strings belong to the netrc, and are
+ * valid until it is freed. A value containing a NUL byte, which can not be
+ * a C string, is `NULL`.
+ */
+typedef struct NetrcAuthenticator {
+  const char *login;
+  const char *account;
+  const char *password;
+} NetrcAuthenticator;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parse the content of a netrc file.
+ *
+ * Return `NULL` on error, with `*error` set to the message if `error` is
+ * not `NULL`, to free with `netrc_string_free`.
+ *
+ * # Safety
+ *
+ * `content` must be a valid C string, and `error` `NULL` or a valid pointer.
+ */
+NetrcHandle *netrc_parse(const char *content,
+                         char **error);
+
+/**
+ * Parse a netrc file, like `netrc_parse`.
+ *
+ * # Safety
+ *
+ * `path` must be a valid C string, and `error` `NULL` or a valid pointer.
+ */
+NetrcHandle *netrc_parse_file(const char *path,
+                              char **error);
+
+/**
+ * Look up the authenticator of a host like `Netrc::lookup`, with the
+ * `default` entry as fallback.
+ *
+ * Return 1 if found, with `*auth` set, 0 if not found, and -1 if an
+ * argument is invalid.
+ *
+ * # Safety
+ *
+ * `netrc` must be `NULL` or returned by `netrc_parse` or `netrc_parse_file`
+ * and not freed, `host` a valid C string, and `auth` `NULL` or a valid
+ * pointer.
+ */
+int netrc_lookup(const NetrcHandle *netrc,
+                 const char *host,
+                 NetrcAuthenticator *auth);
+
+/**
+ * Free a netrc.
+ *
+ * # Safety
+ *
+ * `netrc` must be `NULL` or returned by `netrc_parse` or `netrc_parse_file`
+ * and not freed yet.
+ */
+void netrc_free(NetrcHandle *netrc);
+
+/**
+ * Free an error message.
+ *
+ * # Safety
+ *
+ * `s` must be `NULL` or a message set by this library and not freed yet.
+ */
+void netrc_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NETRC_H */
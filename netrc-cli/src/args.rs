@@ -0,0 +1,108 @@
+//! Arguments of the command line.
+
+use crate::Error;
+
+/// Arguments not parsed yet.
+///
+/// The options are taken first, anywhere on the command line, then the
+/// positional arguments in order.
+pub struct Args(Vec<String>);
+
+impl Args {
+    pub fn new<I: IntoIterator<Item = String>>(args: I) -> Self {
+        Args(args.into_iter().collect())
+    }
+
+    /// Take a flag, like `--all`.
+    pub fn flag(&mut self, names: &[&str]) -> bool {
+        let mut found = false;
+        while let Some(i) = self.position(names) {
+            self.0.remove(i);
+            found = true;
+        }
+        found
+    }
+
+    /// Take the value of an option, like `--field password` or
+    /// `--field=password`. The last occurrence wins.
+    pub fn value(&mut self, names: &[&str]) -> Result<Option<String>, Error> {
+        Ok(self.values(names)?.pop())
+    }
+
+    /// Take all the values of an option given several times, like
+    /// `-f a -f b`.
+    pub fn values(&mut self, names: &[&str]) -> Result<Vec<String>, Error> {
+        let mut values = Vec::new();
+        while let Some(i) = self.position(names) {
+            let arg = self.0.remove(i);
+            match arg.split_once('=') {
+                Some((_, value)) if arg.starts_with("--") => values.push(value.to_owned()),
+                _ if i < self.0.len() => values.push(self.0.remove(i)),
+                _ => return Err(Error::Usage(format!("missing value of '{}'", arg))),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Take the next positional argument.
+    pub fn positional(&mut self) -> Option<String> {
+        let i = self
+            .0
+            .iter()
+            .position(|arg| arg == "-" || !arg.starts_with('-'))?;
+        Some(self.0.remove(i))
+    }
+
+    /// Take the next positional argument, which is required.
+    pub fn required(&mut self, name: &str) -> Result<String, Error> {
+        self.positional()
+            .ok_or_else(|| Error::Usage(format!("missing argument <{}>", name)))
+    }
+
+    /// Fail if some arguments were not taken.
+    pub fn finish(self) -> Result<(), Error> {
+        match self.0.first() {
+            Some(arg) => Err(Error::Usage(format!("unexpected argument '{}'", arg))),
+            None => Ok(()),
+        }
+    }
+
+    fn position(&self, names: &[&str]) -> Option<usize> {
+        self.0.iter().position(|arg| {
+            names.iter().any(|name| {
+                arg == name
+                    || (name.starts_with("--")
+                        && arg.strip_prefix(name).is_some_and(|v| v.starts_with('=')))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Args {
+        Args::new(s.split_whitespace().map(str::to_owned))
+    }
+
+    #[test]
+    fn test_args() {
+        let mut a = args("get -f a host.com --field=login --file b -v");
+        assert_eq!(a.values(&["-f", "--file"]).unwrap(), vec!["a", "b"]);
+        assert_eq!(a.value(&["--field"]).unwrap(), Some(String::from("login")));
+        assert!(a.flag(&["-v"]));
+        assert!(!a.flag(&["-v"]));
+        assert_eq!(a.positional(), Some(String::from("get")));
+        assert_eq!(a.required("host").unwrap(), "host.com");
+        assert!(a.required("other").is_err());
+        assert!(a.finish().is_ok());
+
+        let mut a = args("get --field");
+        assert!(a.value(&["--field"]).is_err());
+
+        let mut a = args("get --fieldx");
+        assert_eq!(a.value(&["--field"]).unwrap(), None);
+        assert!(a.finish().is_err());
+    }
+}
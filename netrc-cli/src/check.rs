@@ -0,0 +1,134 @@
+//! Validation of a netrc file.
+
+use crate::Error;
+use netrc::{NetrcDocument, ParseOptions};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Problems of a netrc file: syntax error, duplicate machines, entries
+/// without password, `default` entry before machine entries, and unsafe
+/// permissions.
+pub fn check(file: &Path) -> Result<Vec<String>, Error> {
+    let doc = match NetrcDocument::from_file(file) {
+        Ok(doc) => doc,
+        Err(e @ netrc::Error::Parsing { .. }) => return Ok(vec![e.to_string()]),
+        Err(e) => return Err(e.into()),
+    };
+    let mut problems = lint(&doc);
+
+    let options = ParseOptions {
+        check_permissions: true,
+        ..Default::default()
+    };
+    match netrc::Netrc::from_file_with(file, &options) {
+        Err(e @ netrc::Error::Insecure { .. }) => problems.push(e.to_string()),
+        Err(e) => return Err(e.into()),
+        Ok(_) => {}
+    }
+    Ok(problems)
+}
+
+/// Problems of the entries of a document.
+pub fn lint(doc: &NetrcDocument) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in doc.machines() {
+        *counts.entry(entry.name()).or_default() += 1;
+    }
+    let mut reported = Vec::new();
+    for entry in doc.machines() {
+        let name = entry.name();
+        if counts[name] > 1 && !reported.contains(&name) {
+            reported.push(name);
+            problems.push(match entry.is_default() {
+                true => format!("the default entry is defined {} times", counts[name]),
+                false => format!(
+                    "machine '{}' is defined {} times, only the last definition is used",
+                    name, counts[name]
+                ),
+            });
+        }
+    }
+
+    for entry in doc.machines() {
+        let login = entry.get("login").unwrap_or_default();
+        if entry.get("password").is_none() && login != "anonymous" {
+            problems.push(match entry.is_default() {
+                true => String::from("the default entry has no password"),
+                false => format!("machine '{}' has no password", entry.name()),
+            });
+        }
+    }
+
+    let after_default: Vec<_> = doc
+        .machines()
+        .skip_while(|entry| !entry.is_default())
+        .filter(|entry| !entry.is_default())
+        .map(|entry| format!("'{}'", entry.name()))
+        .collect();
+    if !after_default.is_empty() {
+        problems.push(format!(
+            "the default entry is before the machines {}, ignored by some programs",
+            after_default.join(", ")
+        ));
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let doc: NetrcDocument = "\
+            machine host.domain.com login log password pass
+            machine ftp.domain.com login anonymous
+            default login log password pass
+            machine host.domain.com login log2 password pass
+            machine api.domain.com login token
+            "
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            lint(&doc),
+            vec![
+                "machine 'host.domain.com' is defined 2 times, only the last definition is used",
+                "machine 'api.domain.com' has no password",
+                "the default entry is before the machines 'host.domain.com', \
+                 'api.domain.com', ignored by some programs",
+            ]
+        );
+
+        let doc: NetrcDocument = "machine host.domain.com login log password pass\n"
+            .parse()
+            .unwrap();
+        assert!(lint(&doc).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("netrc-check-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("netrc");
+
+        fs::write(&file, "machine host.domain.com login log password pass\n").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+        assert!(check(&file).unwrap().is_empty());
+
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(check(&file).unwrap().len(), 1);
+
+        fs::write(&file, "machine\n").unwrap();
+        assert_eq!(check(&file).unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
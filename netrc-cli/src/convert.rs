@@ -0,0 +1,223 @@
+//! Import and export of the entries as JSON, TOML or CSV.
+//!
+//! An entry is a record of the fields `machine` (`default` for the default
+//! entry), `login`, `account` and `password`. The records are imported with
+//! the names of the fields used by the password managers too, like
+//! `username` or `url`.
+
+use crate::edit::{self, Fields};
+use crate::{csv, json, toml, Error};
+use netrc::NetrcDocument;
+use std::path::Path;
+
+/// Columns of the exported CSV.
+const COLUMNS: [&str; 4] = ["machine", "login", "account", "password"];
+
+/// Format of the records.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    Json,
+    Toml,
+    Csv,
+}
+
+impl Format {
+    /// Format of the `--format` option, or of the extension of a file,
+    /// JSON by default.
+    pub fn new(name: Option<&str>, file: Option<&Path>) -> Result<Self, Error> {
+        let extension = file
+            .and_then(Path::extension)
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        match name.or(extension.as_deref()) {
+            Some("json") | None => Ok(Format::Json),
+            Some("toml") => Ok(Format::Toml),
+            Some("csv") => Ok(Format::Csv),
+            Some(_) if name.is_none() => Ok(Format::Json),
+            Some(name) => Err(Error::Usage(format!("unknown format '{}'", name))),
+        }
+    }
+}
+
+/// Entry to import.
+#[derive(Debug, Default)]
+pub struct Record {
+    pub machine: String,
+    pub fields: Fields,
+}
+
+/// Entries of the document, in their order.
+pub fn export(doc: &NetrcDocument, format: Format) -> String {
+    let records: Vec<Vec<(&str, &str)>> = doc
+        .machines()
+        .map(|m| {
+            let mut fields = vec![("machine", m.name())];
+            for key in &COLUMNS[1..] {
+                fields.push((key, m.get(key).unwrap_or_default()));
+            }
+            fields
+        })
+        .collect();
+    let without_empty = || {
+        records
+            .iter()
+            .map(|fields| {
+                let fields = fields.iter().filter(|(_, value)| !value.is_empty());
+                fields.copied().collect()
+            })
+            .collect::<Vec<_>>()
+    };
+    match format {
+        Format::Json => json::write(&without_empty()),
+        Format::Toml => toml::write(&without_empty()),
+        Format::Csv => {
+            let rows: Vec<Vec<&str>> = records
+                .iter()
+                .map(|fields| fields.iter().map(|(_, value)| *value).collect())
+                .collect();
+            csv::write(&COLUMNS, &rows)
+        }
+    }
+}
+
+/// Parse the records of an input.
+pub fn parse(input: &str, format: Format) -> Result<Vec<Record>, Error> {
+    let records = match format {
+        Format::Json => json::parse(input),
+        Format::Toml => toml::parse(input),
+        Format::Csv => csv::parse(input),
+    }
+    .map_err(|e| Error::Failed(format!("invalid input: {}", e)))?;
+    Ok(records.into_iter().map(record).collect())
+}
+
+/// Record of the fields of an object, a table or a row.
+fn record(fields: Vec<(String, String)>) -> Record {
+    let mut record = Record::default();
+    for (key, value) in fields {
+        if value.is_empty() {
+            continue;
+        }
+        match key.to_lowercase().as_str() {
+            "machine" | "host" | "hostname" => record.machine = value,
+            "url" | "uri" | "login_uri" if record.machine.is_empty() => {
+                record.machine = machine_name(&value)
+            }
+            "login" | "username" | "user" | "login_username" => record.fields.login = Some(value),
+            "account" => record.fields.account = Some(value),
+            "password" | "secret" | "login_password" => record.fields.password = Some(value),
+            _ => {}
+        }
+    }
+    record
+}
+
+/// Machine name of a URL, like `host.com:8080` for
+/// `https://user@host.com:8080/path`.
+fn machine_name(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let url = url.split(['/', '?', '#']).next().unwrap_or_default();
+    let url = url.rsplit_once('@').map_or(url, |(_, host)| host);
+    url.to_owned()
+}
+
+/// Add or update the entries of the records, and return the number of the
+/// records skipped, without machine or without credentials.
+pub fn import(doc: &mut NetrcDocument, records: &[Record]) -> Result<usize, Error> {
+    let mut skipped = 0;
+    for record in records {
+        let fields = &record.fields;
+        let empty = fields.login.is_none() && fields.account.is_none() && fields.password.is_none();
+        match record.machine.is_empty() || empty {
+            true => skipped += 1,
+            false => edit::set(doc, &record.machine, fields)?,
+        }
+    }
+    Ok(skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> NetrcDocument {
+        "\
+machine host.domain.com login log password \"my pass\"
+machine other.com account acc password pass
+default login anonymous password anonymous
+"
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_format() {
+        let format = |name, file: Option<&str>| Format::new(name, file.map(Path::new));
+        assert_eq!(format(None, None).unwrap(), Format::Json);
+        assert_eq!(format(Some("csv"), None).unwrap(), Format::Csv);
+        assert_eq!(
+            format(None, Some("/tmp/export.TOML")).unwrap(),
+            Format::Toml
+        );
+        assert_eq!(format(None, Some("/tmp/export.txt")).unwrap(), Format::Json);
+        assert!(format(Some("yaml"), None).is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        assert_eq!(
+            export(&doc(), Format::Csv),
+            "\
+machine,login,account,password
+host.domain.com,log,,my pass
+other.com,,acc,pass
+default,anonymous,,anonymous
+"
+        );
+        assert_eq!(
+            export(&doc(), Format::Json),
+            r#"[
+  {"machine": "host.domain.com", "login": "log", "password": "my pass"},
+  {"machine": "other.com", "account": "acc", "password": "pass"},
+  {"machine": "default", "login": "anonymous", "password": "anonymous"}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in [Format::Json, Format::Toml, Format::Csv] {
+            let records = parse(&export(&doc(), format), format).unwrap();
+            let mut imported = NetrcDocument::default();
+            assert_eq!(import(&mut imported, &records).unwrap(), 0);
+            assert_eq!(
+                netrc::Netrc::from(&imported),
+                netrc::Netrc::from(&doc()),
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_import() {
+        let input = "\
+folder,name,login_uri,login_username,login_password
+,Host,https://log@host.domain.com/login,log,new pass
+,Api,https://api.domain.com:8443,token,secret
+,Note,,,
+";
+        let records = parse(input, Format::Csv).unwrap();
+        let mut doc = doc();
+        assert_eq!(import(&mut doc, &records).unwrap(), 1);
+
+        let nrc = netrc::Netrc::from(&doc);
+        assert_eq!(nrc.hosts["host.domain.com"].password, "new pass");
+        assert_eq!(nrc.hosts["api.domain.com:8443"].login, "token");
+        assert_eq!(nrc.hosts["other.com"].account, "acc");
+
+        assert!(parse("[{\"machine\": 1", Format::Json).is_err());
+    }
+}
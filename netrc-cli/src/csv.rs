@@ -0,0 +1,135 @@
+//! CSV with a header line, like the exports of the password managers.
+
+/// Parse the rows as the fields named by the header line. The empty lines
+/// are ignored.
+pub fn parse(s: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let mut rows = rows(s)?.into_iter();
+    let header = match rows.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+    rows.enumerate()
+        .map(|(i, row)| {
+            if row.len() > header.len() {
+                return Err(format!("row {}: more fields than in the header", i + 2));
+            }
+            Ok(header.iter().cloned().zip(row).collect())
+        })
+        .collect()
+}
+
+/// Header line of the columns, and a line per row.
+pub fn write(columns: &[&str], rows: &[Vec<&str>]) -> String {
+    let line = |fields: &[&str]| {
+        let fields: Vec<_> = fields.iter().map(|field| quote(field)).collect();
+        fields.join(",") + "\n"
+    };
+    let mut text = line(columns);
+    for row in rows {
+        text.push_str(&line(row));
+    }
+    text
+}
+
+/// Field quoted when necessary.
+fn quote(field: &str) -> String {
+    let special = field.contains([',', '"', '\n', '\r'])
+        || field.starts_with(char::is_whitespace)
+        || field.ends_with(char::is_whitespace);
+    match special {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_owned(),
+    }
+}
+
+/// Fields of the non-empty lines.
+fn rows(s: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = s.strip_prefix('\u{feff}').unwrap_or(s).chars().peekable();
+    let mut lineno = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => match chars.next_if_eq(&'"') {
+                Some(_) => field.push('"'),
+                None => quoted = false,
+            },
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                lineno += 1;
+                field.push(c);
+            }
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                lineno += 1;
+                row.push(std::mem::take(&mut field));
+                match row.len() > 1 || !row[0].is_empty() {
+                    true => rows.push(std::mem::take(&mut row)),
+                    false => row.clear(),
+                }
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("line {}: unterminated quoted field", lineno));
+    }
+    if !row.is_empty() || !field.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let data = "\u{feff}name,url,username,password\r\n\
+                    host,https://host.com,log,\"a,\"\"b\"\"\nc\"\r\n\
+                    \r\n\
+                    empty,,\n\
+                    last,last.com";
+        let rows = parse(data).unwrap();
+        let fields = |row: &Vec<(String, String)>| {
+            row.iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            fields(&rows[0]),
+            [
+                "name=host",
+                "url=https://host.com",
+                "username=log",
+                "password=a,\"b\"\nc"
+            ]
+        );
+        assert_eq!(fields(&rows[1]), ["name=empty", "url=", "username="]);
+        assert_eq!(fields(&rows[2]), ["name=last", "url=last.com"]);
+
+        assert_eq!(parse(""), Ok(vec![]));
+        assert!(parse("a,b\n1,2,3\n").is_err());
+        assert!(parse("a,b\n\"1,2\n").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let text = write(
+            &["machine", "password"],
+            &[vec!["host.com", "a,\"b\""], vec!["default", " pass"]],
+        );
+        assert_eq!(
+            text,
+            "machine,password\nhost.com,\"a,\"\"b\"\"\"\ndefault,\" pass\"\n"
+        );
+        assert_eq!(parse(&text).unwrap()[1][1].1, " pass");
+    }
+}
@@ -0,0 +1,189 @@
+//! Commands editing the netrc file in place, keeping its comments, the order
+//! of the entries and the other entries.
+
+use crate::Error;
+use netrc::{Authenticator, DiscoveryOptions, Netrc, NetrcDocument};
+use std::io::{BufRead, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Fields of an entry to set, `None` for the fields left unchanged.
+#[derive(Debug, Default)]
+pub struct Fields {
+    pub login: Option<String>,
+    pub account: Option<String>,
+    pub password: Option<String>,
+}
+
+/// File of the `-f` option, or the netrc file of the user, created if it
+/// does not exist.
+pub fn file(file: Option<PathBuf>) -> Result<PathBuf, Error> {
+    let options = DiscoveryOptions {
+        require_exists: false,
+        ..Default::default()
+    };
+    file.or_else(|| Netrc::discover(&options))
+        .ok_or_else(|| Error::Failed(String::from("no netrc file found")))
+}
+
+/// Document of a netrc file, empty if it does not exist.
+pub fn document(file: &Path) -> Result<NetrcDocument, Error> {
+    match NetrcDocument::from_file(file) {
+        Err(netrc::Error::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(NetrcDocument::default()),
+        res => Ok(res?),
+    }
+}
+
+/// Read the value `-` of a field from the first line of `input`, so that a
+/// password is not visible in the list of the processes.
+pub fn read_value(value: Option<String>, input: &mut dyn BufRead) -> Result<Option<String>, Error> {
+    match value.as_deref() {
+        Some("-") => {
+            let mut line = String::new();
+            input.read_line(&mut line)?;
+            Ok(Some(line.trim_end_matches(['\n', '\r']).to_owned()))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Add the entry of a host, or update the given fields of an existing one.
+pub fn set(doc: &mut NetrcDocument, host: &str, fields: &Fields) -> Result<(), Error> {
+    let values = [
+        ("login", &fields.login),
+        ("account", &fields.account),
+        ("password", &fields.password),
+    ];
+    if values.iter().all(|(_, value)| value.is_none()) {
+        return Err(Error::Usage(String::from(
+            "missing --login, --account or --password",
+        )));
+    }
+    match doc.machine_mut(host) {
+        Some(entry) => {
+            for (key, value) in values {
+                if let Some(value) = value {
+                    entry.set(key, value);
+                }
+            }
+        }
+        None => {
+            let value = |v: &Option<String>| v.clone().unwrap_or_default();
+            let auth = Authenticator::new(
+                &value(&fields.login),
+                &value(&fields.account),
+                &value(&fields.password),
+            );
+            doc.insert(host, &auth);
+        }
+    }
+    Ok(())
+}
+
+/// Add the entry of a host, which must not exist.
+pub fn add(doc: &mut NetrcDocument, host: &str, fields: &Fields) -> Result<(), Error> {
+    if doc.machine(host).is_some() {
+        return Err(Error::Failed(format!(
+            "'{}' already has an entry, use `netrc set` to update it",
+            host
+        )));
+    }
+    set(doc, host, fields)
+}
+
+/// Remove the entries of a host.
+pub fn delete(doc: &mut NetrcDocument, host: &str) -> Result<(), Error> {
+    match doc.remove(host) {
+        Some(_) => Ok(()),
+        None => Err(Error::Failed(format!("no entry for '{}'", host))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> NetrcDocument {
+        "\
+# work
+machine host.domain.com login log password pass # main server
+
+machine other.com login other password pass
+"
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_set() {
+        let mut doc = doc();
+        let fields = Fields {
+            password: Some(String::from("new pass")),
+            ..Default::default()
+        };
+        set(&mut doc, "host.domain.com", &fields).unwrap();
+        let fields = Fields {
+            login: Some(String::from("log")),
+            password: Some(String::from("pass")),
+            ..Default::default()
+        };
+        set(&mut doc, "new.com", &fields).unwrap();
+        assert!(matches!(
+            set(&mut doc, "new.com", &Fields::default()),
+            Err(Error::Usage(_))
+        ));
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+# work
+machine host.domain.com login log password \"new pass\" # main server
+
+machine other.com login other password pass
+machine new.com
+\tlogin log
+\tpassword pass
+"
+        );
+    }
+
+    #[test]
+    fn test_add() {
+        let mut doc = doc();
+        let fields = Fields {
+            login: Some(String::from("log")),
+            password: Some(String::from("pass")),
+            ..Default::default()
+        };
+        assert!(add(&mut doc, "host.domain.com", &fields).is_err());
+        add(&mut doc, "new.com", &fields).unwrap();
+        assert_eq!(
+            doc.machine("new.com").unwrap().get("password"),
+            Some("pass")
+        );
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut doc = doc();
+        delete(&mut doc, "other.com").unwrap();
+        assert!(delete(&mut doc, "other.com").is_err());
+        assert_eq!(
+            doc.to_string(),
+            "# work\nmachine host.domain.com login log password pass # main server\n\n"
+        );
+    }
+
+    #[test]
+    fn test_read_value() {
+        let mut input = "secret\nother\n".as_bytes();
+        assert_eq!(
+            read_value(Some(String::from("-")), &mut input).unwrap(),
+            Some(String::from("secret"))
+        );
+        assert_eq!(
+            read_value(Some(String::from("pass")), &mut input).unwrap(),
+            Some(String::from("pass"))
+        );
+        assert_eq!(read_value(None, &mut input).unwrap(), None);
+    }
+}
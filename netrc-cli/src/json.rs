@@ -0,0 +1,237 @@
+//! JSON array of flat objects, like
+//! `[{"machine": "host.com", "login": "log", "password": "pass"}]`.
+
+use std::fmt::Write;
+
+/// Parse an array of objects. The numbers are kept as text, and the `null`
+/// and boolean values are ignored.
+pub fn parse(s: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let mut parser = Parser {
+        chars: s.chars().peekable(),
+    };
+    let mut objects = Vec::new();
+    parser.expect('[')?;
+    if parser.peek() == Some(']') {
+        parser.next();
+    } else {
+        loop {
+            objects.push(parser.object()?);
+            match parser.next() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => return Err(String::from("expected ',' or ']'")),
+            }
+        }
+    }
+    match parser.peek() {
+        None => Ok(objects),
+        Some(_) => Err(String::from("unexpected characters after the array")),
+    }
+}
+
+/// Array of objects, with an object per line.
+pub fn write(objects: &[Vec<(&str, &str)>]) -> String {
+    let objects: Vec<_> = objects
+        .iter()
+        .map(|fields| {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(key, value)| format!("{}: {}", string(key), string(value)))
+                .collect();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect();
+    match objects.is_empty() {
+        true => String::from("[]\n"),
+        false => format!("[\n{}\n]\n", objects.join(",\n")),
+    }
+}
+
+/// Quoted and escaped string.
+fn string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(res, "\\u{:04x}", c as u32);
+            }
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    /// Next character, after the whitespaces.
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.peek()?;
+        self.chars.next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(format!("expected '{}'", expected)),
+        }
+    }
+
+    fn object(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut fields = Vec::new();
+        self.expect('{')?;
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(fields);
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(':')?;
+            if let Some(value) = self.value()? {
+                fields.push((key, value));
+            }
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(fields),
+                _ => return Err(String::from("expected ',' or '}'")),
+            }
+        }
+    }
+
+    /// String or number value, `None` for the other literals.
+    fn value(&mut self) -> Result<Option<String>, String> {
+        match self.peek() {
+            Some('"') => self.string().map(Some),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    number.push(c);
+                }
+                Ok(Some(number))
+            }
+            _ => {
+                let mut literal = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+                    literal.push(c);
+                }
+                match literal.as_str() {
+                    "null" | "true" | "false" => Ok(None),
+                    _ => Err(String::from("expected a string")),
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut res = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(res),
+                Some('\\') => match self.chars.next() {
+                    Some('"') => res.push('"'),
+                    Some('\\') => res.push('\\'),
+                    Some('/') => res.push('/'),
+                    Some('b') => res.push('\u{8}'),
+                    Some('f') => res.push('\u{c}'),
+                    Some('n') => res.push('\n'),
+                    Some('r') => res.push('\r'),
+                    Some('t') => res.push('\t'),
+                    Some('u') => res.push(self.unicode()?),
+                    _ => return Err(String::from("invalid escape sequence")),
+                },
+                Some(c) => res.push(c),
+                None => return Err(String::from("unterminated string")),
+            }
+        }
+    }
+
+    /// Character of a `\uXXXX` sequence, or of a surrogate pair.
+    fn unicode(&mut self) -> Result<char, String> {
+        let high = self.hex()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                    return Err(String::from("invalid surrogate pair"));
+                }
+                let low = self.hex()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(String::from("invalid surrogate pair"));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| String::from("invalid unicode escape"))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.by_ref().take(4).collect();
+        match digits.len() {
+            4 => {
+                u32::from_str_radix(&digits, 16).map_err(|_| String::from("invalid unicode escape"))
+            }
+            _ => Err(String::from("invalid unicode escape")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(
+                r#" [ {"machine": "host.com", "port": 8080, "login": null, "password": "a\"bé"},
+                      {} ] "#
+            ),
+            Ok(vec![
+                vec![
+                    (String::from("machine"), String::from("host.com")),
+                    (String::from("port"), String::from("8080")),
+                    (String::from("password"), String::from("a\"bé")),
+                ],
+                vec![],
+            ])
+        );
+        assert_eq!(parse("[]"), Ok(vec![]));
+        assert!(parse(r#"{"machine": "host.com"}"#).is_err());
+        assert!(parse(r#"[{"machine": ["host.com"]}]"#).is_err());
+        assert!(parse(r#"[{"machine": "host.com"}"#).is_err());
+        assert!(parse(r#"[{"machine": "host.com}]"#).is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let objects = vec![
+            vec![("machine", "host.com"), ("password", "a\"b\n")],
+            vec![("machine", "default")],
+        ];
+        let text = write(&objects);
+        assert_eq!(
+            text,
+            "[\n  {\"machine\": \"host.com\", \"password\": \"a\\\"b\\n\"},\n  {\"machine\": \"default\"}\n]\n"
+        );
+        assert_eq!(parse(&text).unwrap().len(), 2);
+        assert_eq!(write(&[]), "[]\n");
+    }
+}
@@ -0,0 +1,539 @@
+//! Command line tool to read and edit the netrc file.
+//!
+//! ```text
+//! $ netrc list
+//! $ netrc get api.domain.com --field login
+//! $ eval "$(netrc env api.domain.com)"
+//! $ echo "$TOKEN" | netrc set api.domain.com --login token --password -
+//! ```
+//!
+//! The netrc file is found like [`Netrc::new`] does, unless it is given with
+//! `-f <file>`. The edited file is the first one of the `NETRC` variable, or
+//! `~/.netrc`.
+
+use args::Args;
+use convert::Format;
+use edit::Fields;
+use migrate::Keyring;
+use netrc::{Layout, Netrc, NetrcDocument};
+use prompt::Prompt;
+use std::io::{self, IsTerminal, Read, Write};
+use std::net::Ipv6Addr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+mod args;
+mod check;
+mod convert;
+mod csv;
+mod edit;
+mod json;
+mod migrate;
+mod prompt;
+mod toml;
+
+const USAGE: &str = "\
+usage: netrc [-f <file>] <command> [<args>]
+
+commands:
+    list                                list the machines, without the secrets
+    env <host> [--prefix <prefix>]      print the variables NETRC_LOGIN, NETRC_ACCOUNT
+                                        and NETRC_PASSWORD of a host, for `eval`
+    get <host> [--field <field>]        print a field of the entry of a host
+                                        (login, account or password, the default)
+    to-curl <host> [--bearer]           print the credentials of a host as a curl
+                                        config, with the password as Bearer token
+                                        with --bearer
+    set <host> [--login <login>] [--account <account>] [--password <password>]
+                                        add or update the entry of a host, with
+                                        the values `-` read from the standard input
+    add <host> [--login <login>] [--account <account>] [--password <password>]
+    add --interactive [<host>]          add the entry of a new host, with the
+                                        values asked, and the password not echoed
+    delete <host>                       remove the entry of a host
+    check                               report the problems of the file, and
+                                        fail if there are any
+    fmt [--one-line] [--check | --write]
+                                        print the file in a canonical layout,
+                                        fail if it is not formatted, or write it
+    migrate --to keyring [--remove]     move the passwords to the keyring of the system,
+                                        replaced by `keyring`, or with their entries
+                                        removed
+    migrate --from keyring [--remove] [<host>...]
+                                        write back the passwords of the keyring,
+                                        and add the entries of the hosts
+    export [--format <format>]          print the entries as JSON, TOML or CSV
+    import [--format <format>] [<file>] add or update the entries of a JSON, TOML
+                                        or CSV file, or of the standard input
+";
+
+/// Error of a command.
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid command line.
+    Usage(String),
+
+    /// Failure of the command.
+    Failed(String),
+}
+
+impl From<netrc::Error> for Error {
+    fn from(e: netrc::Error) -> Self {
+        Error::Failed(e.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Failed(e.to_string())
+    }
+}
+
+/// Netrc of the `-f` option, or the netrc found like [`Netrc::new`].
+fn load(file: &Option<PathBuf>) -> Result<Netrc, Error> {
+    Ok(match file {
+        Some(file) => Netrc::from_file(file)?,
+        None => Netrc::new()?,
+    })
+}
+
+/// File of the `-f` option, or the netrc file found like [`Netrc::new`].
+fn existing_file(file: Option<PathBuf>) -> Result<PathBuf, Error> {
+    file.or_else(Netrc::get_file)
+        .ok_or_else(|| Error::Failed(String::from("no netrc file found")))
+}
+
+/// Host and port of a machine name, like `host.domain.com:8080` or
+/// `[::1]:8080`, split like [`netrc::normalize_host`] does: the port of an
+/// IPv6 address follows its brackets.
+fn host_port(name: &str) -> (&str, Option<u16>) {
+    if name.parse::<Ipv6Addr>().is_ok() {
+        return (name, None);
+    }
+    if let Some((addr, rest)) = name.strip_prefix('[').and_then(|n| n.split_once(']')) {
+        return match rest.strip_prefix(':').map(str::parse) {
+            Some(Ok(port)) => (addr, Some(port)),
+            None if rest.is_empty() => (addr, None),
+            _ => (name, None),
+        };
+    }
+    match name.rsplit_once(':') {
+        Some((host, port)) => match port.parse() {
+            Ok(port) => (host, Some(port)),
+            Err(_) => (name, None),
+        },
+        None => (name, None),
+    }
+}
+
+/// Print the names of the machines, sorted, with the `default` entry last.
+fn list(nrc: &Netrc, out: &mut dyn Write) -> Result<(), Error> {
+    let mut names: Vec<_> = nrc.hosts.keys().collect();
+    names.sort();
+    for name in names {
+        writeln!(out, "{}", name)?;
+    }
+    if nrc.default_entry.is_some() {
+        writeln!(out, "default")?;
+    }
+    Ok(())
+}
+
+/// Print a field of the entry of a host, found like [`Netrc::resolve`].
+fn get(nrc: &Netrc, host: &str, field: &str, out: &mut dyn Write) -> Result<(), Error> {
+    let (name, port) = host_port(host);
+    let auth = nrc
+        .resolve(name, port)
+        .ok_or_else(|| Error::Failed(format!("no entry for '{}'", host)))?;
+    let value = match field {
+        "login" => &auth.login,
+        "account" => &auth.account,
+        "password" => &auth.password,
+        _ => return Err(Error::Usage(format!("unknown field '{}'", field))),
+    };
+    writeln!(out, "{}", value)?;
+    Ok(())
+}
+
+/// Quote a value for the POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Print the assignments of the login, the account and the password of a
+/// host, found like [`Netrc::resolve`], to evaluate in a shell.
+fn env(nrc: &Netrc, host: &str, prefix: &str, out: &mut dyn Write) -> Result<(), Error> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if !prefix.chars().all(valid) || prefix.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(Error::Usage(format!("invalid prefix '{}'", prefix)));
+    }
+    let (name, port) = host_port(host);
+    let auth = nrc
+        .resolve(name, port)
+        .ok_or_else(|| Error::Failed(format!("no entry for '{}'", host)))?;
+    for (key, value) in [
+        ("LOGIN", &auth.login),
+        ("ACCOUNT", &auth.account),
+        ("PASSWORD", &auth.password),
+    ] {
+        writeln!(out, "{}{}={}", prefix, key, shell_quote(value))?;
+    }
+    Ok(())
+}
+
+/// Quote a value of a curl config file.
+fn curl_quote(value: &str) -> String {
+    let mut res = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// Print the credentials of a host, found like [`Netrc::resolve`], as a
+/// curl config to use with `curl --config`.
+fn to_curl(nrc: &Netrc, host: &str, bearer: bool, out: &mut dyn Write) -> Result<(), Error> {
+    let (name, port) = host_port(host);
+    let auth = nrc
+        .resolve(name, port)
+        .ok_or_else(|| Error::Failed(format!("no entry for '{}'", host)))?;
+    match bearer {
+        true => {
+            let header = format!("Authorization: Bearer {}", auth.password);
+            writeln!(out, "header = {}", curl_quote(&header))?;
+        }
+        false => {
+            let user = format!("{}:{}", auth.login, auth.password);
+            writeln!(out, "user = {}", curl_quote(&user))?;
+        }
+    }
+    Ok(())
+}
+
+fn run(mut args: Args) -> Result<(), Error> {
+    let file = args.value(&["-f", "--file"])?.map(PathBuf::from);
+    let command = args.required("command")?;
+    let mut out = io::stdout().lock();
+    match command.as_str() {
+        "list" => {
+            args.finish()?;
+            list(&load(&file)?, &mut out)
+        }
+        "get" => {
+            let field = args.value(&["--field"])?;
+            let host = args.required("host")?;
+            args.finish()?;
+            get(
+                &load(&file)?,
+                &host,
+                field.as_deref().unwrap_or("password"),
+                &mut out,
+            )
+        }
+        "env" => {
+            let prefix = args.value(&["--prefix"])?;
+            let host = args.required("host")?;
+            args.finish()?;
+            env(
+                &load(&file)?,
+                &host,
+                prefix.as_deref().unwrap_or("NETRC_"),
+                &mut out,
+            )
+        }
+        "to-curl" => {
+            let bearer = args.flag(&["--bearer"]);
+            let host = args.required("host")?;
+            args.finish()?;
+            to_curl(&load(&file)?, &host, bearer, &mut out)
+        }
+        "set" => {
+            let mut input = io::stdin().lock();
+            let fields = Fields {
+                login: args.value(&["--login"])?,
+                account: args.value(&["--account"])?,
+                password: edit::read_value(args.value(&["--password"])?, &mut input)?,
+            };
+            let host = args.required("host")?;
+            args.finish()?;
+            let file = edit::file(file)?;
+            let mut doc = edit::document(&file)?;
+            edit::set(&mut doc, &host, &fields)?;
+            Ok(doc.save(&file)?)
+        }
+        "add" => {
+            let interactive = args.flag(&["-i", "--interactive"]);
+            let mut fields = Fields::default();
+            if !interactive {
+                let mut input = io::stdin().lock();
+                fields = Fields {
+                    login: args.value(&["--login"])?,
+                    account: args.value(&["--account"])?,
+                    password: edit::read_value(args.value(&["--password"])?, &mut input)?,
+                };
+            }
+            let host = args.positional();
+            args.finish()?;
+            let (host, fields) = match interactive {
+                true => {
+                    let stdin = io::stdin();
+                    let tty = stdin.is_terminal();
+                    Prompt::new(stdin.lock(), io::stderr(), tty).entry(host)?
+                }
+                false => (
+                    host.ok_or_else(|| Error::Usage(String::from("missing argument <host>")))?,
+                    fields,
+                ),
+            };
+            let file = edit::file(file)?;
+            let mut doc = edit::document(&file)?;
+            edit::add(&mut doc, &host, &fields)?;
+            Ok(doc.save(&file)?)
+        }
+        "delete" => {
+            let host = args.required("host")?;
+            args.finish()?;
+            let file = edit::file(file)?;
+            let mut doc = edit::document(&file)?;
+            edit::delete(&mut doc, &host)?;
+            Ok(doc.save(&file)?)
+        }
+        "check" => {
+            args.finish()?;
+            let file = existing_file(file)?;
+            let problems = check::check(&file)?;
+            for problem in problems.iter() {
+                writeln!(out, "{}: {}", file.display(), problem)?;
+            }
+            match problems.len() {
+                0 => Ok(()),
+                n => Err(Error::Failed(format!("{} problem(s) found", n))),
+            }
+        }
+        "fmt" => {
+            let layout = match args.flag(&["--one-line"]) {
+                true => Layout::OneLine,
+                false => Layout::MultiLine,
+            };
+            let check = args.flag(&["--check"]);
+            let write = args.flag(&["-w", "--write"]);
+            args.finish()?;
+            let file = existing_file(file)?;
+            let mut doc = NetrcDocument::from_file(&file)?;
+            let text = doc.to_string();
+            doc.reformat(layout);
+            let changed = doc.to_string() != text;
+            match (check, write) {
+                (true, true) => Err(Error::Usage(String::from(
+                    "--check and --write are exclusive",
+                ))),
+                (true, false) if changed => Err(Error::Failed(format!(
+                    "{} is not formatted",
+                    file.display()
+                ))),
+                (false, true) if changed => Ok(doc.save(&file)?),
+                (false, false) => Ok(write!(out, "{}", doc)?),
+                _ => Ok(()),
+            }
+        }
+        "migrate" => {
+            let to = args.value(&["--to"])?;
+            let from = args.value(&["--from"])?;
+            let remove = args.flag(&["--remove"]);
+            let hosts: Vec<_> = std::iter::from_fn(|| args.positional()).collect();
+            args.finish()?;
+            let file = existing_file(file)?;
+            let mut doc = NetrcDocument::from_file(&file)?;
+            let mut keyring = migrate::System;
+            match (to.as_deref(), from.as_deref()) {
+                (Some("keyring"), None) if hosts.is_empty() => {
+                    let names = migrate::to_keyring(&mut doc, &mut keyring, remove)?;
+                    doc.save(&file)?;
+                    eprintln!("netrc: {} password(s) moved to the keyring", names.len());
+                    Ok(())
+                }
+                (None, Some("keyring")) => {
+                    let found = migrate::from_keyring(&mut doc, &keyring, &hosts)?;
+                    doc.save(&file)?;
+                    // The passwords are deleted once written in the file.
+                    if remove {
+                        for (host, port, login) in found.iter() {
+                            keyring.delete(host, *port, login)?;
+                        }
+                    }
+                    eprintln!("netrc: {} password(s) moved from the keyring", found.len());
+                    Ok(())
+                }
+                _ => Err(Error::Usage(String::from(
+                    "expected --to keyring or --from keyring",
+                ))),
+            }
+        }
+        "export" => {
+            let format = args.value(&["--format"])?;
+            args.finish()?;
+            let format = Format::new(format.as_deref(), None)?;
+            let doc = NetrcDocument::from_file(&existing_file(file)?)?;
+            Ok(write!(out, "{}", convert::export(&doc, format))?)
+        }
+        "import" => {
+            let format = args.value(&["--format"])?;
+            let input = args.positional().filter(|input| input != "-");
+            args.finish()?;
+            let input = input.map(PathBuf::from);
+            let format = Format::new(format.as_deref(), input.as_deref())?;
+            let text = match &input {
+                Some(input) => std::fs::read_to_string(input)?,
+                None => {
+                    let mut text = String::new();
+                    io::stdin().read_to_string(&mut text)?;
+                    text
+                }
+            };
+            let records = convert::parse(&text, format)?;
+            let file = edit::file(file)?;
+            let mut doc = edit::document(&file)?;
+            let skipped = convert::import(&mut doc, &records)?;
+            if skipped > 0 {
+                eprintln!(
+                    "netrc: {} record(s) without machine or credentials skipped",
+                    skipped
+                );
+            }
+            Ok(doc.save(&file)?)
+        }
+        _ => Err(Error::Usage(format!("unknown command '{}'", command))),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = Args::new(std::env::args().skip(1));
+    if args.flag(&["-h", "--help"]) {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Error::Usage(message)) => {
+            eprint!("netrc: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(Error::Failed(message)) => {
+            eprintln!("netrc: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nrc() -> Netrc {
+        "\
+        default login anonymous password anonymous
+        machine host.domain.com login log account acc password pass
+        machine host.domain.com:8080 login port password pass
+        machine api.domain.com login token password secret
+        "
+        .parse()
+        .unwrap()
+    }
+
+    fn output(f: impl FnOnce(&mut dyn Write) -> Result<(), Error>) -> Result<String, Error> {
+        let mut out = Vec::new();
+        f(&mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_list() {
+        assert_eq!(
+            output(|out| list(&nrc(), out)).unwrap(),
+            "api.domain.com\nhost.domain.com\nhost.domain.com:8080\ndefault\n"
+        );
+    }
+
+    #[test]
+    fn test_host_port() {
+        assert_eq!(host_port("host.domain.com"), ("host.domain.com", None));
+        assert_eq!(
+            host_port("host.domain.com:8080"),
+            ("host.domain.com", Some(8080))
+        );
+        assert_eq!(host_port("::1"), ("::1", None));
+        assert_eq!(host_port("fe80::1"), ("fe80::1", None));
+        assert_eq!(host_port("[::1]"), ("::1", None));
+        assert_eq!(host_port("[::1]:8080"), ("::1", Some(8080)));
+        assert_eq!(host_port("[::1]:port"), ("[::1]:port", None));
+
+        let nrc: Netrc = "machine ::1 login log1\nmachine [::1]:8080 login log2"
+            .parse()
+            .unwrap();
+        let get = |host: &str| output(|out| get(&nrc, host, "login", out));
+        assert_eq!(get("::1").unwrap(), "log1\n");
+        assert_eq!(get("[::1]:8080").unwrap(), "log2\n");
+    }
+
+    #[test]
+    fn test_get() {
+        let nrc = nrc();
+        let get = |host: &str, field: &str| output(|out| get(&nrc, host, field, out));
+
+        assert_eq!(get("api.domain.com", "password").unwrap(), "secret\n");
+        assert_eq!(get("host.domain.com", "account").unwrap(), "acc\n");
+        assert_eq!(get("host.domain.com:8080", "login").unwrap(), "port\n");
+        assert_eq!(get("other.com", "login").unwrap(), "anonymous\n");
+        assert!(matches!(
+            get("api.domain.com", "other"),
+            Err(Error::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_curl() {
+        let nrc: Netrc = "machine host.domain.com login log password \"a\\\"b\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            output(|out| to_curl(&nrc, "host.domain.com", false, out)).unwrap(),
+            "user = \"log:a\\\"b\"\n"
+        );
+        assert_eq!(
+            output(|out| to_curl(&nrc, "host.domain.com", true, out)).unwrap(),
+            "header = \"Authorization: Bearer a\\\"b\"\n"
+        );
+        assert!(output(|out| to_curl(&nrc, "other.com", false, out)).is_err());
+    }
+
+    #[test]
+    fn test_env() {
+        let nrc: Netrc = "machine host.domain.com login log password \"it's $HOME\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            output(|out| env(&nrc, "host.domain.com", "NETRC_", out)).unwrap(),
+            "NETRC_LOGIN='log'\nNETRC_ACCOUNT=''\nNETRC_PASSWORD='it'\\''s $HOME'\n"
+        );
+        assert_eq!(
+            output(|out| env(&nrc, "host.domain.com", "API_", out)).unwrap(),
+            "API_LOGIN='log'\nAPI_ACCOUNT=''\nAPI_PASSWORD='it'\\''s $HOME'\n"
+        );
+        assert!(matches!(
+            output(|out| env(&nrc, "host.domain.com", "A-", out)),
+            Err(Error::Usage(_))
+        ));
+        assert!(matches!(
+            output(|out| env(&nrc, "other.com", "NETRC_", out)),
+            Err(Error::Failed(_))
+        ));
+    }
+}
@@ -0,0 +1,271 @@
+//! Migration of the passwords between the netrc file and the keyring of the
+//! system (the macOS Keychain or the freedesktop Secret Service).
+
+use crate::{host_port, Error};
+use netrc::{Authenticator, NetrcDocument, KEYCHAIN_PASSWORD, KEYRING_PASSWORD};
+
+/// Storage of the passwords.
+pub trait Keyring {
+    fn store(&mut self, host: &str, port: Option<u16>, auth: &Authenticator) -> Result<(), Error>;
+    fn password(&self, host: &str, port: Option<u16>, login: &str) -> Option<String>;
+    fn find(&self, host: &str, port: Option<u16>) -> Option<Authenticator>;
+    fn delete(&mut self, host: &str, port: Option<u16>, login: &str) -> Result<(), Error>;
+}
+
+/// Keyring of the system.
+///
+/// The passwords are written to the standard input of the commands of the
+/// backends, never to their arguments which other users can list.
+pub struct System;
+
+impl Keyring for System {
+    fn store(&mut self, host: &str, port: Option<u16>, auth: &Authenticator) -> Result<(), Error> {
+        Ok(netrc::keyring::store(host, port, auth)?)
+    }
+
+    fn password(&self, host: &str, port: Option<u16>, login: &str) -> Option<String> {
+        netrc::keyring::password(host, port, login)
+    }
+
+    fn find(&self, host: &str, port: Option<u16>) -> Option<Authenticator> {
+        netrc::keyring::find(host, port)
+    }
+
+    fn delete(&mut self, host: &str, port: Option<u16>, login: &str) -> Result<(), Error> {
+        Ok(netrc::keyring::delete(host, port, login)?)
+    }
+}
+
+/// Whether a password refers to a backend of [`netrc::Netrc::resolve`]
+/// instead of being the secret.
+fn is_reference(password: &str) -> bool {
+    password == KEYRING_PASSWORD
+        || password == KEYCHAIN_PASSWORD
+        || password.starts_with("pass:")
+        || password.starts_with("op://")
+}
+
+/// Store the passwords of the machine entries in the keyring, and replace
+/// them by [`KEYRING_PASSWORD`], or remove the entries with `remove` if they
+/// have only a login and a password. Return the names of the machines
+/// migrated.
+///
+/// The `default` entry, the empty passwords and the references to the other
+/// backends are left unchanged.
+pub fn to_keyring(
+    doc: &mut NetrcDocument,
+    keyring: &mut dyn Keyring,
+    remove: bool,
+) -> Result<Vec<String>, Error> {
+    let mut names: Vec<String> = Vec::new();
+    for entry in doc.machines().filter(|m| !m.is_default()) {
+        let password = entry.get("password").unwrap_or_default();
+        if password.is_empty() || is_reference(password) {
+            continue;
+        }
+        if names.iter().any(|name| name == entry.name()) {
+            return Err(Error::Failed(format!(
+                "machine '{}' is defined several times",
+                entry.name()
+            )));
+        }
+        names.push(entry.name().to_owned());
+    }
+
+    for name in names.iter() {
+        let auth = doc
+            .machine(name)
+            .map(|m| m.authenticator())
+            .unwrap_or_default();
+        let (host, port) = host_port(name);
+        keyring.store(host, port, &auth)?;
+        let only_password = auth.account.is_empty()
+            && auth.port.is_empty()
+            && auth.protocol.is_empty()
+            && auth.extras.is_empty();
+        match remove && only_password {
+            true => {
+                doc.remove(name);
+            }
+            false => {
+                if let Some(entry) = doc.machine_mut(name) {
+                    entry.set("password", KEYRING_PASSWORD);
+                }
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Write back the passwords of the entries referring to the keyring, and
+/// add the entries of the given hosts found in the keyring. Return the
+/// hosts, ports and logins of the passwords written.
+pub fn from_keyring(
+    doc: &mut NetrcDocument,
+    keyring: &dyn Keyring,
+    hosts: &[String],
+) -> Result<Vec<(String, Option<u16>, String)>, Error> {
+    let mut found = Vec::new();
+    let names: Vec<String> = doc
+        .machines()
+        .filter(|m| !m.is_default() && m.get("password") == Some(KEYRING_PASSWORD))
+        .map(|m| m.name().to_owned())
+        .collect();
+    for name in names {
+        let (host, port) = host_port(&name);
+        let Some(entry) = doc.machine_mut(&name) else {
+            continue;
+        };
+        let login = entry.get("login").unwrap_or_default().to_owned();
+        let password = keyring
+            .password(host, port, &login)
+            .ok_or_else(|| Error::Failed(format!("no password of '{}' in the keyring", name)))?;
+        entry.set("password", &password);
+        found.push((host.to_owned(), port, login));
+    }
+
+    for name in hosts {
+        if doc.machine(name).is_some() {
+            continue;
+        }
+        let (host, port) = host_port(name);
+        let auth = keyring
+            .find(host, port)
+            .ok_or_else(|| Error::Failed(format!("no password of '{}' in the keyring", name)))?;
+        doc.insert(name, &auth);
+        found.push((host.to_owned(), port, auth.login.clone()));
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keyring in memory, with the entries as netrc machines.
+    #[derive(Default)]
+    struct Memory(Vec<(String, Authenticator)>);
+
+    impl Memory {
+        fn name(host: &str, port: Option<u16>) -> String {
+            match port {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_owned(),
+            }
+        }
+    }
+
+    impl Keyring for Memory {
+        fn store(
+            &mut self,
+            host: &str,
+            port: Option<u16>,
+            auth: &Authenticator,
+        ) -> Result<(), Error> {
+            let login = auth.login.clone();
+            self.delete(host, port, &login)?;
+            let auth = Authenticator::new(&auth.login, "", &auth.password);
+            self.0.push((Self::name(host, port), auth));
+            Ok(())
+        }
+
+        fn password(&self, host: &str, port: Option<u16>, login: &str) -> Option<String> {
+            let name = Self::name(host, port);
+            self.0
+                .iter()
+                .find(|(n, auth)| *n == name && auth.login == login)
+                .map(|(_, auth)| auth.password.clone())
+        }
+
+        fn find(&self, host: &str, port: Option<u16>) -> Option<Authenticator> {
+            let name = Self::name(host, port);
+            self.0
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, auth)| auth.clone())
+        }
+
+        fn delete(&mut self, host: &str, port: Option<u16>, login: &str) -> Result<(), Error> {
+            let name = Self::name(host, port);
+            self.0.retain(|(n, auth)| *n != name || auth.login != login);
+            Ok(())
+        }
+    }
+
+    fn document() -> NetrcDocument {
+        "\
+machine host.domain.com login log password pass
+machine api.domain.com:8443 login token account acc password secret
+machine ftp.domain.com login anonymous
+machine other.com login other password pass:other
+default login anonymous password anonymous
+"
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_to_keyring() {
+        let mut keyring = Memory::default();
+        let mut doc = document();
+        assert_eq!(
+            to_keyring(&mut doc, &mut keyring, false).unwrap(),
+            ["host.domain.com", "api.domain.com:8443"]
+        );
+        assert_eq!(
+            doc.to_string(),
+            "\
+machine host.domain.com login log password keyring
+machine api.domain.com:8443 login token account acc password keyring
+machine ftp.domain.com login anonymous
+machine other.com login other password pass:other
+default login anonymous password anonymous
+"
+        );
+        assert_eq!(
+            keyring.password("api.domain.com", Some(8443), "token"),
+            Some(String::from("secret"))
+        );
+        assert!(to_keyring(&mut doc, &mut keyring, false)
+            .unwrap()
+            .is_empty());
+
+        let mut doc = document();
+        to_keyring(&mut doc, &mut keyring, true).unwrap();
+        assert!(doc.machine("host.domain.com").is_none());
+        assert_eq!(
+            doc.machine("api.domain.com:8443").unwrap().get("password"),
+            Some(KEYRING_PASSWORD)
+        );
+
+        let mut doc: NetrcDocument = "machine a.com password a\nmachine a.com password b\n"
+            .parse()
+            .unwrap();
+        assert!(to_keyring(&mut doc, &mut keyring, false).is_err());
+    }
+
+    #[test]
+    fn test_from_keyring() {
+        let mut keyring = Memory::default();
+        let mut doc = document();
+        to_keyring(&mut doc, &mut keyring, true).unwrap();
+
+        let found = from_keyring(&mut doc, &keyring, &[String::from("host.domain.com")]).unwrap();
+        assert_eq!(
+            found,
+            [
+                (
+                    String::from("api.domain.com"),
+                    Some(8443),
+                    String::from("token")
+                ),
+                (String::from("host.domain.com"), None, String::from("log")),
+            ]
+        );
+        let nrc = netrc::Netrc::from(&doc);
+        assert_eq!(nrc.hosts["api.domain.com:8443"].password, "secret");
+        assert_eq!(nrc.hosts["host.domain.com"].password, "pass");
+
+        assert!(from_keyring(&mut doc, &keyring, &[String::from("new.com")]).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Interactive entry of the credentials of a host.
+
+use crate::edit::Fields;
+use crate::Error;
+use std::io::{BufRead, Write};
+
+/// Questions asked on `output`, answered on `input`.
+pub struct Prompt<I, O> {
+    input: I,
+    output: O,
+    /// The input is the terminal, whose echo is disabled for the passwords.
+    tty: bool,
+}
+
+impl<I: BufRead, O: Write> Prompt<I, O> {
+    pub fn new(input: I, output: O, tty: bool) -> Self {
+        Prompt { input, output, tty }
+    }
+
+    /// Answer of a question, without the end of line.
+    pub fn ask(&mut self, question: &str) -> Result<String, Error> {
+        write!(self.output, "{}: ", question)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(Error::Failed(String::from("unexpected end of input")));
+        }
+        Ok(line.trim_end_matches(['\n', '\r']).to_owned())
+    }
+
+    /// Answer of a question, not echoed by the terminal.
+    pub fn ask_hidden(&mut self, question: &str) -> Result<String, Error> {
+        let _guard = match self.tty {
+            true => NoEcho::new(),
+            false => None,
+        };
+        self.ask(question)
+    }
+
+    /// Host and fields of a new entry, with the password asked twice.
+    pub fn entry(&mut self, host: Option<String>) -> Result<(String, Fields), Error> {
+        let host = match host {
+            Some(host) => host,
+            None => self.ask("Host")?.trim().to_owned(),
+        };
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(Error::Failed(format!("invalid host '{}'", host)));
+        }
+        let login = self.ask("Login")?;
+        let account = self.ask("Account (optional)")?;
+        let password = self.ask_hidden("Password")?;
+        if password.is_empty() && login != "anonymous" {
+            return Err(Error::Failed(String::from("empty password")));
+        }
+        if self.ask_hidden("Confirm password")? != password {
+            return Err(Error::Failed(String::from("the passwords do not match")));
+        }
+        let value = |v: String| Some(v).filter(|v| !v.is_empty());
+        let fields = Fields {
+            login: value(login),
+            account: value(account),
+            password: value(password),
+        };
+        Ok((host, fields))
+    }
+}
+
+/// Echo of the terminal disabled until the guard is dropped.
+#[cfg(unix)]
+struct NoEcho(libc::termios);
+
+#[cfg(unix)]
+impl NoEcho {
+    fn new() -> Option<Self> {
+        // SAFETY: termios is a plain C struct filled by tcgetattr.
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: the pointer is valid for the duration of the call.
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+            return None;
+        }
+        let mut hidden = termios;
+        hidden.c_lflag &= !libc::ECHO;
+        // The end of line is still echoed.
+        hidden.c_lflag |= libc::ECHONL;
+        // SAFETY: the pointer is valid for the duration of the call.
+        match unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &hidden) } {
+            0 => Some(NoEcho(termios)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for NoEcho {
+    fn drop(&mut self) {
+        // SAFETY: the pointer is valid for the duration of the call.
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0) };
+    }
+}
+
+/// The echo of the terminal is kept on the other platforms.
+#[cfg(not(unix))]
+struct NoEcho;
+
+#[cfg(not(unix))]
+impl NoEcho {
+    fn new() -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(input: &str, host: Option<&str>) -> Result<(String, Fields), Error> {
+        let mut output = Vec::new();
+        let mut prompt = Prompt::new(input.as_bytes(), &mut output, false);
+        prompt.entry(host.map(str::to_owned))
+    }
+
+    #[test]
+    fn test_entry() {
+        let (host, fields) = entry("host.domain.com\nlog\n\npass\npass\n", None).unwrap();
+        assert_eq!(host, "host.domain.com");
+        assert_eq!(fields.login.as_deref(), Some("log"));
+        assert_eq!(fields.account, None);
+        assert_eq!(fields.password.as_deref(), Some("pass"));
+
+        let (host, fields) = entry("anonymous\n\n\n\n", Some("ftp.domain.com")).unwrap();
+        assert_eq!(host, "ftp.domain.com");
+        assert_eq!(fields.password, None);
+
+        assert!(entry("host.domain.com\nlog\n\npass\nother\n", None).is_err());
+        assert!(entry("host.domain.com\nlog\n\n\n\n", None).is_err());
+        assert!(entry("my host\n", None).is_err());
+        assert!(entry("host.domain.com\nlog\n", None).is_err());
+    }
+}
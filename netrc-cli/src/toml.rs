@@ -0,0 +1,295 @@
+//! TOML array of tables, like
+//!
+//! ```toml
+//! [[machines]]
+//! machine = "host.com"
+//! login = "log"
+//! password = "pass"
+//! ```
+//!
+//! Only the single-line strings, the integers and the booleans are supported.
+
+use std::fmt::Write;
+
+/// Name of the array of tables written by [`write`].
+const ARRAY: &str = "machines";
+
+/// Parse the tables of the arrays of tables, whatever their name. The
+/// integers are kept as text, and the booleans are ignored.
+pub fn parse(s: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let mut tables: Vec<Vec<(String, String)>> = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        let error = |e: String| format!("line {}: {}", i + 1, e);
+        let mut parser = Parser {
+            chars: line.chars().peekable(),
+        };
+        match parser.peek() {
+            None | Some('#') => continue,
+            Some('[') => {
+                parser.header().map_err(error)?;
+                tables.push(Vec::new());
+            }
+            Some(_) => {
+                let (key, value) = parser.key_value().map_err(error)?;
+                let table = tables
+                    .last_mut()
+                    .ok_or_else(|| error(String::from("expected an array of tables")))?;
+                if let Some(value) = value {
+                    table.push((key, value));
+                }
+            }
+        }
+        parser.end().map_err(error)?;
+    }
+    Ok(tables)
+}
+
+/// Array of tables, separated by a blank line.
+pub fn write(tables: &[Vec<(&str, &str)>]) -> String {
+    let tables: Vec<_> = tables
+        .iter()
+        .map(|fields| {
+            let mut table = format!("[[{}]]\n", ARRAY);
+            for (key, value) in fields {
+                let _ = writeln!(table, "{} = {}", self::key(key), string(value));
+            }
+            table
+        })
+        .collect();
+    tables.join("\n")
+}
+
+/// Bare key, or quoted if necessary.
+fn key(key: &str) -> String {
+    match !key.is_empty() && key.chars().all(is_bare) {
+        true => key.to_owned(),
+        false => string(key),
+    }
+}
+
+/// Basic string.
+fn string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(res, "\\u{:04X}", c as u32);
+            }
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn is_bare(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    /// Next character, after the spaces.
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| matches!(c, ' ' | '\t')).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            _ => Err(format!("expected '{}'", expected)),
+        }
+    }
+
+    /// Header of an array of tables, like `[[machines]]`.
+    fn header(&mut self) -> Result<(), String> {
+        self.expect('[')?;
+        if self.chars.next_if_eq(&'[').is_none() {
+            return Err(String::from("only the arrays of tables are supported"));
+        }
+        self.key()?;
+        while self.peek() == Some('.') {
+            self.chars.next();
+            self.key()?;
+        }
+        self.expect(']')?;
+        match self.chars.next_if_eq(&']') {
+            Some(_) => Ok(()),
+            None => Err(String::from("expected ']]'")),
+        }
+    }
+
+    /// Key and value, `None` for a boolean.
+    fn key_value(&mut self) -> Result<(String, Option<String>), String> {
+        let key = self.key()?;
+        self.expect('=')?;
+        let value = match self.peek() {
+            Some('"') | Some('\'') => Some(self.string()?),
+            Some(c) if c == '-' || c == '+' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = self.chars.next_if(|c| is_bare(*c) || *c == '+') {
+                    if c != '_' {
+                        number.push(c);
+                    }
+                }
+                Some(number)
+            }
+            _ => {
+                let mut literal = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+                    literal.push(c);
+                }
+                match literal.as_str() {
+                    "true" | "false" => None,
+                    _ => return Err(String::from("expected a string")),
+                }
+            }
+        };
+        Ok((key, value))
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') | Some('\'') => self.string(),
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self.chars.next_if(|c| is_bare(*c)) {
+                    key.push(c);
+                }
+                match key.is_empty() {
+                    true => Err(String::from("expected a key")),
+                    false => Ok(key),
+                }
+            }
+        }
+    }
+
+    /// Basic or literal string.
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.chars.next();
+        if self.chars.peek() == quote.as_ref() {
+            self.chars.next();
+            if self.chars.peek() == quote.as_ref() {
+                return Err(String::from("multi-line strings are not supported"));
+            }
+            return Ok(String::new());
+        }
+        let mut res = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if Some(c) == quote => return Ok(res),
+                Some('\\') if quote == Some('"') => match self.chars.next() {
+                    Some('"') => res.push('"'),
+                    Some('\\') => res.push('\\'),
+                    Some('b') => res.push('\u{8}'),
+                    Some('f') => res.push('\u{c}'),
+                    Some('n') => res.push('\n'),
+                    Some('r') => res.push('\r'),
+                    Some('t') => res.push('\t'),
+                    Some('u') => res.push(self.unicode(4)?),
+                    Some('U') => res.push(self.unicode(8)?),
+                    _ => return Err(String::from("invalid escape sequence")),
+                },
+                Some(c) => res.push(c),
+                None => return Err(String::from("unterminated string")),
+            }
+        }
+    }
+
+    fn unicode(&mut self, len: usize) -> Result<char, String> {
+        let digits: String = self.chars.by_ref().take(len).collect();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == len)
+            .and_then(char::from_u32)
+            .ok_or_else(|| String::from("invalid unicode escape"))
+    }
+
+    /// End of the line, with an optional comment.
+    fn end(&mut self) -> Result<(), String> {
+        match self.peek() {
+            None | Some('#') => Ok(()),
+            Some(c) => Err(format!("unexpected character '{}'", c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let data = r#"
+# servers
+[[machines]]
+machine = "host.com"  # main
+"login" = 'C:\log'
+password = "a\"b\u00e9"
+port = 8_080
+enabled = true
+
+[[ other ]]
+machine = ""
+"#;
+        assert_eq!(
+            parse(data),
+            Ok(vec![
+                vec![
+                    (String::from("machine"), String::from("host.com")),
+                    (String::from("login"), String::from("C:\\log")),
+                    (String::from("password"), String::from("a\"bé")),
+                    (String::from("port"), String::from("8080")),
+                ],
+                vec![(String::from("machine"), String::from(""))],
+            ])
+        );
+        assert_eq!(parse(""), Ok(vec![]));
+        assert!(parse("machine = \"host.com\"").is_err());
+        assert!(parse("[machines]").is_err());
+        assert!(parse("[[machines]]\nmachine = \"host.com").is_err());
+        assert!(parse("[[machines]]\nmachine = \"host.com\" x").is_err());
+        assert!(parse("[[machines]]\nmachine = \"\"\"host.com\"\"\"").is_err());
+        assert!(parse("[[machines]]\nmachine = [\"host.com\"]").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let tables = vec![
+            vec![("machine", "host.com"), ("password", "a\"b\n")],
+            vec![("machine", "default"), ("my key", "v")],
+        ];
+        let text = write(&tables);
+        assert_eq!(
+            text,
+            "[[machines]]\nmachine = \"host.com\"\npassword = \"a\\\"b\\n\"\n\n\
+             [[machines]]\nmachine = \"default\"\n\"my key\" = \"v\"\n"
+        );
+        assert_eq!(
+            parse(&text).unwrap(),
+            vec![
+                vec![
+                    (String::from("machine"), String::from("host.com")),
+                    (String::from("password"), String::from("a\"b\n")),
+                ],
+                vec![
+                    (String::from("machine"), String::from("default")),
+                    (String::from("my key"), String::from("v")),
+                ],
+            ]
+        );
+        assert_eq!(write(&[]), "");
+    }
+}
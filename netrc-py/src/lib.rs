@@ -0,0 +1,167 @@
+//! Python bindings of the `rust-netrc` crate, as the `netrc_rs` module with
+//! the API of the `netrc` module of the standard library:
+//!
+//! ```python
+//! import netrc_rs
+//!
+//! nrc = netrc_rs.netrc()
+//! login, account, password = nrc.authenticators("api.domain.com")
+//! ```
+//!
+//! The syntax errors raise `netrc_rs.NetrcParseError`, with the `msg`,
+//! `filename` and `lineno` attributes, and the errors reading the file raise
+//! `OSError`.
+
+// Triggered by the wrappers generated by `pyfunction` in pyo3 0.22.
+#![allow(clippy::useless_conversion)]
+
+use ::netrc::{Error, Netrc, ParseOptions};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyOSError};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Login, account and password of a machine, empty if not defined.
+type Entry = (String, String, String);
+
+create_exception!(
+    netrc_rs,
+    NetrcParseError,
+    PyException,
+    "Exception raised on the syntax errors of a netrc file."
+);
+
+/// `NetrcParseError` to raise, with the `msg`, `filename` and `lineno`
+/// attributes, and shown like in the standard library:
+/// `msg (filename, line lineno)`.
+fn parse_error(
+    py: Python<'_>,
+    msg: String,
+    filename: Option<String>,
+    lineno: Option<u32>,
+) -> PyErr {
+    let text = format!(
+        "{} ({}, line {})",
+        msg,
+        filename.as_deref().unwrap_or("None"),
+        lineno.map_or(String::from("None"), |l| l.to_string())
+    );
+    let err = NetrcParseError::new_err(text);
+    let value = err.value_bound(py);
+    let attrs = value
+        .setattr("msg", msg)
+        .and_then(|_| value.setattr("filename", filename))
+        .and_then(|_| value.setattr("lineno", lineno));
+    match attrs {
+        Ok(()) => err,
+        Err(e) => e,
+    }
+}
+
+/// Python exception of an error of the crate.
+fn to_py_err(py: Python<'_>, e: Error) -> PyErr {
+    match e {
+        Error::Io(e) => e.into(),
+        Error::Parsing { parser, filename } => parse_error(
+            py,
+            parser.message().to_owned(),
+            Some(filename),
+            Some(parser.lineno()),
+        ),
+        Error::Insecure { filename, reason } => parse_error(
+            py,
+            format!("~/.netrc access too permissive: {}", reason),
+            Some(filename),
+            None,
+        ),
+        e => PyOSError::new_err(e.to_string()),
+    }
+}
+
+fn entry(auth: &::netrc::Authenticator) -> Entry {
+    (
+        auth.login.clone(),
+        auth.account.clone(),
+        auth.password.clone(),
+    )
+}
+
+/// Parsed netrc file, like `netrc.netrc` of the standard library.
+#[pyclass(name = "netrc", module = "netrc_rs")]
+struct PyNetrc {
+    inner: Netrc,
+}
+
+#[pymethods]
+impl PyNetrc {
+    /// Parse `file`, or the netrc file of the user (`NETRC`, else
+    /// `~/.netrc`) whose permissions are checked then.
+    #[new]
+    #[pyo3(signature = (file=None))]
+    fn new(py: Python<'_>, file: Option<PathBuf>) -> PyResult<Self> {
+        let (file, options) = match file {
+            Some(file) => (file, ParseOptions::default()),
+            None => {
+                let file =
+                    Netrc::get_file().ok_or_else(|| PyOSError::new_err("Could not find .netrc"))?;
+                let options = ParseOptions {
+                    check_permissions: true,
+                    ..Default::default()
+                };
+                (file, options)
+            }
+        };
+        let inner = py
+            .allow_threads(|| Netrc::from_file_with(&file, &options))
+            .map_err(|e| to_py_err(py, e))?;
+        Ok(PyNetrc { inner })
+    }
+
+    /// Machines with their `(login, account, password)`, the `default` entry
+    /// included.
+    #[getter]
+    fn hosts(&self) -> HashMap<String, Entry> {
+        self.inner
+            .hosts_with_default()
+            .iter()
+            .map(|(name, auth)| (name.clone(), entry(auth)))
+            .collect()
+    }
+
+    /// Macros with their lines.
+    #[getter]
+    fn macros(&self) -> HashMap<String, Vec<String>> {
+        self.inner.macros.clone().into_iter().collect()
+    }
+
+    /// `(login, account, password)` of a host, else of the `default` entry,
+    /// else `None`.
+    fn authenticators(&self, host: &str) -> Option<Entry> {
+        self.inner.lookup(host, None).map(entry)
+    }
+
+    /// Content of the file in the netrc format.
+    fn __repr__(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+/// Parse the content of a netrc file.
+#[pyfunction]
+fn loads(py: Python<'_>, s: &str) -> PyResult<PyNetrc> {
+    s.parse()
+        .map(|inner| PyNetrc { inner })
+        .map_err(|e: ::netrc::ParsingError| {
+            parse_error(py, e.message().to_owned(), None, Some(e.lineno()))
+        })
+}
+
+#[pymodule]
+fn netrc_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyNetrc>()?;
+    m.add("NetrcParseError", m.py().get_type_bound::<NetrcParseError>())?;
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Netrc credentials of the requests of the blocking client.
+
+use crate::{Builder, NetrcMiddleware};
+use netrc::CredentialSource;
+use reqwest::blocking::{Client, Request, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+
+/// The blocking client has no middleware: the configuration of a
+/// [`NetrcMiddleware`] is applied to its requests explicitly.
+///
+/// ```no_run
+/// use reqwest::blocking::Client;
+/// use reqwest_netrc::NetrcMiddleware;
+///
+/// let netrc = NetrcMiddleware::new().unwrap();
+/// let client = Client::new();
+///
+/// // Send the credentials preemptively.
+/// let res = netrc
+///     .init_blocking(client.get("https://domain.com/api"))
+///     .send()
+///     .unwrap();
+///
+/// // Send the credentials only if the server asks for them.
+/// let req = client.get("https://domain.com/api").build().unwrap();
+/// let res = netrc.send_blocking(&client, req).unwrap();
+/// ```
+impl Builder for RequestBuilder {
+    fn url_headers(&self) -> Option<(Url, HeaderMap)> {
+        let req = self.try_clone()?.build().ok()?;
+        Some((req.url().clone(), req.headers().clone()))
+    }
+
+    fn replace_headers(self, headers: HeaderMap) -> Self {
+        self.headers(headers)
+    }
+}
+
+impl<S: CredentialSource> NetrcMiddleware<S> {
+    /// Add the credentials to a request, like the middleware registered with
+    /// `ClientBuilder::with_init`.
+    ///
+    /// The request is copied to find its credentials, so it should be
+    /// initialised before its body is set: a request with a streaming body
+    /// is left untouched, like an invalid request.
+    pub fn init_blocking(&self, req: RequestBuilder) -> RequestBuilder {
+        self.init_builder(req)
+    }
+
+    /// Send a request, and send it again with the credentials if the server
+    /// asks for them, like the middleware registered with
+    /// `ClientBuilder::with`.
+    ///
+    /// The redirects are followed by the client, see
+    /// [`follow_redirects`](NetrcMiddleware::follow_redirects).
+    pub fn send_blocking(&self, client: &Client, req: Request) -> reqwest::Result<Response> {
+        let retry = req.try_clone();
+        let res = client.execute(req)?;
+        if res.status() != StatusCode::UNAUTHORIZED {
+            return Ok(res);
+        }
+        let Some(mut retry) = retry else {
+            return Ok(res);
+        };
+        let Some((name, value)) =
+            self.retry_credentials(retry.method(), retry.url(), retry.headers(), res.headers())
+        else {
+            return Ok(res);
+        };
+        retry.headers_mut().insert(name, value);
+        client.execute(retry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netrc::Netrc;
+
+    #[test]
+    fn test_init_blocking() {
+        let nrc: Netrc = "machine host.domain.com login log password pass"
+            .parse()
+            .unwrap();
+        let netrc = NetrcMiddleware::from_source(nrc);
+        let client = Client::new();
+
+        let req = netrc
+            .init_blocking(client.get("https://host.domain.com/api"))
+            .build()
+            .unwrap();
+        assert_eq!(req.headers()["authorization"], "Basic bG9nOnBhc3M=");
+
+        // The error of the URL is kept, rather than the one of an empty URL.
+        let err = netrc
+            .init_blocking(client.get("https://"))
+            .build()
+            .unwrap_err();
+        assert!(err.is_builder());
+        assert!(format!("{:?}", err).contains("EmptyHost"));
+    }
+}
@@ -0,0 +1,216 @@
+//! Builder to configure a middleware.
+
+use crate::{AuthScheme, HeaderTemplate, NetrcMiddleware, Options};
+use netrc::{normalize_host, Authenticator, CredentialSource, Error, Netrc, Result};
+use reqwest::Url;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Builder for [`NetrcMiddleware`], created by [`NetrcMiddleware::builder`].
+///
+/// The netrc is loaded like [`NetrcMiddleware::new`] unless another source is
+/// given, and the combination of the options is checked by
+/// [`build`](NetrcMiddlewareBuilder::build).
+///
+/// ```no_run
+/// use reqwest_netrc::{AuthScheme, NetrcMiddleware};
+///
+/// let middleware = NetrcMiddleware::builder()
+///     .allow_hosts(["*.domain.com"])
+///     .auth_scheme(AuthScheme::Auto)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct NetrcMiddlewareBuilder<S = Netrc> {
+    load: Box<dyn FnOnce() -> Result<Arc<S>>>,
+    options: Options,
+}
+
+impl NetrcMiddleware {
+    /// Create a builder to configure a middleware.
+    pub fn builder() -> NetrcMiddlewareBuilder {
+        NetrcMiddlewareBuilder {
+            load: Box::new(|| Netrc::new().map(Arc::new)),
+            options: Options::default(),
+        }
+    }
+}
+
+impl NetrcMiddlewareBuilder {
+    /// Load the netrc from `file`.
+    pub fn file(mut self, file: &Path) -> Self {
+        let file = PathBuf::from(file);
+        self.load = Box::new(move || Netrc::from_file(&file).map(Arc::new));
+        self
+    }
+}
+
+impl<S: CredentialSource + 'static> NetrcMiddlewareBuilder<S> {
+    /// Find the credentials in `source`, like an already parsed netrc or a
+    /// chain of backends.
+    pub fn source<T: CredentialSource + 'static>(self, source: T) -> NetrcMiddlewareBuilder<T> {
+        self.shared_source(Arc::new(source))
+    }
+
+    /// Find the credentials in a source shared with the rest of the
+    /// application, see [`NetrcMiddleware::from_arc`].
+    pub fn shared_source<T: CredentialSource + 'static>(
+        self,
+        source: Arc<T>,
+    ) -> NetrcMiddlewareBuilder<T> {
+        NetrcMiddlewareBuilder {
+            load: Box::new(move || Ok(source)),
+            options: self.options,
+        }
+    }
+
+    /// See [`NetrcMiddleware::override_authorization`].
+    pub fn override_authorization(mut self, enabled: bool) -> Self {
+        self.options.override_authorization = enabled;
+        self
+    }
+
+    /// See [`NetrcMiddleware::https_only`].
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.options.https_only = enabled;
+        self
+    }
+
+    /// See [`NetrcMiddleware::allow_hosts`].
+    pub fn allow_hosts<I, T>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.options
+            .allowed_hosts
+            .extend(hosts.into_iter().map(|h| normalize_host(h.as_ref())));
+        self
+    }
+
+    /// See [`NetrcMiddleware::deny_hosts`].
+    pub fn deny_hosts<I, T>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.options
+            .denied_hosts
+            .extend(hosts.into_iter().map(|h| normalize_host(h.as_ref())));
+        self
+    }
+
+    /// See [`NetrcMiddleware::follow_redirects`].
+    pub fn follow_redirects(mut self, max: usize) -> Self {
+        self.options.max_redirects = max;
+        self
+    }
+
+    /// See [`NetrcMiddleware::auth_scheme`].
+    pub fn auth_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.options.auth_scheme = scheme;
+        self
+    }
+
+    /// See [`NetrcMiddleware::header_template`].
+    pub fn header_template(mut self, template: HeaderTemplate) -> Self {
+        self.options.header_templates.push((None, template));
+        self
+    }
+
+    /// See [`NetrcMiddleware::host_header_template`].
+    pub fn host_header_template(mut self, host: &str, template: HeaderTemplate) -> Self {
+        self.options
+            .header_templates
+            .push((Some(normalize_host(host)), template));
+        self
+    }
+
+    /// See [`NetrcMiddleware::with_resolver`].
+    pub fn resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&Url) -> Option<Authenticator> + Send + Sync + 'static,
+    {
+        self.options.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Check the options, load the source and create the middleware.
+    ///
+    /// Return an [`Error::Invalid`] error if the options contradict each
+    /// other, like a host both allowed and denied.
+    pub fn build(self) -> Result<NetrcMiddleware<S>> {
+        self.check().map_err(Error::Invalid)?;
+        Ok(NetrcMiddleware {
+            source: (self.load)()?,
+            options: Arc::new(self.options),
+        })
+    }
+
+    fn check(&self) -> std::result::Result<(), String> {
+        let options = &self.options;
+        let mut hosts = options.allowed_hosts.iter().chain(&options.denied_hosts);
+        if hosts.any(|h| h.is_empty()) {
+            return Err(String::from("empty host"));
+        }
+        if let Some(host) = options
+            .allowed_hosts
+            .iter()
+            .find(|h| options.denied_hosts.contains(h))
+        {
+            return Err(format!("host '{}' is both allowed and denied", host));
+        }
+        if let Some((Some(host), _)) = options.header_templates.iter().find(|(host, _)| {
+            host.as_ref()
+                .is_some_and(|h| options.denied_hosts.contains(h))
+        }) {
+            return Err(format!("header template of the denied host '{}'", host));
+        }
+        if options.auth_scheme != AuthScheme::Basic
+            && options
+                .header_templates
+                .iter()
+                .any(|(host, _)| host.is_none())
+        {
+            return Err(String::from(
+                "the authentication scheme is not used with a header template for every host",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let builder = || NetrcMiddleware::builder().source(Netrc::default());
+        let template =
+            || HeaderTemplate::new(http::HeaderName::from_static("private-token"), "{password}");
+
+        assert!(builder().allow_hosts(["*.domain.com"]).build().is_ok());
+        assert!(builder()
+            .allow_hosts(["Host.domain.com"])
+            .deny_hosts(["host.domain.com"])
+            .build()
+            .is_err());
+        assert!(builder().allow_hosts([""]).build().is_err());
+        assert!(builder()
+            .deny_hosts(["host.domain.com"])
+            .host_header_template("host.domain.com", template())
+            .build()
+            .is_err());
+        assert!(builder()
+            .auth_scheme(AuthScheme::Bearer)
+            .header_template(template())
+            .build()
+            .is_err());
+        assert!(builder()
+            .auth_scheme(AuthScheme::Bearer)
+            .host_header_template("host.domain.com", template())
+            .build()
+            .is_ok());
+    }
+}
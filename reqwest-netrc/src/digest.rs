@@ -0,0 +1,273 @@
+//! Digest access authentication (RFC 7616).
+
+use md5::{Digest, Md5};
+use netrc::Authenticator;
+use reqwest::header::{HeaderMap, HeaderValue, WWW_AUTHENTICATE};
+use reqwest::{Method, Url};
+use sha2::{Sha256, Sha512_256};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Hash algorithm of a challenge.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Algorithm {
+    Md5,
+    Sha256,
+    Sha512_256,
+}
+
+impl Algorithm {
+    fn hash(self, data: &str) -> String {
+        match self {
+            Algorithm::Md5 => format!("{:x}", Md5::digest(data)),
+            Algorithm::Sha256 => format!("{:x}", Sha256::digest(data)),
+            Algorithm::Sha512_256 => format!("{:x}", Sha512_256::digest(data)),
+        }
+    }
+}
+
+/// `Digest` challenge of a `WWW-Authenticate` header.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    session: bool,
+    qop: bool,
+    userhash: bool,
+}
+
+impl Challenge {
+    /// First `Digest` challenge of the headers with a supported algorithm,
+    /// the most secure being preferred.
+    pub(crate) fn find(headers: &HeaderMap) -> Option<Challenge> {
+        let mut challenges: Vec<Challenge> = headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(parse_challenges)
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("digest"))
+            .filter_map(|(_, params)| Challenge::from_params(&params))
+            .collect();
+        challenges.sort_by_key(|c| match c.algorithm {
+            Algorithm::Sha512_256 => 0,
+            Algorithm::Sha256 => 1,
+            Algorithm::Md5 => 2,
+        });
+        challenges.into_iter().next()
+    }
+
+    fn from_params(params: &[(String, String)]) -> Option<Challenge> {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+        let algorithm = param("algorithm").unwrap_or("MD5").to_ascii_uppercase();
+        let (algorithm, session) = match algorithm.strip_suffix("-SESS") {
+            Some(algorithm) => (algorithm, true),
+            None => (algorithm.as_str(), false),
+        };
+        let algorithm = match algorithm {
+            "MD5" => Algorithm::Md5,
+            "SHA-256" => Algorithm::Sha256,
+            "SHA-512-256" => Algorithm::Sha512_256,
+            _ => return None,
+        };
+        // Only the `auth` quality of protection is supported, a challenge
+        // without `qop` is the legacy one of RFC 2069.
+        let qop = match param("qop") {
+            Some(qop) => match qop.split(',').any(|q| q.trim() == "auth") {
+                true => true,
+                false => return None,
+            },
+            None => false,
+        };
+        Some(Challenge {
+            realm: param("realm")?.to_owned(),
+            nonce: param("nonce")?.to_owned(),
+            opaque: param("opaque").map(str::to_owned),
+            algorithm,
+            session,
+            qop,
+            userhash: param("userhash").is_some_and(|v| v.eq_ignore_ascii_case("true")),
+        })
+    }
+
+    /// `Authorization` header answering the challenge for a request.
+    pub(crate) fn authorization(
+        &self,
+        auth: &Authenticator,
+        method: &Method,
+        url: &Url,
+    ) -> Option<HeaderValue> {
+        let uri = &url[url::Position::BeforePath..url::Position::AfterQuery];
+        let mut value =
+            HeaderValue::from_str(&self.response(auth, method.as_str(), uri, &cnonce())).ok()?;
+        value.set_sensitive(true);
+        Some(value)
+    }
+
+    fn response(&self, auth: &Authenticator, method: &str, uri: &str, cnonce: &str) -> String {
+        let h = |data: String| self.algorithm.hash(&data);
+        let nc = "00000001";
+        let mut ha1 = h(format!("{}:{}:{}", auth.login, self.realm, auth.password));
+        if self.session {
+            ha1 = h(format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = h(format!("{}:{}", method, uri));
+        let response = match self.qop {
+            true => h(format!(
+                "{}:{}:{}:{}:auth:{}",
+                ha1, self.nonce, nc, cnonce, ha2
+            )),
+            false => h(format!("{}:{}:{}", ha1, self.nonce, ha2)),
+        };
+        let username = match self.userhash {
+            true => h(format!("{}:{}", auth.login, self.realm)),
+            false => auth.login.clone(),
+        };
+
+        let algorithm = match self.algorithm {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha512_256 => "SHA-512-256",
+        };
+        let mut value = format!(
+            "Digest username={}, realm={}, uri={}, algorithm={}{}, nonce={}",
+            quote(&username),
+            quote(&self.realm),
+            quote(uri),
+            algorithm,
+            if self.session { "-sess" } else { "" },
+            quote(&self.nonce),
+        );
+        if self.qop {
+            value += &format!(", nc={}, cnonce={}, qop=auth", nc, quote(cnonce));
+        }
+        value += &format!(", response={}", quote(&response));
+        if let Some(opaque) = &self.opaque {
+            value += &format!(", opaque={}", quote(opaque));
+        }
+        if self.userhash {
+            value += ", userhash=true";
+        }
+        value
+    }
+}
+
+/// Client nonce, unique but not cryptographically secure.
+fn cnonce() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH);
+    hasher.write_u128(now.map_or(0, |d| d.as_nanos()));
+    format!("{:016x}", hasher.finish())
+}
+
+/// Quoted string of `s`.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Parse the challenges of a `WWW-Authenticate` header, like
+/// `Basic realm="a", Digest realm="b", nonce="c"`, into their scheme and
+/// parameters.
+fn parse_challenges(header: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut challenges: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    let mut chars = header.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        let mut token = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ',' && *c != '=') {
+            token.push(c);
+        }
+        if token.is_empty() {
+            // A token68 value, or the end of the header.
+            if chars.next().is_none() {
+                return challenges;
+            }
+            continue;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(challenge) = challenges.last_mut().filter(|_| chars.peek() == Some(&'=')) else {
+            challenges.push((token, Vec::new()));
+            continue;
+        };
+        chars.next();
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ',') {
+                value.push(c);
+            }
+        }
+        challenge.1.push((token, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+
+    #[test]
+    fn test_parse_challenges() {
+        let challenges = parse_challenges(
+            r#"Basic realm="a\"b", Negotiate, Digest qop="auth,auth-int", nonce=xyz"#,
+        );
+        let param = |k: &str, v: &str| (k.to_owned(), v.to_owned());
+        assert_eq!(
+            challenges,
+            vec![
+                (String::from("Basic"), vec![param("realm", "a\"b")]),
+                (String::from("Negotiate"), vec![]),
+                (
+                    String::from("Digest"),
+                    vec![param("qop", "auth,auth-int"), param("nonce", "xyz")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_response() {
+        // Example of the RFC 7616, section 3.9.1.
+        let mut headers = HeaderMap::new();
+        headers.append(WWW_AUTHENTICATE, HeaderValue::from_static(CHALLENGE));
+        headers.append(
+            WWW_AUTHENTICATE,
+            HeaderValue::from_str(&CHALLENGE.replace("SHA-256", "MD5")).unwrap(),
+        );
+        let challenge = Challenge::find(&headers).unwrap();
+        assert_eq!(challenge.algorithm, Algorithm::Sha256);
+
+        let auth = Authenticator::new("Mufasa", "", "Circle of Life");
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        let response = challenge.response(&auth, "GET", "/dir/index.html", cnonce);
+        assert!(response.contains(
+            r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+
+        let challenge = Challenge {
+            algorithm: Algorithm::Md5,
+            ..challenge
+        };
+        let response = challenge.response(&auth, "GET", "/dir/index.html", cnonce);
+        assert_eq!(
+            response,
+            r#"Digest username="Mufasa", realm="http-auth@example.org", uri="/dir/index.html", algorithm=MD5, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", nc=00000001, cnonce="f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ", qop=auth, response="8ca523f5e9506fed4657c9700eebdbec", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Netrc loaded on first use, and cache of the credentials of a source.
+
+use netrc::{normalize_host, Authenticator, CredentialSource, MatchKind, Netrc, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Netrc loaded when the credentials are looked up for the first time, so
+/// that an application making no authenticated request never reads it.
+///
+/// A netrc which can not be loaded provides no credentials.
+pub struct LazyNetrc {
+    load: Box<dyn Fn() -> Result<Netrc> + Send + Sync>,
+    nrc: OnceLock<Option<Netrc>>,
+}
+
+impl LazyNetrc {
+    /// Create a netrc loaded like [`Netrc::new`].
+    pub fn new() -> Self {
+        LazyNetrc {
+            load: Box::new(Netrc::new),
+            nrc: OnceLock::new(),
+        }
+    }
+
+    /// Create a netrc loaded from `file`.
+    pub fn from_file(file: &Path) -> Self {
+        let file = PathBuf::from(file);
+        LazyNetrc {
+            load: Box::new(move || Netrc::from_file(&file)),
+            nrc: OnceLock::new(),
+        }
+    }
+
+    /// Netrc, loaded on the first call.
+    pub fn netrc(&self) -> Option<&Netrc> {
+        self.nrc.get_or_init(|| (self.load)().ok()).as_ref()
+    }
+}
+
+impl Default for LazyNetrc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialSource for LazyNetrc {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        self.netrc()?.find(host, port, scheme)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        self.netrc()?.find_match(host, port, scheme)
+    }
+}
+
+type CacheKey = (String, Option<u16>, Option<String>);
+
+/// Authenticator found for a key, and when it was found.
+type CacheEntry = (Option<(Authenticator, MatchKind)>, Instant);
+
+/// Default maximum number of results kept by a [`CachedSource`].
+const MAX_ENTRIES: usize = 1024;
+
+/// Source caching the credentials found by another one for every host, port
+/// and scheme, including the absence of credentials.
+///
+/// The results are kept for `ttl`, or forever without TTL. No more than 1024
+/// results are kept by default (see [`CachedSource::with_max_entries`]): the
+/// oldest one is forgotten to make room for a new one.
+pub struct CachedSource<S> {
+    source: S,
+    ttl: Option<Duration>,
+    max_entries: usize,
+    cache: Mutex<HashMap<CacheKey, CacheEntry>>,
+}
+
+impl<S: CredentialSource> CachedSource<S> {
+    /// Create a cache of the results of `source`.
+    pub fn new(source: S, ttl: Option<Duration>) -> Self {
+        CachedSource {
+            source,
+            ttl,
+            max_entries: MAX_ENTRIES,
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Keep no more than `max` results.
+    pub fn with_max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    /// Forget the cached results.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl<S: CredentialSource> CredentialSource for CachedSource<S> {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        self.find_match(host, port, scheme).map(|(auth, _)| auth)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        let key = (normalize_host(host), port, scheme.map(str::to_owned));
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((found, time)) = cache.get(&key) {
+            if self.ttl.is_none_or(|ttl| time.elapsed() < ttl) {
+                return found.clone();
+            }
+        }
+        let found = self.source.find_match(host, port, scheme);
+        if self.max_entries == 0 {
+            return found;
+        }
+        if cache.len() >= self.max_entries && !cache.contains_key(&key) {
+            if let Some(ttl) = self.ttl {
+                cache.retain(|_, (_, time)| time.elapsed() < ttl);
+            }
+            if cache.len() >= self.max_entries {
+                let oldest = cache
+                    .iter()
+                    .min_by_key(|(_, (_, time))| *time)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    cache.remove(&oldest);
+                }
+            }
+        }
+        cache.insert(key, (found.clone(), Instant::now()));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct CountingSource(Cell<usize>);
+
+    impl CredentialSource for CountingSource {
+        fn find(&self, host: &str, _: Option<u16>, _: Option<&str>) -> Option<Authenticator> {
+            self.0.set(self.0.get() + 1);
+            (host == "host.domain.com").then(|| Authenticator::new("log", "", "pass"))
+        }
+    }
+
+    #[test]
+    fn test_cached_source() {
+        let source = CachedSource::new(CountingSource(Cell::new(0)), None);
+        assert!(source.find("host.domain.com", None, None).is_some());
+        assert!(source.find("Host.Domain.com", None, None).is_some());
+        assert!(source.find("other.com", None, None).is_none());
+        assert!(source.find("other.com", None, None).is_none());
+        assert_eq!(source.source.0.get(), 2);
+
+        source.clear();
+        assert!(source.find("other.com", None, None).is_none());
+        assert_eq!(source.source.0.get(), 3);
+
+        let source = CachedSource::new(CountingSource(Cell::new(0)), Some(Duration::ZERO));
+        assert!(source.find("other.com", None, None).is_none());
+        assert!(source.find("other.com", None, None).is_none());
+        assert_eq!(source.source.0.get(), 2);
+    }
+
+    #[test]
+    fn test_cached_source_max_entries() {
+        let source = CachedSource::new(CountingSource(Cell::new(0)), None).with_max_entries(2);
+        assert!(source.find("host.domain.com", None, None).is_some());
+        assert!(source.find("other.com", None, None).is_none());
+        assert!(source.find("third.com", None, None).is_none());
+        assert_eq!(source.cache.lock().unwrap().len(), 2);
+        assert_eq!(source.source.0.get(), 3);
+
+        // The oldest result is forgotten, the newest ones are still cached.
+        assert!(source.find("third.com", None, None).is_none());
+        assert!(source.find("other.com", None, None).is_none());
+        assert_eq!(source.source.0.get(), 3);
+        assert!(source.find("host.domain.com", None, None).is_some());
+        assert_eq!(source.source.0.get(), 4);
+
+        let source = CachedSource::new(CountingSource(Cell::new(0)), None).with_max_entries(0);
+        assert!(source.find("other.com", None, None).is_none());
+        assert!(source.find("other.com", None, None).is_none());
+        assert_eq!(source.source.0.get(), 2);
+        assert!(source.cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lazy_netrc() {
+        let nrc = LazyNetrc::from_file(Path::new("/nonexistent/.netrc"));
+        assert!(nrc.nrc.get().is_none());
+        assert!(nrc.find("host.domain.com", None, None).is_none());
+        assert!(matches!(nrc.nrc.get(), Some(None)));
+    }
+}
@@ -0,0 +1,139 @@
+//! Netrc reloaded when its file changes.
+
+use netrc::{Authenticator, CredentialSource, MatchKind, Netrc, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Netrc parsed again when the modification time of its file changes, so
+/// that a long-running service picks up the rotated credentials.
+///
+/// The file is checked at most once per `interval`, when the credentials are
+/// looked up. If the new file can not be parsed, the previous credentials are
+/// kept until it is fixed.
+///
+/// ```no_run
+/// use reqwest_netrc::{NetrcMiddleware, ReloadingNetrc};
+/// use std::path::Path;
+/// use std::time::Duration;
+///
+/// let nrc = ReloadingNetrc::new(Path::new("/home/user/.netrc"), Duration::from_secs(10)).unwrap();
+/// let middleware = NetrcMiddleware::from_source(nrc);
+/// ```
+#[derive(Debug)]
+pub struct ReloadingNetrc {
+    file: PathBuf,
+    interval: Duration,
+    state: RwLock<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    nrc: Arc<Netrc>,
+    modified: Option<SystemTime>,
+    checked: Instant,
+}
+
+impl ReloadingNetrc {
+    /// Parse `file`, and check it for changes every `interval`.
+    pub fn new(file: &Path, interval: Duration) -> Result<Self> {
+        let modified = modified(file);
+        let nrc = Netrc::from_file(file)?;
+        Ok(ReloadingNetrc {
+            file: file.to_owned(),
+            interval,
+            state: RwLock::new(State {
+                nrc: Arc::new(nrc),
+                modified,
+                checked: Instant::now(),
+            }),
+        })
+    }
+
+    /// Current netrc, parsed again if the file has changed.
+    pub fn netrc(&self) -> Arc<Netrc> {
+        {
+            let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+            if state.checked.elapsed() < self.interval {
+                return state.nrc.clone();
+            }
+        }
+
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        // Another thread may have checked the file in the meantime.
+        if state.checked.elapsed() >= self.interval {
+            state.checked = Instant::now();
+            let modified = modified(&self.file);
+            if modified != state.modified {
+                if let Ok(nrc) = Netrc::from_file(&self.file) {
+                    state.nrc = Arc::new(nrc);
+                    state.modified = modified;
+                }
+            }
+        }
+        state.nrc.clone()
+    }
+}
+
+impl CredentialSource for ReloadingNetrc {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        self.netrc().find(host, port, scheme)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        self.netrc().find_match(host, port, scheme)
+    }
+}
+
+/// Modification time of `file`.
+fn modified(file: &Path) -> Option<SystemTime> {
+    fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write `content` to `file` with the modification time `secs`, so that
+    /// each write is seen by the reload whatever the timestamp resolution.
+    fn write(file: &Path, content: &str, secs: u64) {
+        fs::write(file, content).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(file)
+            .and_then(|f| f.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reload() {
+        let file = std::env::temp_dir().join("reqwest-netrc-reload.netrc");
+        write(
+            &file,
+            "machine host.domain.com login log1 password pass1",
+            1,
+        );
+        let nrc = ReloadingNetrc::new(&file, Duration::ZERO).unwrap();
+        let login = || {
+            nrc.find("host.domain.com", None, None)
+                .map(|a| a.login.clone())
+        };
+        assert_eq!(login(), Some(String::from("log1")));
+
+        write(
+            &file,
+            "machine host.domain.com login log2 password pass2",
+            2,
+        );
+        assert_eq!(login(), Some(String::from("log2")));
+
+        write(&file, "machine host.domain.com bad token", 3);
+        assert_eq!(login(), Some(String::from("log2")));
+    }
+}
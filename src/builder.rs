@@ -0,0 +1,236 @@
+//! Builder to construct a netrc programmatically.
+
+use crate::{normalize_host, Authenticator, Error, Netrc, Result};
+
+/// Builder for [`Netrc`], created by [`Netrc::builder`].
+///
+/// The `login`, `account` and `password` methods apply to the last machine
+/// started with [`machine`](NetrcBuilder::machine) or
+/// [`default`](NetrcBuilder::default).
+///
+/// ```
+/// use netrc::Netrc;
+///
+/// let nrc = Netrc::builder()
+///     .machine("host.domain.com")
+///     .login("log")
+///     .password("pass")
+///     .default()
+///     .login("anonymous")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(nrc.hosts["host.domain.com"].login, "log");
+/// assert_eq!(nrc.default_entry.unwrap().login, "anonymous");
+/// ```
+#[derive(Debug)]
+pub struct NetrcBuilder {
+    nrc: Netrc,
+    current: Option<Entry>,
+    error: Option<String>,
+}
+
+/// Entry started by the builder.
+#[derive(Debug)]
+enum Entry {
+    Machine(String),
+    Default,
+}
+
+impl Netrc {
+    /// Create a builder to construct a netrc.
+    pub fn builder() -> NetrcBuilder {
+        NetrcBuilder {
+            nrc: Netrc::default(),
+            current: None,
+            error: None,
+        }
+    }
+}
+
+impl NetrcBuilder {
+    /// Start the entry of a machine.
+    ///
+    /// The name is normalized with [`normalize_host`], like the names of the
+    /// parsed entries.
+    pub fn machine(mut self, name: &str) -> Self {
+        if name.is_empty() {
+            return self.fail(String::from("empty machine name"));
+        }
+        let name = normalize_host(name);
+        if self.nrc.hosts.contains_key(&name) {
+            return self.fail(format!("machine '{}' already defined", name));
+        }
+        self.nrc
+            .hosts
+            .insert(name.clone(), Authenticator::default());
+        self.current = Some(Entry::Machine(name));
+        self
+    }
+
+    /// Start the `default` entry, used for the machines without entry.
+    pub fn default(mut self) -> Self {
+        if self.nrc.default_entry.is_some() {
+            return self.fail(String::from("default entry already defined"));
+        }
+        self.nrc.default_entry = Some(Authenticator::default());
+        self.current = Some(Entry::Default);
+        self
+    }
+
+    /// Set the login of the current machine.
+    pub fn login(self, login: &str) -> Self {
+        self.set(|auth| auth.login = login.to_owned())
+    }
+
+    /// Set the account of the current machine.
+    pub fn account(self, account: &str) -> Self {
+        self.set(|auth| auth.account = account.to_owned())
+    }
+
+    /// Set the password of the current machine.
+    pub fn password(self, password: &str) -> Self {
+        self.set(|auth| auth.password = password.to_owned())
+    }
+
+    /// Define a macro.
+    ///
+    /// The lines can not be empty as an empty line ends the macro definition.
+    pub fn macdef<I, S>(mut self, name: &str, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if name.is_empty() {
+            return self.fail(String::from("empty macro name"));
+        }
+        if self.nrc.macros.contains_key(name) {
+            return self.fail(format!("macro '{}' already defined", name));
+        }
+        let lines: Vec<String> = lines.into_iter().map(Into::into).collect();
+        if lines
+            .iter()
+            .any(|l| l.trim().is_empty() || l.contains('\n'))
+        {
+            return self.fail(format!("invalid line in the macro '{}'", name));
+        }
+        self.nrc.macros.insert(name.to_owned(), lines);
+        self
+    }
+
+    /// Build the netrc, or return the first error found.
+    pub fn build(self) -> Result<Netrc> {
+        match self.error {
+            Some(msg) => Err(Error::Invalid(msg)),
+            None => Ok(self.nrc),
+        }
+    }
+
+    fn set<F: FnOnce(&mut Authenticator)>(mut self, f: F) -> Self {
+        let auth = match &self.current {
+            Some(Entry::Machine(name)) => self.nrc.hosts.get_mut(name),
+            Some(Entry::Default) => self.nrc.default_entry.as_mut(),
+            None => None,
+        };
+        match auth {
+            Some(auth) => {
+                f(auth);
+                self
+            }
+            None => self.fail(String::from("no machine defined")),
+        }
+    }
+
+    fn fail(mut self, msg: String) -> Self {
+        self.error.get_or_insert(msg);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_build() {
+        let nrc = Netrc::builder()
+            .machine("host.domain.com")
+            .login("lo g")
+            .account("acct")
+            .password("pass")
+            .machine("Other.Domain.com.")
+            .login("log")
+            .default()
+            .login("anonymous")
+            .password("me@")
+            .macdef("init", ["cd /pub", "bin"])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("lo g", "acct", "pass")
+        );
+        assert_eq!(
+            nrc.hosts["other.domain.com"],
+            Authenticator::new("log", "", "")
+        );
+        assert_eq!(
+            nrc.default_entry,
+            Some(Authenticator::new("anonymous", "", "me@"))
+        );
+        assert_eq!(nrc.macros["init"], vec!["cd /pub", "bin"]);
+        assert_eq!(Netrc::from_str(&nrc.to_string()).unwrap(), nrc);
+    }
+
+    #[test]
+    fn test_build_machine_default() {
+        let nrc = Netrc::builder()
+            .machine("default")
+            .login("log")
+            .default()
+            .login("anonymous")
+            .build()
+            .unwrap();
+        assert_eq!(nrc.hosts["default"].login, "log");
+        assert_eq!(nrc.default_entry.unwrap().login, "anonymous");
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let data = vec![
+            (Netrc::builder().login("log"), "no machine defined"),
+            (Netrc::builder().machine(""), "empty machine name"),
+            (
+                Netrc::builder().machine("host").machine("host"),
+                "machine 'host' already defined",
+            ),
+            (
+                Netrc::builder().default().default(),
+                "default entry already defined",
+            ),
+            (
+                Netrc::builder().macdef("init", ["cd /pub", ""]),
+                "invalid line in the macro 'init'",
+            ),
+            (
+                Netrc::builder()
+                    .macdef("init", ["bin"])
+                    .macdef("init", ["bin"]),
+                "macro 'init' already defined",
+            ),
+            (
+                Netrc::builder().login("log").machine(""),
+                "no machine defined",
+            ),
+        ];
+
+        for (builder, msg) in data {
+            assert_eq!(
+                builder.build().unwrap_err().to_string(),
+                format!("invalid netrc: {}", msg)
+            );
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! C API, declared by the header `include/netrc.h`:
+//!
+//! ```c
+//! char *error = NULL;
+//! NetrcHandle *nrc = netrc_parse_file("/home/me/.netrc", &error);
+//! if (nrc == NULL) {
+//!     fprintf(stderr, "%s\n", error);
+//!     netrc_string_free(error);
+//!     return 1;
+//! }
+//! NetrcAuthenticator auth;
+//! if (netrc_lookup(nrc, "api.domain.com", &auth) == 1) {
+//!     printf("%s\n", auth.login);
+//! }
+//! netrc_free(nrc);
+//! ```
+//!
+//! The library is built with
+//! `cargo rustc --release --features capi --crate-type cdylib` (or
+//! `staticlib`), and the header can be regenerated with
+//! `cbindgen --config cbindgen.toml --output include/netrc.h` after a change
+//! of the functions.
+
+use crate::Netrc;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+/// Parsed netrc, to free with `netrc_free`.
+pub struct NetrcHandle {
+    nrc: Netrc,
+    /// Login, account and password of the machines, as C strings unless
+    /// they contain a NUL byte, the `default` entry being under `None`.
+    strings: HashMap<Option<String>, [Option<CString>; 3]>,
+}
+
+impl NetrcHandle {
+    fn new(nrc: Netrc) -> Self {
+        let c_string = |s: &str| CString::new(s).ok();
+        let strings = nrc
+            .hosts
+            .iter()
+            .map(|(name, auth)| (Some(name.clone()), auth))
+            .chain(nrc.default_entry.iter().map(|auth| (None, auth)))
+            .map(|(name, auth)| {
+                let strings = [
+                    c_string(&auth.login),
+                    c_string(&auth.account),
+                    c_string(&auth.password),
+                ];
+                (name, strings)
+            })
+            .collect();
+        NetrcHandle { nrc, strings }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for NetrcHandle {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        for (_, strings) in self.strings.drain() {
+            for s in strings.into_iter().flatten() {
+                s.into_bytes().zeroize();
+            }
+        }
+    }
+}
+
+/// Authenticator of a machine. Its strings belong to the netrc, and are
+/// valid until it is freed. A value containing a NUL byte, which can not be
+/// a C string, is `NULL`.
+#[repr(C)]
+pub struct NetrcAuthenticator {
+    pub login: *const c_char,
+    pub account: *const c_char,
+    pub password: *const c_char,
+}
+
+/// Set the error message, if requested.
+unsafe fn set_error(error: *mut *mut c_char, message: &str) {
+    if !error.is_null() {
+        *error = CString::new(message.replace('\0', " "))
+            .unwrap_or_default()
+            .into_raw();
+    }
+}
+
+/// Parse the content of a netrc file.
+///
+/// Return `NULL` on error, with `*error` set to the message if `error` is
+/// not `NULL`, to free with `netrc_string_free`.
+///
+/// # Safety
+///
+/// `content` must be a valid C string, and `error` `NULL` or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn netrc_parse(
+    content: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut NetrcHandle {
+    if content.is_null() {
+        set_error(error, "null content");
+        return ptr::null_mut();
+    }
+    let content = match CStr::from_ptr(content).to_str() {
+        Ok(content) => content,
+        Err(e) => {
+            set_error(error, &e.to_string());
+            return ptr::null_mut();
+        }
+    };
+    match content.parse::<Netrc>() {
+        Ok(nrc) => Box::into_raw(Box::new(NetrcHandle::new(nrc))),
+        Err(e) => {
+            set_error(error, &e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Parse a netrc file, like `netrc_parse`.
+///
+/// # Safety
+///
+/// `path` must be a valid C string, and `error` `NULL` or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn netrc_parse_file(
+    path: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut NetrcHandle {
+    if path.is_null() {
+        set_error(error, "null path");
+        return ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => Path::new(path),
+        Err(e) => {
+            set_error(error, &e.to_string());
+            return ptr::null_mut();
+        }
+    };
+    match Netrc::from_file(path) {
+        Ok(nrc) => Box::into_raw(Box::new(NetrcHandle::new(nrc))),
+        Err(e) => {
+            set_error(error, &e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Look up the authenticator of a host like `Netrc::lookup`, with the
+/// `default` entry as fallback.
+///
+/// Return 1 if found, with `*auth` set, 0 if not found, and -1 if an
+/// argument is invalid.
+///
+/// # Safety
+///
+/// `netrc` must be `NULL` or returned by `netrc_parse` or `netrc_parse_file`
+/// and not freed, `host` a valid C string, and `auth` `NULL` or a valid
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn netrc_lookup(
+    netrc: *const NetrcHandle,
+    host: *const c_char,
+    auth: *mut NetrcAuthenticator,
+) -> c_int {
+    if netrc.is_null() || host.is_null() || auth.is_null() {
+        return -1;
+    }
+    let handle = &*netrc;
+    let Ok(host) = CStr::from_ptr(host).to_str() else {
+        return -1;
+    };
+    let found = handle.nrc.lookup(host, None).and_then(|found| {
+        let name = match &handle.nrc.default_entry {
+            Some(auth) if ptr::eq(auth, found) => None,
+            _ => handle
+                .nrc
+                .hosts
+                .iter()
+                .find(|(_, auth)| ptr::eq(*auth, found))
+                .map(|(name, _)| Some(name.clone()))?,
+        };
+        handle.strings.get(&name)
+    });
+    match found {
+        Some([login, account, password]) => {
+            let as_ptr = |s: &Option<CString>| s.as_ref().map_or(ptr::null(), |s| s.as_ptr());
+            *auth = NetrcAuthenticator {
+                login: as_ptr(login),
+                account: as_ptr(account),
+                password: as_ptr(password),
+            };
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Free a netrc.
+///
+/// # Safety
+///
+/// `netrc` must be `NULL` or returned by `netrc_parse` or `netrc_parse_file`
+/// and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn netrc_free(netrc: *mut NetrcHandle) {
+    if !netrc.is_null() {
+        drop(Box::from_raw(netrc));
+    }
+}
+
+/// Free an error message.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a message set by this library and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn netrc_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn string(s: *const c_char) -> String {
+        CStr::from_ptr(s).to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn test_lookup() {
+        let content = CString::new(
+            "machine host.domain.com login log password pass\n\
+             default login anonymous password me@\n",
+        )
+        .unwrap();
+        let host = CString::new("Host.Domain.com").unwrap();
+        let other = CString::new("other.com").unwrap();
+        unsafe {
+            let nrc = netrc_parse(content.as_ptr(), ptr::null_mut());
+            assert!(!nrc.is_null());
+
+            let mut auth = NetrcAuthenticator {
+                login: ptr::null(),
+                account: ptr::null(),
+                password: ptr::null(),
+            };
+            assert_eq!(netrc_lookup(nrc, host.as_ptr(), &mut auth), 1);
+            assert_eq!(string(auth.login), "log");
+            assert_eq!(string(auth.account), "");
+            assert_eq!(string(auth.password), "pass");
+
+            assert_eq!(netrc_lookup(nrc, other.as_ptr(), &mut auth), 1);
+            assert_eq!(string(auth.login), "anonymous");
+
+            assert_eq!(netrc_lookup(nrc, ptr::null(), &mut auth), -1);
+            netrc_free(nrc);
+        }
+    }
+
+    #[test]
+    fn test_lookup_nul() {
+        let mut nrc = Netrc::default();
+        nrc.hosts.insert(
+            String::from("host.domain.com"),
+            crate::Authenticator::new("log", "", "pa\0ss"),
+        );
+        let nrc = Box::into_raw(Box::new(NetrcHandle::new(nrc)));
+        let host = CString::new("host.domain.com").unwrap();
+        unsafe {
+            let mut auth = NetrcAuthenticator {
+                login: ptr::null(),
+                account: ptr::null(),
+                password: ptr::null(),
+            };
+            assert_eq!(netrc_lookup(nrc, host.as_ptr(), &mut auth), 1);
+            assert_eq!(string(auth.login), "log");
+            assert!(auth.password.is_null());
+            netrc_free(nrc);
+        }
+    }
+
+    #[test]
+    fn test_error() {
+        let content = CString::new("machine host.domain.com invalid").unwrap();
+        let path = CString::new("/nonexistent/netrc").unwrap();
+        unsafe {
+            let mut error = ptr::null_mut();
+            assert!(netrc_parse(content.as_ptr(), &mut error).is_null());
+            assert_eq!(
+                string(error),
+                "parsing error: bad follower token 'invalid' (line 1)"
+            );
+            netrc_string_free(error);
+
+            let mut error = ptr::null_mut();
+            assert!(netrc_parse_file(path.as_ptr(), &mut error).is_null());
+            assert!(string(error).starts_with("I/O error"));
+            netrc_string_free(error);
+
+            assert!(netrc_parse(ptr::null(), ptr::null_mut()).is_null());
+            netrc_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Credentials of the handles of the `curl` crate, to send the same
+//! credentials as the rest of this crate instead of relying on the netrc
+//! parser of libcurl.
+//!
+//! ```no_run
+//! use curl::easy::Easy;
+//! use netrc::Netrc;
+//! use netrc::curl::configure;
+//!
+//! let nrc = Netrc::new().unwrap();
+//! let mut easy = Easy::new();
+//! configure(&nrc, &mut easy, "https://api.domain.com/v1").unwrap();
+//! easy.perform().unwrap();
+//! ```
+
+use crate::{Authenticator, CredentialSource};
+use ::curl::easy::{Easy, Easy2, Handler, List};
+use ::curl::Error;
+use url::Url;
+
+/// Handle of a transfer, implemented by [`Easy`] and [`Easy2`].
+pub trait Handle {
+    fn url(&mut self, url: &str) -> Result<(), Error>;
+    fn username(&mut self, user: &str) -> Result<(), Error>;
+    fn password(&mut self, pass: &str) -> Result<(), Error>;
+    fn http_headers(&mut self, list: List) -> Result<(), Error>;
+}
+
+impl Handle for Easy {
+    fn url(&mut self, url: &str) -> Result<(), Error> {
+        Easy::url(self, url)
+    }
+
+    fn username(&mut self, user: &str) -> Result<(), Error> {
+        Easy::username(self, user)
+    }
+
+    fn password(&mut self, pass: &str) -> Result<(), Error> {
+        Easy::password(self, pass)
+    }
+
+    fn http_headers(&mut self, list: List) -> Result<(), Error> {
+        Easy::http_headers(self, list)
+    }
+}
+
+impl<H: Handler> Handle for Easy2<H> {
+    fn url(&mut self, url: &str) -> Result<(), Error> {
+        Easy2::url(self, url)
+    }
+
+    fn username(&mut self, user: &str) -> Result<(), Error> {
+        Easy2::username(self, user)
+    }
+
+    fn password(&mut self, pass: &str) -> Result<(), Error> {
+        Easy2::password(self, pass)
+    }
+
+    fn http_headers(&mut self, list: List) -> Result<(), Error> {
+        Easy2::http_headers(self, list)
+    }
+}
+
+/// Set the URL of a handle, and the login and the password of the host of
+/// the URL, and return whether the credentials were found.
+///
+/// The credentials are only sent over HTTPS or to a loopback host, see
+/// [`is_secure`]. libcurl picks the authentication method, `Basic` by
+/// default.
+pub fn configure<S, H>(source: &S, handle: &mut H, url: &str) -> Result<bool, Error>
+where
+    S: CredentialSource + ?Sized,
+    H: Handle,
+{
+    handle.url(url)?;
+    let Some(auth) = secure_authenticator(source, url) else {
+        return Ok(false);
+    };
+    handle.username(&auth.login)?;
+    handle.password(&auth.password)?;
+    Ok(true)
+}
+
+/// Set the URL of a handle, and the `Authorization` header of the `Bearer`
+/// authentication with the password of the host of the URL as token, and
+/// return whether the credentials were found.
+///
+/// The header is added to `headers`, which replaces the headers of the
+/// handle.
+pub fn configure_bearer<S, H>(
+    source: &S,
+    handle: &mut H,
+    url: &str,
+    mut headers: List,
+) -> Result<bool, Error>
+where
+    S: CredentialSource + ?Sized,
+    H: Handle,
+{
+    handle.url(url)?;
+    let auth = secure_authenticator(source, url);
+    if let Some(auth) = &auth {
+        headers.append(&format!("Authorization: Bearer {}", auth.password))?;
+    }
+    handle.http_headers(headers)?;
+    Ok(auth.is_some())
+}
+
+/// Authenticator of the host of `url`, whatever its scheme.
+///
+/// The default port of the scheme is used if the URL has no port, so that a
+/// `host:443` entry matches `https://host/`.
+pub fn authenticator<S>(source: &S, url: &Url) -> Option<Authenticator>
+where
+    S: CredentialSource + ?Sized,
+{
+    source.find(
+        url.host_str()?,
+        url.port_or_known_default(),
+        Some(url.scheme()),
+    )
+}
+
+/// Whether the credentials sent to `url` can not be read by a third party,
+/// see [`crate::is_secure`].
+pub fn is_secure(url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| crate::is_secure(url.scheme(), host))
+}
+
+fn secure_authenticator<S>(source: &S, url: &str) -> Option<Authenticator>
+where
+    S: CredentialSource + ?Sized,
+{
+    let url = Url::parse(url).ok().filter(is_secure)?;
+    authenticator(source, &url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Netrc;
+
+    #[derive(Default)]
+    struct Recorder {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+        headers: Vec<String>,
+    }
+
+    impl Handle for Recorder {
+        fn url(&mut self, url: &str) -> Result<(), Error> {
+            self.url = url.to_owned();
+            Ok(())
+        }
+
+        fn username(&mut self, user: &str) -> Result<(), Error> {
+            self.username = Some(user.to_owned());
+            Ok(())
+        }
+
+        fn password(&mut self, pass: &str) -> Result<(), Error> {
+            self.password = Some(pass.to_owned());
+            Ok(())
+        }
+
+        fn http_headers(&mut self, list: List) -> Result<(), Error> {
+            self.headers = list
+                .iter()
+                .map(|h| String::from_utf8_lossy(h).into_owned())
+                .collect();
+            Ok(())
+        }
+    }
+
+    fn nrc() -> Netrc {
+        "\
+        machine host.domain.com login log password pass
+        machine localhost:8080 login local password pass
+        "
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_configure() {
+        let nrc = nrc();
+        let credentials = |url: &str| {
+            let mut handle = Recorder::default();
+            assert_eq!(
+                configure(&nrc, &mut handle, url).unwrap(),
+                handle.username.is_some()
+            );
+            assert_eq!(handle.url, url);
+            handle.username.zip(handle.password)
+        };
+
+        assert_eq!(
+            credentials("https://host.domain.com/api"),
+            Some((String::from("log"), String::from("pass")))
+        );
+        assert_eq!(credentials("http://host.domain.com/api"), None);
+        assert_eq!(
+            credentials("http://localhost:8080/api"),
+            Some((String::from("local"), String::from("pass")))
+        );
+        assert_eq!(credentials("https://other.com/api"), None);
+        assert_eq!(credentials("ftp://host.domain.com/file"), None);
+        assert_eq!(credentials("smtp://host.domain.com/"), None);
+    }
+
+    #[test]
+    fn test_configure_bearer() {
+        let nrc = nrc();
+        let mut headers = List::new();
+        headers.append("Accept: application/json").unwrap();
+        let mut handle = Recorder::default();
+
+        assert!(configure_bearer(&nrc, &mut handle, "https://host.domain.com/", headers).unwrap());
+        assert_eq!(
+            handle.headers,
+            vec!["Accept: application/json", "Authorization: Bearer pass"]
+        );
+
+        assert!(!configure_bearer(&nrc, &mut handle, "https://other.com/", List::new()).unwrap());
+        assert!(handle.headers.is_empty());
+    }
+}
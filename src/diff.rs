@@ -0,0 +1,118 @@
+//! Differences between two versions of a netrc, so that the consumers of a
+//! reloaded file only invalidate the machines which changed.
+
+use crate::Netrc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Machines added, removed or changed between two netrc, returned by
+/// [`Netrc::diff`] and `Netrc::reload`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct NetrcDiff {
+    /// Machines of the new netrc only, sorted.
+    pub added: Vec<String>,
+
+    /// Machines of the old netrc only, sorted.
+    pub removed: Vec<String>,
+
+    /// Machines of both netrc with a different authenticator, sorted.
+    pub changed: Vec<String>,
+
+    /// Whether the `default` entry was added, removed or changed.
+    pub default_changed: bool,
+}
+
+impl NetrcDiff {
+    /// Whether no machine nor the `default` entry changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && !self.default_changed
+    }
+
+    /// Whether the authenticator of a machine name, as found in
+    /// [`Netrc::hosts`], may have changed: the machines without entry use the
+    /// `default` entry.
+    pub fn affects(&self, name: &str) -> bool {
+        let listed = |names: &Vec<String>| names.iter().any(|n| n == name);
+        listed(&self.added)
+            || listed(&self.removed)
+            || listed(&self.changed)
+            || self.default_changed
+    }
+}
+
+impl Netrc {
+    /// Compare this netrc with a `new` version of it.
+    ///
+    /// Only the machines and the `default` entry are compared, not the
+    /// macros nor the locations of the entries.
+    ///
+    /// ```
+    /// use netrc::Netrc;
+    ///
+    /// let old: Netrc = "machine a login x machine b login y".parse().unwrap();
+    /// let new: Netrc = "machine b login z machine c login w".parse().unwrap();
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added, ["c"]);
+    /// assert_eq!(diff.removed, ["a"]);
+    /// assert_eq!(diff.changed, ["b"]);
+    /// assert!(!diff.default_changed);
+    /// ```
+    pub fn diff(&self, new: &Netrc) -> NetrcDiff {
+        let mut diff = NetrcDiff::default();
+        for (name, auth) in &new.hosts {
+            match self.hosts.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(old) if old != auth => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .hosts
+            .keys()
+            .filter(|name| !new.hosts.contains_key(*name))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff.default_changed = self.default_entry != new.default_entry;
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn test_diff() {
+        let data = "machine a login x\nmachine b login y password p\nmachine c login z\n\
+                    default login d";
+        let old = Netrc::from_str(data).unwrap();
+        assert!(old.diff(&Netrc::from_str(data).unwrap()).is_empty());
+
+        let new = Netrc::from_str(
+            "machine e login v\nmachine c login z\nmachine b login y password q\nmachine d",
+        )
+        .unwrap();
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["d", "e"]);
+        assert_eq!(diff.removed, ["a"]);
+        assert_eq!(diff.changed, ["b"]);
+        assert!(diff.default_changed);
+        assert!(!diff.is_empty());
+        assert!(diff.affects("b"));
+        assert!(diff.affects("unknown"));
+
+        let diff = new.diff(&Netrc::from_str("machine c login z").unwrap());
+        assert_eq!(diff.removed, ["b", "d", "e"]);
+        assert!(!diff.default_changed);
+        assert!(diff.affects("e"));
+        assert!(!diff.affects("c"));
+        assert!(!diff.affects("unknown"));
+    }
+}
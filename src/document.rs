@@ -0,0 +1,1157 @@
+//! Format-preserving representation of a netrc file.
+//!
+//! Unlike [`Netrc`], a [`NetrcDocument`] keeps the comments, blank lines and
+//! spacing of the source, so that a single value can be updated and the file
+//! written back without touching the rest of the text.
+
+use crate::lex::Lex;
+use crate::netrc::{
+    follower_field, is_entry_start, quote, read_macro, skip_comment, skip_value, ParsingError,
+};
+use crate::{normalize_host, Authenticator, CompatMode, Format, Netrc, ParseOptions};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A field (`login`, `account`, `password` or an extra token) of a machine
+/// entry.
+#[derive(Debug, Clone)]
+struct Field {
+    /// Canonical key (`user` is stored as `login`).
+    key: String,
+    value: String,
+    /// Start of the key token in the entry text.
+    start: usize,
+    /// End of the key token in the entry text.
+    key_end: usize,
+    /// Location of the value token in the entry text.
+    value_start: usize,
+    value_end: usize,
+}
+
+/// A `machine` or `default` entry of a netrc document.
+#[derive(Debug, Clone)]
+pub struct MachineEntry {
+    /// Machine name, normalized like in `Netrc`, `None` for the `default`
+    /// entry.
+    name: Option<String>,
+    /// Source text of the entry, from the keyword to the last value.
+    raw: String,
+    /// End of the machine name (or of the `default` keyword) in the entry
+    /// text.
+    name_end: usize,
+    fields: Vec<Field>,
+    format: Format,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    /// Whitespace and comments between the entries.
+    Trivia(String),
+    Machine(MachineEntry),
+    Macro {
+        name: String,
+        lines: Vec<String>,
+        raw: String,
+    },
+}
+
+/// A netrc file that preserves the formatting of the source.
+///
+/// ```
+/// use netrc::NetrcDocument;
+///
+/// let mut doc: NetrcDocument = "machine host.domain.com login log   password pass # my server\n"
+///     .parse()
+///     .unwrap();
+///
+/// doc.machine_mut("host.domain.com").unwrap().set("password", "new pass");
+///
+/// assert_eq!(
+///     doc.to_string(),
+///     "machine host.domain.com login log   password \"new pass\" # my server\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NetrcDocument {
+    items: Vec<Item>,
+    options: ParseOptions,
+}
+
+/// Layout of the entries of a reformatted [`NetrcDocument`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Layout {
+    /// An entry per line, like `machine host login log password pass`.
+    OneLine,
+
+    /// A field per line, indented with a tab, and a blank line between the
+    /// entries.
+    #[default]
+    MultiLine,
+}
+
+fn canonical_key(key: &str) -> &str {
+    match key {
+        "user" => "login",
+        _ => key,
+    }
+}
+
+/// Fields of an authenticator defined by the format, without the extras.
+fn known_fields(auth: &Authenticator, format: Format) -> Vec<(&'static str, &String)> {
+    let mut fields = vec![
+        ("login", &auth.login),
+        ("account", &auth.account),
+        ("password", &auth.password),
+    ];
+    if format == Format::Authinfo {
+        fields.push(("port", &auth.port));
+        fields.push(("protocol", &auth.protocol));
+    }
+    fields
+}
+
+impl MachineEntry {
+    /// Name of the machine, `default` for the default entry.
+    ///
+    /// The name is normalized with [`normalize_host`] unless
+    /// [`ParseOptions::normalize_hosts`] is disabled.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+
+    /// Whether this is the `default` entry.
+    pub fn is_default(&self) -> bool {
+        self.name.is_none()
+    }
+
+    /// Get the value of a field (`login`, `account`, `password` or an extra
+    /// token).
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let key = canonical_key(key);
+        self.fields
+            .iter()
+            .rev()
+            .find(|f| f.key == key)
+            .map(|f| f.value.as_str())
+    }
+
+    /// Set the value of a field, appending it to the entry if it is missing.
+    ///
+    /// Only the text of the value is replaced when the field already exists.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let key = canonical_key(key);
+        let quoted = quote(value);
+
+        match self.fields.iter().rposition(|f| f.key == key) {
+            Some(i) => {
+                let field = &self.fields[i];
+                // An empty value at the end of the file has no token.
+                let sep = if field.value_start == field.key_end {
+                    " "
+                } else {
+                    ""
+                };
+                let (start, end) = (field.value_start, field.value_end);
+                self.replace(start, end, &format!("{}{}", sep, quoted));
+                let field = &mut self.fields[i];
+                field.value = value.to_owned();
+                field.value_start = start + sep.len();
+                field.value_end = field.value_start + quoted.len();
+            }
+            None => {
+                let sep = match self.raw.rfind('\n') {
+                    Some(i) => {
+                        let last_line = &self.raw[i + 1..];
+                        let indent = last_line.len() - last_line.trim_start().len();
+                        format!("\n{}", &last_line[..indent])
+                    }
+                    None => String::from(" "),
+                };
+                let start = self.raw.len() + sep.len();
+                self.raw.push_str(&format!("{}{} {}", sep, key, quoted));
+                self.fields.push(Field {
+                    key: key.to_owned(),
+                    value: value.to_owned(),
+                    start,
+                    key_end: start + key.len(),
+                    value_start: start + key.len() + 1,
+                    value_end: self.raw.len(),
+                });
+            }
+        }
+    }
+
+    /// Remove a field from the entry and return its value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let key = canonical_key(key);
+        let mut value = None;
+        while let Some(i) = self.fields.iter().rposition(|f| f.key == key) {
+            let field = self.fields.remove(i);
+            let start = self.raw[..field.start].trim_end().len();
+            self.replace(start, field.value_end, "");
+            value.get_or_insert(field.value);
+        }
+        value
+    }
+
+    /// Build the authenticator defined by this entry.
+    pub fn authenticator(&self) -> Authenticator {
+        let mut auth = Authenticator::default();
+        for field in self.fields.iter() {
+            let value = field.value.clone();
+            match field.key.as_str() {
+                "login" => auth.login = value,
+                "account" => auth.account = value,
+                "password" => auth.password = value,
+                "port" if self.format == Format::Authinfo => auth.port = value,
+                "protocol" if self.format == Format::Authinfo => auth.protocol = value,
+                key => {
+                    auth.extras.insert(key.to_owned(), value);
+                }
+            }
+        }
+        auth
+    }
+
+    /// Fields of the entry in the canonical order (`login`, `account`,
+    /// `password`, `port`, `protocol`, then the extras in source order), with
+    /// the last value of the fields defined several times.
+    fn canonical_fields(&self) -> Vec<(&str, &str)> {
+        let mut fields: Vec<(&str, &str)> = Vec::new();
+        for field in self.fields.iter() {
+            match fields.iter_mut().find(|(key, _)| *key == field.key) {
+                Some(f) => f.1 = &field.value,
+                None => fields.push((&field.key, &field.value)),
+            }
+        }
+        let rank = |key: &str| {
+            ["login", "account", "password", "port", "protocol"]
+                .iter()
+                .position(|k| *k == key)
+                .unwrap_or(usize::MAX)
+        };
+        // The sort is stable, so the extras keep their order.
+        fields.sort_by_key(|(key, _)| rank(key));
+        fields
+    }
+
+    /// Comments between the tokens of the entry.
+    fn comments(&self) -> Vec<&str> {
+        let mut gaps = Vec::new();
+        let mut last = self.name_end;
+        for field in self.fields.iter() {
+            gaps.push(&self.raw[last..field.start]);
+            last = field.value_end.max(field.key_end);
+        }
+        gaps.into_iter()
+            .flat_map(str::lines)
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    /// Text of the entry in the given layout.
+    fn formatted(&self, layout: Layout) -> String {
+        let mut text = match &self.name {
+            Some(name) => format!("machine {}", quote(name)),
+            None => String::from("default"),
+        };
+        let sep = match layout {
+            Layout::OneLine => " ",
+            Layout::MultiLine => "\n\t",
+        };
+        for (key, value) in self.canonical_fields() {
+            text.push_str(&format!("{}{} {}", sep, quote(key), quote(value)));
+        }
+        text
+    }
+
+    /// Replace `raw[start..end]` and shift the fields located after it.
+    fn replace(&mut self, start: usize, end: usize, text: &str) {
+        self.raw.replace_range(start..end, text);
+        let shift = |pos: &mut usize| {
+            if *pos >= end && *pos > start {
+                *pos = *pos + text.len() - (end - start);
+            }
+        };
+        for field in self.fields.iter_mut() {
+            shift(&mut field.start);
+            shift(&mut field.key_end);
+            shift(&mut field.value_start);
+            shift(&mut field.value_end);
+        }
+    }
+}
+
+impl NetrcDocument {
+    /// Parse a document with the given options.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> core::result::Result<Self, ParsingError> {
+        // Report the errors exactly like `Netrc`.
+        Netrc::parse_with(s, options)?;
+        Ok(NetrcDocument {
+            items: parse_items(s, options)?,
+            options: options.clone(),
+        })
+    }
+
+    /// Iterate over the `machine` and `default` entries in source order.
+    pub fn machines(&self) -> impl Iterator<Item = &MachineEntry> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Machine(m) => Some(m),
+            _ => None,
+        })
+    }
+
+    /// Get the entry of a machine (`default` for the default entry).
+    ///
+    /// If the machine is defined several times, the last definition is
+    /// returned, as it is the one used by [`Netrc`].
+    pub fn machine(&self, name: &str) -> Option<&MachineEntry> {
+        let name = self.key(name);
+        self.machines().filter(|m| m.name() == name).last()
+    }
+
+    /// Get a mutable reference to the entry of a machine.
+    pub fn machine_mut(&mut self, name: &str) -> Option<&mut MachineEntry> {
+        let name = self.key(name);
+        self.items
+            .iter_mut()
+            .filter_map(|item| match item {
+                Item::Machine(m) if m.name() == name => Some(m),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Insert or update a machine entry.
+    ///
+    /// An existing entry is updated in place. A new entry is added before the
+    /// `default` entry, or at the end of the document if there is none.
+    pub fn insert(&mut self, name: &str, auth: &Authenticator) -> &mut MachineEntry {
+        if self.machine(name).is_none() {
+            let mut text = match name {
+                "default" => String::from("default"),
+                _ => format!("machine {}", quote(name)),
+            };
+            for (key, value) in known_fields(auth, self.options.format) {
+                if !value.is_empty() {
+                    text.push_str(&format!("\n\t{} {}", key, quote(value)));
+                }
+            }
+            let mut extras: Vec<_> = auth.extras.iter().collect();
+            extras.sort();
+            for (key, value) in extras {
+                text.push_str(&format!("\n\t{} {}", quote(key), quote(value)));
+            }
+            let entry = match parse_items(&text, &self.options)
+                .expect("generated entry is valid")
+                .swap_remove(0)
+            {
+                Item::Machine(m) => m,
+                _ => unreachable!(),
+            };
+
+            let default = self
+                .items
+                .iter()
+                .position(|item| matches!(item, Item::Machine(m) if m.is_default()));
+            match default {
+                Some(i) if name != "default" => {
+                    self.items.insert(i, Item::Trivia(String::from("\n")));
+                    self.items.insert(i, Item::Machine(entry));
+                }
+                _ => {
+                    let text = self.to_string();
+                    if !text.is_empty() && !text.ends_with('\n') {
+                        self.items.push(Item::Trivia(String::from("\n")));
+                    }
+                    self.items.push(Item::Machine(entry));
+                    self.items.push(Item::Trivia(String::from("\n")));
+                }
+            }
+            return self.machine_mut(name).unwrap();
+        }
+
+        let format = self.options.format;
+        let entry = self.machine_mut(name).unwrap();
+        let fields = known_fields(auth, format);
+        for (key, value) in fields.iter() {
+            if value.is_empty() {
+                entry.remove(key);
+            } else if entry.get(key) != Some(value.as_str()) {
+                entry.set(key, value);
+            }
+        }
+        let extras: Vec<_> = entry
+            .fields
+            .iter()
+            .filter(|f| !fields.iter().any(|(key, _)| f.key == *key))
+            .map(|f| f.key.clone())
+            .collect();
+        for key in extras {
+            if !auth.extras.contains_key(&key) {
+                entry.remove(&key);
+            }
+        }
+        for (key, value) in auth.extras.iter() {
+            if entry.get(key) != Some(value) {
+                entry.set(key, value);
+            }
+        }
+        entry
+    }
+
+    /// Remove all the entries of a machine and return its authenticator.
+    pub fn remove(&mut self, name: &str) -> Option<Authenticator> {
+        let auth = self.machine(name).map(|m| m.authenticator());
+        let name = self.key(name);
+        while let Some(i) = self
+            .items
+            .iter()
+            .position(|item| matches!(item, Item::Machine(m) if m.name() == name))
+        {
+            self.items.remove(i);
+            // Drop the end of the line of the removed entry.
+            if let Some(Item::Trivia(t)) = self.items.get_mut(i) {
+                let rest = t.trim_start_matches([' ', '\t']);
+                if let Some(rest) = rest.strip_prefix('\n') {
+                    *t = rest.to_owned();
+                }
+            }
+        }
+        auth
+    }
+}
+
+impl NetrcDocument {
+    /// Rewrite the document in a canonical layout, with the tokens quoted
+    /// when necessary.
+    ///
+    /// The order of the entries and the comments are kept, the comments
+    /// inside an entry being moved before it. The blank lines are collapsed,
+    /// and the fields of the entries are sorted like `login`, `account`,
+    /// `password`, then the other fields.
+    ///
+    /// ```
+    /// use netrc::{Layout, NetrcDocument};
+    ///
+    /// let mut doc: NetrcDocument = "\
+    /// ## servers
+    /// machine host.domain.com  password \"my pass\"
+    ///     login log   # main server
+    ///
+    ///
+    /// machine other.com login other password pass
+    /// "
+    /// .parse()
+    /// .unwrap();
+    ///
+    /// doc.reformat(Layout::OneLine);
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "\
+    /// ## servers
+    /// machine host.domain.com login log password \"my pass\" # main server
+    ///
+    /// machine other.com login other password pass
+    /// "
+    /// );
+    /// ```
+    pub fn reformat(&mut self, layout: Layout) {
+        let mut out = Writer::default();
+        for item in self.items.iter() {
+            match item {
+                Item::Trivia(raw) => {
+                    let mut segments = raw.split('\n');
+                    // The end of the line of the previous item.
+                    let first = segments.next().unwrap_or_default().trim();
+                    if !first.is_empty() {
+                        out.trailing_comment(first);
+                    }
+                    let segments: Vec<_> = segments.collect();
+                    for (i, segment) in segments.iter().enumerate() {
+                        let segment = segment.trim();
+                        if !segment.is_empty() {
+                            out.comment(segment);
+                        } else if i + 1 < segments.len() {
+                            out.blank = true;
+                        }
+                    }
+                }
+                Item::Machine(m) => {
+                    for comment in m.comments() {
+                        out.comment(comment);
+                    }
+                    if out.after_entry && layout == Layout::MultiLine {
+                        out.blank = true;
+                    }
+                    out.line(m.formatted(layout));
+                    out.after_entry = true;
+                }
+                Item::Macro {
+                    name, lines: body, ..
+                } => {
+                    if out.after_entry && layout == Layout::MultiLine {
+                        out.blank = true;
+                    }
+                    out.line(format!("macdef {}", quote(name)));
+                    for line in body.iter() {
+                        out.line(line.clone());
+                    }
+                    // The blank line ends the macro.
+                    out.blank = true;
+                }
+            }
+        }
+
+        let mut text = out.lines.join("\n");
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        self.items = parse_items(&text, &self.options).expect("reformatted document is valid");
+    }
+
+    /// Name of a machine as stored in the entries.
+    fn key(&self, name: &str) -> String {
+        match self.options.normalize_hosts {
+            true => normalize_host(name),
+            false => name.to_owned(),
+        }
+    }
+}
+
+/// Lines of a reformatted document.
+#[derive(Default)]
+struct Writer {
+    lines: Vec<String>,
+    /// A blank line is written before the next line.
+    blank: bool,
+    /// The last line is the end of an entry.
+    after_entry: bool,
+}
+
+impl Writer {
+    fn line(&mut self, line: String) {
+        if self.blank && !self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.blank = false;
+        self.after_entry = false;
+        self.lines.push(line);
+    }
+
+    /// Comment on its own line.
+    ///
+    /// Like in the Python module, a comment is only skipped if it starts
+    /// on the line of the previous token, so the comments following an
+    /// entry are not separated from it by a blank line, and the comments
+    /// following a quoted token are written at the end of its line.
+    fn comment(&mut self, comment: &str) {
+        if self.after_entry {
+            self.blank = false;
+            if self.lines.last().is_some_and(|line| line.ends_with('"')) {
+                return self.trailing_comment(comment);
+            }
+        }
+        self.line(comment.to_owned());
+    }
+
+    /// Comment at the end of the last line.
+    fn trailing_comment(&mut self, comment: &str) {
+        match self.lines.last_mut() {
+            Some(last) if self.after_entry => {
+                last.push(' ');
+                last.push_str(comment);
+            }
+            _ => self.line(comment.to_owned()),
+        }
+    }
+}
+
+impl From<&NetrcDocument> for Netrc {
+    fn from(doc: &NetrcDocument) -> Self {
+        let mut nrc = Netrc::default();
+        for item in doc.items.iter() {
+            match item {
+                Item::Machine(m) if m.is_default() => {
+                    nrc.default_entry = Some(m.authenticator());
+                }
+                Item::Machine(m) => {
+                    nrc.hosts.insert(m.name().to_owned(), m.authenticator());
+                }
+                Item::Macro { name, lines, .. } => {
+                    nrc.macros.insert(name.clone(), lines.clone());
+                }
+                Item::Trivia(_) => {}
+            }
+        }
+        nrc
+    }
+}
+
+impl core::fmt::Display for NetrcDocument {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for item in self.items.iter() {
+            match item {
+                Item::Trivia(raw)
+                | Item::Machine(MachineEntry { raw, .. })
+                | Item::Macro { raw, .. } => {
+                    f.write_str(raw)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::str::FromStr for NetrcDocument {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> core::result::Result<Self, ParsingError> {
+        NetrcDocument::parse_with(s, &ParseOptions::default())
+    }
+}
+
+/// Split a valid netrc source into entries and trivia.
+///
+/// The unknown tokens of the entries are considered as extra fields.
+fn parse_items(s: &str, options: &ParseOptions) -> core::result::Result<Vec<Item>, ParsingError> {
+    let mut items = Vec::new();
+    let mut lexer = Lex::new(s, options);
+    let mut last = 0;
+
+    let mut push = |items: &mut Vec<Item>, start: usize, end: usize, item: Item| {
+        if start > last {
+            items.push(Item::Trivia(s[last..start].to_owned()));
+        }
+        items.push(item);
+        last = end;
+    };
+
+    loop {
+        let saved_lineno = lexer.lineno;
+        let tt = lexer.get_token();
+        if tt.is_empty() {
+            break;
+        }
+        if skip_comment(&mut lexer, &tt, saved_lineno, true, options.compat) {
+            continue;
+        }
+        let start = lexer.span.start;
+
+        let name = match &*tt {
+            // The included files are not part of the document.
+            "include" if options.includes => {
+                lexer.get_token();
+                continue;
+            }
+            "machine" => Some(match lexer.get_token() {
+                name if options.normalize_hosts => normalize_host(&name),
+                name => name.into_owned(),
+            }),
+            "default" => None,
+            "macdef" => {
+                let name = lexer.get_token().into_owned();
+                let name_end = lexer.span.end;
+                let (lines, end) = read_macro(&mut lexer);
+                let end = end.unwrap_or(name_end);
+                let raw = s[start..end].to_owned();
+                push(&mut items, start, end, Item::Macro { name, lines, raw });
+                continue;
+            }
+            // The ignored tokens are trivia.
+            _ if options.lenient || options.compat != CompatMode::Python => continue,
+            _ => {
+                return Err(ParsingError::new(
+                    lexer.lineno,
+                    format!("bad toplevel token '{}'", tt),
+                ))
+            }
+        };
+        let mut end = lexer.span.end;
+        let name_end = end - start;
+
+        let mut fields = Vec::new();
+        loop {
+            let prev_lineno = lexer.lineno;
+            let tt = lexer.get_token();
+            if skip_comment(&mut lexer, &tt, prev_lineno, false, options.compat) {
+                continue;
+            }
+            if is_entry_start(&tt, options) {
+                lexer.push_token(tt);
+                break;
+            }
+            // The skipped tokens are trivia, inside the raw entry if other
+            // fields follow them.
+            let name = match follower_field(&tt, options) {
+                Some(field) => field,
+                None if options.extras => &tt,
+                None if options.lenient => {
+                    skip_value(&mut lexer, &tt, options);
+                    continue;
+                }
+                None if options.compat != CompatMode::Python => continue,
+                None => &tt,
+            };
+            let key = lexer.span;
+            let value = lexer.get_token();
+            let (value_start, value_end) = if lexer.span == key {
+                (key.end, key.end)
+            } else {
+                (lexer.span.start, lexer.span.end)
+            };
+            end = value_end;
+            fields.push(Field {
+                key: name.to_owned(),
+                value: value.into_owned(),
+                start: key.start - start,
+                key_end: key.end - start,
+                value_start: value_start - start,
+                value_end: value_end - start,
+            });
+        }
+
+        let raw = s[start..end].to_owned();
+        push(
+            &mut items,
+            start,
+            end,
+            Item::Machine(MachineEntry {
+                name,
+                raw,
+                name_end,
+                fields,
+                format: options.format,
+            }),
+        );
+    }
+
+    if last < s.len() {
+        items.push(Item::Trivia(s[last..].to_owned()));
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const CONTENT: &str = "\
+# Personal servers
+machine host.domain.com
+    login log  # the login
+    password pass
+
+machine other.domain.com login \"lo g\" password pass account acct
+
+macdef init
+cd /pub
+
+default login anonymous password me@
+";
+
+    #[test]
+    fn test_round_trip() {
+        let doc = NetrcDocument::from_str(CONTENT).unwrap();
+        assert_eq!(doc.to_string(), CONTENT);
+        assert_eq!(Netrc::from(&doc), Netrc::from_str(CONTENT).unwrap());
+    }
+
+    #[test]
+    fn test_get() {
+        let doc = NetrcDocument::from_str(CONTENT).unwrap();
+        let names: Vec<_> = doc.machines().map(|m| m.name()).collect();
+        assert_eq!(
+            names,
+            vec!["host.domain.com", "other.domain.com", "default"]
+        );
+
+        let m = doc.machine("other.domain.com").unwrap();
+        assert_eq!(m.get("user"), Some("lo g"));
+        assert_eq!(
+            m.authenticator(),
+            Authenticator::new("lo g", "acct", "pass")
+        );
+        assert!(doc.machine("default").unwrap().is_default());
+        assert!(doc.machine("unknown").is_none());
+    }
+
+    #[test]
+    fn test_set() {
+        let mut doc = NetrcDocument::from_str(CONTENT).unwrap();
+        let m = doc.machine_mut("host.domain.com").unwrap();
+        m.set("login", "new log");
+        m.set("password", "p");
+        m.set("account", "acct");
+        doc.machine_mut("default").unwrap().set("account", "a");
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+# Personal servers
+machine host.domain.com
+    login \"new log\"  # the login
+    password p
+    account acct
+
+machine other.domain.com login \"lo g\" password pass account acct
+
+macdef init
+cd /pub
+
+default login anonymous password me@ account a
+"
+        );
+    }
+
+    #[test]
+    fn test_set_empty_value() {
+        let mut doc = NetrcDocument::from_str("machine host.domain.com login").unwrap();
+        doc.machine_mut("host.domain.com")
+            .unwrap()
+            .set("login", "log");
+        doc.machine_mut("host.domain.com")
+            .unwrap()
+            .set("login", "other");
+        assert_eq!(doc.to_string(), "machine host.domain.com login other");
+    }
+
+    #[test]
+    fn test_remove_field() {
+        let mut doc = NetrcDocument::from_str(CONTENT).unwrap();
+        let m = doc.machine_mut("other.domain.com").unwrap();
+        assert_eq!(m.remove("password"), Some(String::from("pass")));
+        assert_eq!(m.remove("password"), None);
+        let m = doc.machine_mut("host.domain.com").unwrap();
+        assert_eq!(m.remove("password"), Some(String::from("pass")));
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+# Personal servers
+machine host.domain.com
+    login log  # the login
+
+machine other.domain.com login \"lo g\" account acct
+
+macdef init
+cd /pub
+
+default login anonymous password me@
+"
+        );
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut doc = NetrcDocument::from_str(CONTENT).unwrap();
+        doc.insert("new.domain.com", &Authenticator::new("log", "", "pass"));
+        doc.insert("other.domain.com", &Authenticator::new("lo g", "", "p"));
+        assert_eq!(
+            doc.remove("host.domain.com"),
+            Some(Authenticator::new("log", "", "pass"))
+        );
+        assert_eq!(doc.remove("host.domain.com"), None);
+
+        assert_eq!(
+            doc.to_string(),
+            "\
+# Personal servers
+
+machine other.domain.com login \"lo g\" password p
+
+macdef init
+cd /pub
+
+machine new.domain.com
+\tlogin log
+\tpassword pass
+default login anonymous password me@
+"
+        );
+        assert_eq!(
+            Netrc::from_str(&doc.to_string()).unwrap(),
+            Netrc::from(&doc)
+        );
+    }
+
+    #[test]
+    fn test_insert_empty() {
+        let mut doc = NetrcDocument::default();
+        doc.insert("host.domain.com", &Authenticator::new("log", "", "pass"));
+        doc.insert("default", &Authenticator::new("anonymous", "", ""));
+        assert_eq!(
+            doc.to_string(),
+            "\
+machine host.domain.com
+\tlogin log
+\tpassword pass
+default
+\tlogin anonymous
+"
+        );
+    }
+
+    #[test]
+    fn test_save() {
+        let fi = std::env::temp_dir().join("mynetrc-document");
+        std::fs::write(&fi, CONTENT).unwrap();
+        let mut doc = NetrcDocument::from_file(&fi).unwrap();
+        doc.machine_mut("default").unwrap().set("password", "pass");
+        doc.save(&fi).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&fi).unwrap(),
+            CONTENT.replace("password me@", "password pass")
+        );
+    }
+
+    #[test]
+    fn test_extras() {
+        let data = "machine host.domain.com login log port 8443\n";
+        assert!(NetrcDocument::from_str(data).is_err());
+
+        let options = ParseOptions {
+            extras: true,
+            ..Default::default()
+        };
+        let mut doc = NetrcDocument::parse_with(data, &options).unwrap();
+        assert_eq!(
+            Netrc::from(&doc),
+            Netrc::parse_with(data, &options).unwrap()
+        );
+
+        let m = doc.machine_mut("host.domain.com").unwrap();
+        assert_eq!(m.get("port"), Some("8443"));
+        m.set("port", "443");
+        let mut auth = Authenticator::new("log", "", "");
+        auth.extras
+            .insert("protocol".to_owned(), "https".to_owned());
+        doc.insert("host.domain.com", &auth);
+        assert_eq!(
+            doc.to_string(),
+            "machine host.domain.com login log protocol https\n"
+        );
+    }
+
+    #[test]
+    fn test_authinfo() {
+        let data = "machine imap.domain.com login log port imaps password pass\n";
+        let options = ParseOptions {
+            format: Format::Authinfo,
+            ..Default::default()
+        };
+        let mut doc = NetrcDocument::parse_with(data, &options).unwrap();
+        assert_eq!(
+            Netrc::from(&doc),
+            Netrc::parse_with(data, &options).unwrap()
+        );
+
+        let mut auth = doc.machine("imap.domain.com").unwrap().authenticator();
+        assert_eq!(auth.port, "imaps");
+        auth.port = String::from("993");
+        doc.insert("imap.domain.com", &auth);
+        auth.protocol = String::from("imap");
+        doc.insert("smtp.domain.com", &auth);
+        assert_eq!(
+            doc.to_string(),
+            "\
+machine imap.domain.com login log port 993 password pass
+machine smtp.domain.com
+\tlogin log
+\tpassword pass
+\tport 993
+\tprotocol imap
+"
+        );
+    }
+
+    #[test]
+    fn test_normalize_hosts() {
+        let data = "machine GitHub.com. login log\n";
+        let mut doc = NetrcDocument::from_str(data).unwrap();
+        assert_eq!(doc.machine("github.com").unwrap().name(), "github.com");
+        doc.machine_mut("GITHUB.com")
+            .unwrap()
+            .set("password", "pass");
+        assert_eq!(
+            doc.to_string(),
+            "machine GitHub.com. login log password pass\n"
+        );
+        assert_eq!(
+            Netrc::from(&doc),
+            Netrc::from_str(&doc.to_string()).unwrap()
+        );
+
+        let options = ParseOptions {
+            normalize_hosts: false,
+            ..Default::default()
+        };
+        let doc = NetrcDocument::parse_with(data, &options).unwrap();
+        assert!(doc.machine("github.com").is_none());
+        assert_eq!(doc.machine("GitHub.com.").unwrap().name(), "GitHub.com.");
+    }
+
+    #[test]
+    fn test_bom() {
+        let data = "\u{feff}machine host.domain.com login log\n";
+        let mut doc = NetrcDocument::from_str(data).unwrap();
+        doc.machine_mut("host.domain.com")
+            .unwrap()
+            .set("password", "pass");
+        assert_eq!(
+            doc.to_string(),
+            "\u{feff}machine host.domain.com login log password pass\n"
+        );
+    }
+
+    #[test]
+    fn test_crlf() {
+        let data =
+            "machine host.domain.com login log # comment\r\n\r\nmacdef init\r\ncd /pub\r\n\r\n";
+        let mut doc = NetrcDocument::from_str(data).unwrap();
+        assert_eq!(doc.to_string(), data);
+        doc.machine_mut("host.domain.com")
+            .unwrap()
+            .set("password", "pass");
+        assert_eq!(Netrc::from(&doc).hosts["host.domain.com"].password, "pass");
+    }
+
+    #[test]
+    fn test_compat() {
+        let data = "machine host.domain.com user me passwd pass # comment\n";
+        let options = ParseOptions {
+            compat: CompatMode::Bsd,
+            ..Default::default()
+        };
+        let mut doc = NetrcDocument::parse_with(data, &options).unwrap();
+        let machine = doc.machine_mut("host.domain.com").unwrap();
+        assert_eq!(machine.get("password"), Some("pass"));
+        assert_eq!(machine.get("comment"), None);
+        machine.set("password", "new");
+        assert_eq!(
+            doc.to_string(),
+            "machine host.domain.com user me passwd new # comment\n"
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let data = "machine host.domain.com port 22 login log vendor\nmachine other.com\n";
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut doc = NetrcDocument::parse_with(data, &options).unwrap();
+        assert_eq!(doc.to_string(), data);
+        let machine = doc.machine_mut("host.domain.com").unwrap();
+        assert_eq!(machine.get("port"), None);
+        machine.set("login", "new");
+        assert_eq!(
+            doc.to_string(),
+            "machine host.domain.com port 22 login new vendor\nmachine other.com\n"
+        );
+        assert_eq!(Netrc::from(&doc).hosts["host.domain.com"].login, "new");
+    }
+
+    #[test]
+    fn test_parsing_error() {
+        assert_eq!(
+            NetrcDocument::from_str("machine host.domain.com invalid")
+                .unwrap_err()
+                .to_string(),
+            "parsing error: bad follower token 'invalid' (line 1)"
+        );
+    }
+
+    #[test]
+    fn test_reformat() {
+        let data = "\
+# servers
+machine host.domain.com  password \"my pass\" # main
+    # account of the team
+    login log account team
+    login other
+
+
+
+default
+login anonymous password anonymous
+machine api.domain.com password \"my token\"
+machine other.com # other
+    login other password pass
+macdef init
+cd /pub
+binary
+
+# end
+";
+        let mut doc = NetrcDocument::from_str(data).unwrap();
+        doc.reformat(Layout::MultiLine);
+        let multi = "\
+# servers
+# main
+# account of the team
+machine host.domain.com
+\tlogin other
+\taccount team
+\tpassword \"my pass\"
+
+default
+\tlogin anonymous
+\tpassword anonymous
+
+machine api.domain.com
+\tpassword \"my token\" # other
+
+machine other.com
+\tlogin other
+\tpassword pass
+
+macdef init
+cd /pub
+binary
+
+# end
+";
+        assert_eq!(doc.to_string(), multi);
+        assert_eq!(
+            Netrc::from(&doc),
+            Netrc::from(&NetrcDocument::from_str(data).unwrap())
+        );
+
+        doc.reformat(Layout::MultiLine);
+        assert_eq!(doc.to_string(), multi);
+
+        doc.reformat(Layout::OneLine);
+        assert_eq!(
+            doc.to_string(),
+            "\
+# servers
+# main
+# account of the team
+machine host.domain.com login other account team password \"my pass\"
+
+default login anonymous password anonymous
+
+machine api.domain.com password \"my token\" # other
+
+machine other.com login other password pass
+
+macdef init
+cd /pub
+binary
+
+# end
+"
+        );
+
+        let mut doc = NetrcDocument::default();
+        doc.reformat(Layout::MultiLine);
+        assert_eq!(doc.to_string(), "");
+    }
+}
@@ -0,0 +1,202 @@
+//! Environment seen by the discovery of the netrc files, and the per-host
+//! environment variables.
+
+use crate::{normalize_host, Authenticator, LookupOptions, Netrc};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Source of the environment variables and of the home directory used to
+/// search the netrc files.
+///
+/// The default implementation, [`SystemEnv`], reads the environment of the
+/// process. Tests and sandboxed applications can provide their own, for
+/// example with a `HashMap<String, String>`:
+///
+/// ```
+/// use std::collections::HashMap;
+/// use netrc::{DiscoveryOptions, Netrc};
+///
+/// let env = HashMap::from([(String::from("HOME"), String::from("/nonexistent"))]);
+/// let options = DiscoveryOptions {
+///     require_exists: false,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     Netrc::discover_in(&options, &env),
+///     Some("/nonexistent/.netrc".into())
+/// );
+/// ```
+pub trait Env {
+    /// Value of the environment variable `key`.
+    fn var(&self, key: &str) -> Option<OsString>;
+
+    /// Home directory of the user.
+    ///
+    /// Use the `HOME` variable (`USERPROFILE` on windows) by default.
+    fn home_dir(&self) -> Option<PathBuf> {
+        #[cfg(windows)]
+        let home = self.var("USERPROFILE");
+
+        #[cfg(not(windows))]
+        let home = self.var("HOME");
+
+        home.map(PathBuf::from)
+    }
+}
+
+/// Environment of the process.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemEnv;
+
+impl Env for SystemEnv {
+    fn var(&self, key: &str) -> Option<OsString> {
+        std::env::var_os(key)
+    }
+}
+
+impl Env for HashMap<String, String> {
+    fn var(&self, key: &str) -> Option<OsString> {
+        self.get(key).map(OsString::from)
+    }
+}
+
+impl<E: Env + ?Sized> Env for &E {
+    fn var(&self, key: &str) -> Option<OsString> {
+        (**self).var(key)
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        (**self).home_dir()
+    }
+}
+
+/// Precedence of the per-host environment variables, see
+/// [`Netrc::resolve_with_env`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum EnvPrecedence {
+    /// The variables override the fields of the entry of the machine.
+    #[default]
+    Before,
+
+    /// The variables are only used for a machine without entry, before the
+    /// `default` entry.
+    After,
+}
+
+impl Netrc {
+    /// Get the authenticator of a machine like [`Netrc::resolve`], with the
+    /// `NETRC_<HOST>_LOGIN` and `NETRC_<HOST>_PASSWORD` variables of `env`.
+    ///
+    /// `<HOST>` is the normalized machine name in upper case, with the
+    /// characters other than the letters and the digits replaced by `_`, like
+    /// `NETRC_API_GITHUB_COM_PASSWORD` for `api.github.com`.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use netrc::{EnvPrecedence, Netrc};
+    ///
+    /// let nrc: Netrc = "machine api.github.com login log password pass".parse().unwrap();
+    /// let env = HashMap::from([(
+    ///     String::from("NETRC_API_GITHUB_COM_PASSWORD"),
+    ///     String::from("token"),
+    /// )]);
+    /// let auth = nrc
+    ///     .resolve_with_env("api.github.com", None, &env, EnvPrecedence::Before)
+    ///     .unwrap();
+    /// assert_eq!((auth.login.as_str(), auth.password.as_str()), ("log", "token"));
+    /// ```
+    pub fn resolve_with_env(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        env: &dyn Env,
+        precedence: EnvPrecedence,
+    ) -> Option<Authenticator> {
+        let login = host_var(env, host, "LOGIN");
+        let password = host_var(env, host, "PASSWORD");
+        if login.is_none() && password.is_none() {
+            return self.resolve(host, port);
+        }
+
+        let options = LookupOptions {
+            default_entry: false,
+            ..Default::default()
+        };
+        let entry = self.resolve_with(host, port, &options);
+        if precedence == EnvPrecedence::After && entry.is_some() {
+            return entry;
+        }
+        let mut auth = match precedence {
+            EnvPrecedence::Before => entry.unwrap_or_default(),
+            EnvPrecedence::After => Authenticator::default(),
+        };
+        if let Some(login) = login {
+            auth.login = login;
+        }
+        if let Some(password) = password {
+            auth.password = password;
+        }
+        Some(auth)
+    }
+}
+
+/// Value of the `NETRC_<HOST>_<FIELD>` variable.
+pub(crate) fn host_var(env: &dyn Env, host: &str, field: &str) -> Option<String> {
+    let host: String = normalize_host(host)
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect();
+    env.var(&format!("NETRC_{}_{}", host, field))
+        .and_then(|v| v.into_string().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_resolve_with_env() {
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com login log1 password pass1
+            default login anonymous password me@
+            ",
+        )
+        .unwrap();
+        let env = HashMap::from([
+            (
+                String::from("NETRC_HOST_DOMAIN_COM_PASSWORD"),
+                String::from("pass2"),
+            ),
+            (String::from("NETRC_OTHER_COM_LOGIN"), String::from("log3")),
+        ]);
+        let resolve = |host, precedence| {
+            nrc.resolve_with_env(host, None, &env, precedence)
+                .map(|auth| (auth.login.clone(), auth.password.clone()))
+        };
+        let auth = |login: &str, password: &str| Some((login.to_owned(), password.to_owned()));
+
+        assert_eq!(
+            resolve("Host.Domain.com", EnvPrecedence::Before),
+            auth("log1", "pass2")
+        );
+        assert_eq!(
+            resolve("host.domain.com", EnvPrecedence::After),
+            auth("log1", "pass1")
+        );
+        assert_eq!(
+            resolve("other.com", EnvPrecedence::Before),
+            auth("log3", "")
+        );
+        assert_eq!(resolve("other.com", EnvPrecedence::After), auth("log3", ""));
+        assert_eq!(
+            resolve("domain.com", EnvPrecedence::After),
+            auth("anonymous", "me@")
+        );
+    }
+}
@@ -0,0 +1,1082 @@
+//! Netrc files: discovery in the environment, reading and saving.
+//!
+//! Not available on `wasm32-unknown-unknown`, which has neither a filesystem
+//! nor environment variables.
+
+#[cfg(feature = "gpg")]
+use crate::gpg;
+use crate::netrc::read_limited;
+#[cfg(feature = "sops")]
+use crate::sops;
+use crate::{
+    Env, Error, Format, MergeStrategy, Netrc, NetrcDiff, NetrcDocument, ParseOptions, Result,
+    SystemEnv,
+};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+impl Netrc {
+    /// Create a new `Netrc` object.
+    ///
+    /// Look up the `NETRC` environment variable if it is defined else that the
+    /// default `~/.netrc` file (see [`Netrc::get_files`]). The fragments of the
+    /// netrc.d directory (see [`Netrc::get_dir`]) are merged too, with a lower
+    /// precedence than the netrc files.
+    pub fn new() -> Result<Self> {
+        Self::new_in(&SystemEnv)
+    }
+
+    /// Create a new `Netrc` object like [`Netrc::new`], with the environment
+    /// variables and the home directory of `env` instead of the process ones.
+    pub fn new_in(env: &dyn Env) -> Result<Self> {
+        let files = files_in(env);
+        let dir = dir_in(env);
+        if files.is_empty() && dir.is_none() {
+            return Err(Error::Io(io::Error::new(
+                ErrorKind::NotFound,
+                "no netrc file found",
+            )));
+        }
+
+        let mut nrc = Netrc::default();
+        for file in files {
+            nrc.merge(Netrc::from_file(&file)?, MergeStrategy::PreferSelf)?;
+        }
+        if let Some(dir) = dir {
+            nrc.merge(Netrc::from_dir(&dir)?, MergeStrategy::PreferSelf)?;
+        }
+        Ok(nrc)
+    }
+
+    /// Create a new `Netrc` object like [`Netrc::new`], with the system-wide
+    /// netrc file (see [`Netrc::get_system_file`]) as fallback.
+    ///
+    /// The entries of the system file have the lowest precedence.
+    pub fn new_with_system() -> Result<Self> {
+        let system = Self::get_system_file();
+        match (Self::new(), system) {
+            (Ok(mut nrc), Some(system)) => {
+                nrc.merge(Netrc::from_file(&system)?, MergeStrategy::PreferSelf)?;
+                Ok(nrc)
+            }
+            (Err(Error::Io(e)), Some(system)) if e.kind() == ErrorKind::NotFound => {
+                Netrc::from_file(&system)
+            }
+            (res, _) => res,
+        }
+    }
+
+    /// Create a new `Netrc` object from a file.
+    pub fn from_file(file: &Path) -> Result<Self> {
+        Netrc::from_file_with(file, &ParseOptions::default())
+    }
+
+    /// Create a new `Netrc` object from the `*.netrc` files of a directory.
+    ///
+    /// The files are merged in the order of their names, the entries of a file
+    /// taking precedence over the ones of the previous files.
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let mut files = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        files.retain(|f| f.extension().is_some_and(|ext| ext == "netrc") && f.is_file());
+        files.sort();
+
+        let mut nrc = Netrc::default();
+        for file in files {
+            nrc.merge(Netrc::from_file(&file)?, MergeStrategy::PreferOther)?;
+        }
+        Ok(nrc)
+    }
+
+    /// Create a new `Netrc` object from a file, parsed with the given options.
+    ///
+    /// With the `gpg` feature, a file with the `.gpg` extension is decrypted
+    /// first. With the `sops` feature, a file encrypted by SOPS is decrypted
+    /// first.
+    pub fn from_file_with(file: &Path, options: &ParseOptions) -> Result<Self> {
+        #[cfg(feature = "gpg")]
+        let content = match gpg::is_encrypted(file) {
+            true => gpg::decrypt(file, &options.limits)?,
+            false => read_limited(file, &options.limits)?,
+        };
+
+        #[cfg(not(feature = "gpg"))]
+        let content = read_limited(file, &options.limits)?;
+
+        #[cfg(feature = "sops")]
+        let content = match sops::is_encrypted(&content) {
+            true => sops::decrypt(file)?,
+            false => content,
+        };
+
+        let mut includes = vec![fs::canonicalize(file)?];
+        let res = options
+            .limits
+            .check_size(content.len())
+            .and_then(|_| options.encoding.decode(&content))
+            .and_then(|source| {
+                let res = Netrc::parse_source(
+                    &source,
+                    options,
+                    &mut Vec::new(),
+                    Some(file),
+                    &mut includes,
+                );
+
+                #[cfg(feature = "zeroize")]
+                if let std::borrow::Cow::Owned(mut source) = source {
+                    use zeroize::Zeroize;
+
+                    source.zeroize();
+                }
+
+                res
+            });
+
+        #[cfg(feature = "zeroize")]
+        {
+            use zeroize::Zeroize;
+
+            let mut content = content;
+            content.zeroize();
+        }
+
+        let nrc = res.map_err(|e| Error::Parsing {
+            parser: e,
+            filename: file.display().to_string(),
+        })?;
+        if options.check_permissions {
+            check_permissions(file, &nrc)?;
+        }
+        Ok(nrc)
+    }
+
+    /// Read a file again, like with [`Netrc::from_file`], and return the
+    /// machines which changed since this netrc.
+    ///
+    /// The netrc is left unchanged if the file cannot be read or parsed.
+    ///
+    /// ```no_run
+    /// use netrc::Netrc;
+    /// use std::path::Path;
+    ///
+    /// let file = Path::new("/home/me/.netrc");
+    /// let mut nrc = Netrc::from_file(file).unwrap();
+    /// // ... once the file changed, like notified by `Netrc::watch`:
+    /// let diff = nrc.reload(file).unwrap();
+    /// for name in diff.changed.iter().chain(&diff.removed) {
+    ///     println!("close the connections to {}", name);
+    /// }
+    /// ```
+    pub fn reload(&mut self, file: &Path) -> Result<NetrcDiff> {
+        self.reload_with(file, &ParseOptions::default())
+    }
+
+    /// Read a file again like [`Netrc::reload`], parsed with the given
+    /// options.
+    pub fn reload_with(&mut self, file: &Path, options: &ParseOptions) -> Result<NetrcDiff> {
+        let nrc = Netrc::from_file_with(file, options)?;
+        let diff = self.diff(&nrc);
+        *self = nrc;
+        Ok(diff)
+    }
+
+    /// Save the netrc to a file.
+    ///
+    /// The file is replaced atomically: the content is written to a temporary
+    /// file in the same directory, private to the user (with the `0600`
+    /// permissions on Unix, and only accessible by its owner on Windows), and
+    /// then renamed.
+    pub fn save(&self, file: &Path) -> Result<()> {
+        write_file(file, self.to_string().as_bytes())
+    }
+
+    /// Create a new `Netrc` object from the authinfo file of the user.
+    ///
+    /// See [`Netrc::get_authinfo_file`].
+    pub fn authinfo() -> Result<Self> {
+        let options = ParseOptions {
+            format: Format::Authinfo,
+            ..Default::default()
+        };
+        Self::get_authinfo_file()
+            .ok_or(Error::Io(io::Error::new(
+                ErrorKind::NotFound,
+                "no authinfo file found",
+            )))
+            .and_then(|f| Netrc::from_file_with(f.as_path(), &options))
+    }
+
+    /// Search an authinfo file.
+    ///
+    /// Use the .authinfo file in the user's home directory, or the
+    /// .authinfo.gpg file with the `gpg` feature.
+    pub fn get_authinfo_file() -> Option<PathBuf> {
+        #[cfg(feature = "gpg")]
+        let filenames = [".authinfo", ".authinfo.gpg"];
+
+        #[cfg(not(feature = "gpg"))]
+        let filenames = [".authinfo"];
+
+        let home = SystemEnv.home_dir()?;
+        filenames
+            .into_iter()
+            .map(|name| home.join(name))
+            .find(|f| f.exists())
+    }
+
+    /// Search the directory of the netrc fragments.
+    ///
+    /// Use the `netrc.d` directory in `$XDG_CONFIG_HOME` or `~/.config`
+    /// (`%APPDATA%` on windows).
+    pub fn get_dir() -> Option<PathBuf> {
+        dir_in(&SystemEnv)
+    }
+
+    /// Search the system-wide netrc file.
+    ///
+    /// Use `/etc/netrc` (`%ProgramData%\netrc` on windows).
+    pub fn get_system_file() -> Option<PathBuf> {
+        system_file(&SystemEnv).filter(|f| f.exists())
+    }
+
+    /// Search a netrc file.
+    ///
+    /// Return the first file of [`Netrc::get_files`].
+    pub fn get_file() -> Option<PathBuf> {
+        Self::discover(&DiscoveryOptions::default())
+    }
+
+    /// Search the netrc files, by order of precedence.
+    ///
+    /// Look up the `NETRC` environment variable if it is defined, as a list of
+    /// paths separated by `:` (`;` on windows) like `PATH`. If none of these
+    /// files exists, use the .netrc (or _netrc file on windows) in the user's
+    /// home directory.
+    pub fn get_files() -> Vec<PathBuf> {
+        files_in(&SystemEnv)
+    }
+
+    /// Search a netrc file with the given options, and return the chosen path.
+    ///
+    /// The candidates are, in this order, the files of the `NETRC`
+    /// environment variable, the [`paths`](DiscoveryOptions::paths), the
+    /// [`filenames`](DiscoveryOptions::filenames) in the user's home
+    /// directory, and the system-wide file.
+    pub fn discover(options: &DiscoveryOptions) -> Option<PathBuf> {
+        Self::discover_in(options, &SystemEnv)
+    }
+
+    /// Search a netrc file like [`Netrc::discover`], with the environment
+    /// variables and the home directory of `env`.
+    pub fn discover_in(options: &DiscoveryOptions, env: &dyn Env) -> Option<PathBuf> {
+        let env_var = if options.env {
+            env_files(env)
+        } else {
+            Vec::new()
+        };
+        let home = env
+            .home_dir()
+            .into_iter()
+            .flat_map(|home| options.filenames.iter().map(move |name| home.join(name)));
+        let system = if options.system {
+            system_file(env)
+        } else {
+            None
+        };
+        let mut candidates = env_var
+            .into_iter()
+            .chain(options.paths.iter().cloned())
+            .chain(home)
+            .chain(system);
+
+        if options.require_exists {
+            candidates.find(|f| f.exists())
+        } else {
+            candidates.next()
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Netrc {
+    /// Create a new `Netrc` object like [`Netrc::new`], without blocking the
+    /// runtime of `tokio`.
+    ///
+    /// Like `tokio::fs`, the files are searched and read on the blocking
+    /// thread pool of the runtime, with the decryption of the `gpg` and `sops`
+    /// features and the included files.
+    pub async fn new_async() -> Result<Self> {
+        Netrc::new_async_in(SystemEnv).await
+    }
+
+    /// Create a new `Netrc` object like [`Netrc::new_async`], with the
+    /// environment variables and the home directory of `env` (see
+    /// [`Netrc::new_in`]).
+    pub async fn new_async_in<E: Env + Send + 'static>(env: E) -> Result<Self> {
+        blocking(move || Netrc::new_in(&env)).await
+    }
+
+    /// Create a new `Netrc` object from a file like [`Netrc::from_file`],
+    /// without blocking the runtime of `tokio` (see [`Netrc::new_async`]).
+    pub async fn from_file_async(file: &Path) -> Result<Self> {
+        Netrc::from_file_with_async(file, &ParseOptions::default()).await
+    }
+
+    /// Create a new `Netrc` object from a file like
+    /// [`Netrc::from_file_with`], without blocking the runtime of `tokio`
+    /// (see [`Netrc::new_async`]).
+    pub async fn from_file_with_async(file: &Path, options: &ParseOptions) -> Result<Self> {
+        let file = file.to_path_buf();
+        let options = options.clone();
+        blocking(move || Netrc::from_file_with(&file, &options)).await
+    }
+}
+
+/// Run `f` on the blocking thread pool of `tokio`.
+#[cfg(feature = "tokio")]
+async fn blocking<F>(f: F) -> Result<Netrc>
+where
+    F: FnOnce() -> Result<Netrc> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Io(io::Error::other(e)))?
+}
+
+impl NetrcDocument {
+    /// Create a new `NetrcDocument` object from a file.
+    pub fn from_file(file: &Path) -> Result<Self> {
+        NetrcDocument::from_file_with(file, &ParseOptions::default())
+    }
+
+    /// Create a new `NetrcDocument` object from a file, parsed with the given
+    /// options.
+    pub fn from_file_with(file: &Path, options: &ParseOptions) -> Result<Self> {
+        let content = read_limited(file, &options.limits)?;
+        options
+            .limits
+            .check_size(content.len())
+            .and_then(|_| options.encoding.decode(&content))
+            .and_then(|source| NetrcDocument::parse_with(&source, options))
+            .map_err(|e| Error::Parsing {
+                parser: e,
+                filename: file.display().to_string(),
+            })
+    }
+
+    /// Save the document to a file, with the same guarantees as
+    /// [`Netrc::save`].
+    pub fn save(&self, file: &Path) -> Result<()> {
+        write_file(file, self.to_string().as_bytes())
+    }
+}
+
+/// Options of [`Netrc::discover`].
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// Look up the `NETRC` environment variable (enabled by default).
+    pub env: bool,
+
+    /// Additional candidate files.
+    pub paths: Vec<PathBuf>,
+
+    /// Names of the files searched in the user's home directory (`.netrc`,
+    /// and `_netrc` on windows, by default). With the `gpg` feature, the
+    /// encrypted `.gpg` files are searched after each one.
+    pub filenames: Vec<String>,
+
+    /// Choose the first existing file (enabled by default). If disabled, the
+    /// first candidate is chosen, for example to create it.
+    pub require_exists: bool,
+
+    /// Fall back to the system-wide netrc file (see
+    /// [`Netrc::get_system_file`]).
+    pub system: bool,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        #[cfg(windows)]
+        let filenames = vec![String::from(".netrc"), String::from("_netrc")];
+
+        #[cfg(not(windows))]
+        let filenames = vec![String::from(".netrc")];
+
+        #[cfg(feature = "gpg")]
+        let filenames = filenames
+            .into_iter()
+            .flat_map(|name| [name.clone(), format!("{}.gpg", name)])
+            .collect();
+
+        DiscoveryOptions {
+            env: true,
+            paths: Vec::new(),
+            filenames,
+            require_exists: true,
+            system: false,
+        }
+    }
+}
+
+/// Netrc files of the environment, see [`Netrc::get_files`].
+fn files_in(env: &dyn Env) -> Vec<PathBuf> {
+    let env_var: Vec<PathBuf> = env_files(env).into_iter().filter(|f| f.exists()).collect();
+    if !env_var.is_empty() {
+        return env_var;
+    }
+    let options = DiscoveryOptions {
+        env: false,
+        ..Default::default()
+    };
+    Netrc::discover_in(&options, env).into_iter().collect()
+}
+
+/// Directory of the netrc fragments, see [`Netrc::get_dir`].
+fn dir_in(env: &dyn Env) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let config = env.var("APPDATA").map(PathBuf::from);
+
+    #[cfg(not(windows))]
+    let config = env
+        .var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env.home_dir().map(|home| home.join(".config")));
+
+    config
+        .map(|config| config.join("netrc.d"))
+        .filter(|dir| dir.is_dir())
+}
+
+/// System-wide netrc file.
+#[allow(unused_variables)]
+fn system_file(env: &dyn Env) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let file = env
+        .var("ProgramData")
+        .map(|dir| PathBuf::from(dir).join("netrc"));
+
+    #[cfg(not(windows))]
+    let file = Some(PathBuf::from("/etc/netrc"));
+
+    file
+}
+
+/// Files of the `NETRC` environment variable.
+fn env_files(env: &dyn Env) -> Vec<PathBuf> {
+    env.var("NETRC")
+        .iter()
+        .flat_map(std::env::split_paths)
+        .map(|f| shellexpand::path::tilde_with_context(&f, || env.home_dir()).into_owned())
+        .collect()
+}
+
+/// Whether `nrc` contains the password of a non-anonymous login.
+#[cfg(any(unix, windows))]
+fn has_secret(nrc: &Netrc) -> bool {
+    nrc.hosts
+        .values()
+        .chain(&nrc.default_entry)
+        .any(|auth| !auth.password.is_empty() && auth.login != "anonymous")
+}
+
+/// Check that `file` is private to the user if `nrc` contains the password of
+/// a non-anonymous login.
+#[cfg(unix)]
+fn check_permissions(file: &Path, nrc: &Netrc) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    if !has_secret(nrc) {
+        return Ok(());
+    }
+
+    let insecure = |reason: &str| Error::Insecure {
+        filename: file.display().to_string(),
+        reason: reason.to_owned(),
+    };
+    let meta = fs::metadata(file)?;
+    // SAFETY: getuid has no precondition and always succeeds.
+    if meta.uid() != unsafe { libc::getuid() } {
+        return Err(insecure("the owner is not the current user"));
+    }
+    if meta.mode() & 0o077 != 0 {
+        return Err(insecure("the file is accessible by other users"));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn check_permissions(file: &Path, nrc: &Netrc) -> Result<()> {
+    if !has_secret(nrc) {
+        return Ok(());
+    }
+    match is_shared(file)? {
+        true => Err(Error::Insecure {
+            filename: file.display().to_string(),
+            reason: String::from("the file is accessible by other users"),
+        }),
+        false => Ok(()),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn check_permissions(_file: &Path, _nrc: &Netrc) -> Result<()> {
+    Ok(())
+}
+
+/// Atomically replace the content of `file` with a file private to the user.
+fn write_file(file: &Path, content: &[u8]) -> Result<()> {
+    // Follow the symbolic links to not replace them by a regular file.
+    let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let name = file
+        .file_name()
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "invalid netrc file name"))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = file.with_file_name(tmp_name);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let _ = fs::remove_file(&tmp);
+    let res = options
+        .open(&tmp)
+        .and_then(|mut f| {
+            #[cfg(windows)]
+            make_private(&tmp)?;
+            f.write_all(content)?;
+            f.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, &file));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(res?)
+}
+
+/// Security functions of Windows, to keep the netrc files private.
+#[cfg(windows)]
+mod security {
+    use std::ffi::c_void;
+
+    pub const SDDL_REVISION_1: u32 = 1;
+    pub const SE_FILE_OBJECT: u32 = 1;
+    pub const DACL_SECURITY_INFORMATION: u32 = 0x0000_0004;
+    pub const PROTECTED_DACL_SECURITY_INFORMATION: u32 = 0x8000_0000;
+    pub const ACL_SIZE_INFORMATION: u32 = 2;
+    pub const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+    pub const WIN_WORLD_SID: u32 = 1;
+    pub const WIN_AUTHENTICATED_USER_SID: u32 = 17;
+    pub const WIN_BUILTIN_USERS_SID: u32 = 27;
+
+    #[repr(C)]
+    pub struct AceHeader {
+        pub ace_type: u8,
+        pub ace_flags: u8,
+        pub ace_size: u16,
+    }
+
+    #[repr(C)]
+    pub struct AccessAllowedAce {
+        pub header: AceHeader,
+        pub mask: u32,
+        pub sid_start: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct AclSizeInformation {
+        pub ace_count: u32,
+        pub bytes_in_use: u32,
+        pub bytes_free: u32,
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        pub fn ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl: *const u16,
+            revision: u32,
+            descriptor: *mut *mut c_void,
+            size: *mut u32,
+        ) -> i32;
+        pub fn GetSecurityDescriptorDacl(
+            descriptor: *mut c_void,
+            present: *mut i32,
+            dacl: *mut *mut c_void,
+            defaulted: *mut i32,
+        ) -> i32;
+        pub fn SetNamedSecurityInfoW(
+            name: *const u16,
+            object_type: u32,
+            info: u32,
+            owner: *mut c_void,
+            group: *mut c_void,
+            dacl: *mut c_void,
+            sacl: *mut c_void,
+        ) -> u32;
+        pub fn GetNamedSecurityInfoW(
+            name: *const u16,
+            object_type: u32,
+            info: u32,
+            owner: *mut *mut c_void,
+            group: *mut *mut c_void,
+            dacl: *mut *mut c_void,
+            sacl: *mut *mut c_void,
+            descriptor: *mut *mut c_void,
+        ) -> u32;
+        pub fn GetAclInformation(
+            acl: *mut c_void,
+            information: *mut c_void,
+            length: u32,
+            class: u32,
+        ) -> i32;
+        pub fn GetAce(acl: *mut c_void, index: u32, ace: *mut *mut c_void) -> i32;
+        pub fn IsWellKnownSid(sid: *mut c_void, well_known: u32) -> i32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn LocalFree(mem: *mut c_void) -> *mut c_void;
+    }
+}
+
+/// File name as a NUL terminated wide string, for the functions of Windows.
+#[cfg(windows)]
+fn wide_name(file: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    file.as_os_str().encode_wide().chain([0]).collect()
+}
+
+/// Restrict the access to `file` to its owner, with a protected DACL which
+/// does not inherit the entries of its directory.
+#[cfg(windows)]
+fn make_private(file: &Path) -> io::Result<()> {
+    use security::*;
+    use std::ptr;
+
+    // Full access for the owner of the file only.
+    let sddl: Vec<u16> = "D:P(A;;FA;;;OW)".encode_utf16().chain([0]).collect();
+    let name = wide_name(file);
+    let mut descriptor = ptr::null_mut();
+    // SAFETY: the SDDL string is NUL terminated, and the descriptor it
+    // allocates is freed with LocalFree below.
+    let converted = unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            ptr::null_mut(),
+        )
+    };
+    if converted == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (mut present, mut defaulted, mut dacl) = (0, 0, ptr::null_mut());
+    // SAFETY: the DACL points into the descriptor, which is valid until it is
+    // freed after the call, and the file name is NUL terminated.
+    let res = unsafe {
+        match GetSecurityDescriptorDacl(descriptor, &mut present, &mut dacl, &mut defaulted) {
+            0 => Err(io::Error::last_os_error()),
+            _ => match SetNamedSecurityInfoW(
+                name.as_ptr(),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                dacl,
+                ptr::null_mut(),
+            ) {
+                0 => Ok(()),
+                code => Err(io::Error::from_raw_os_error(code as i32)),
+            },
+        }
+    };
+    // SAFETY: the descriptor was allocated by LocalAlloc, and is not used
+    // anymore.
+    unsafe { LocalFree(descriptor) };
+    res
+}
+
+/// Whether the DACL of `file` grants an access to everyone, to the
+/// authenticated users or to the users of the machine. A file without DACL
+/// is accessible by everyone.
+#[cfg(windows)]
+fn is_shared(file: &Path) -> io::Result<bool> {
+    use security::*;
+    use std::ffi::c_void;
+    use std::ptr;
+
+    let name = wide_name(file);
+    let (mut dacl, mut descriptor) = (ptr::null_mut(), ptr::null_mut());
+    // SAFETY: the file name is NUL terminated, and the descriptor allocated
+    // is freed with LocalFree below.
+    let code = unsafe {
+        GetNamedSecurityInfoW(
+            name.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut dacl,
+            ptr::null_mut(),
+            &mut descriptor,
+        )
+    };
+    if code != 0 {
+        return Err(io::Error::from_raw_os_error(code as i32));
+    }
+    // SAFETY: the DACL points into the descriptor, which is valid until it is
+    // freed, and the entries are read within the size of the DACL.
+    let res = unsafe {
+        let mut info = AclSizeInformation::default();
+        let size = std::mem::size_of::<AclSizeInformation>() as u32;
+        if dacl.is_null() {
+            Ok(true)
+        } else if GetAclInformation(
+            dacl,
+            ptr::addr_of_mut!(info).cast(),
+            size,
+            ACL_SIZE_INFORMATION,
+        ) == 0
+        {
+            Err(io::Error::last_os_error())
+        } else {
+            let shared = (0..info.ace_count).any(|index| {
+                let mut ace: *mut c_void = ptr::null_mut();
+                if GetAce(dacl, index, &mut ace) == 0 {
+                    return false;
+                }
+                let ace = ace.cast::<AccessAllowedAce>();
+                if (*ace).header.ace_type != ACCESS_ALLOWED_ACE_TYPE {
+                    return false;
+                }
+                let sid = ptr::addr_of_mut!((*ace).sid_start).cast::<c_void>();
+                [
+                    WIN_WORLD_SID,
+                    WIN_AUTHENTICATED_USER_SID,
+                    WIN_BUILTIN_USERS_SID,
+                ]
+                .into_iter()
+                .any(|well_known| IsWellKnownSid(sid, well_known) != 0)
+            });
+            Ok(shared)
+        }
+    };
+    // SAFETY: the descriptor was allocated by LocalAlloc, and is not used
+    // anymore.
+    unsafe { LocalFree(descriptor) };
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Authenticator, Encoding, ParsingErrorKind};
+    use std::collections::HashMap;
+
+    const CONTENT: &str = "\
+machine cocolog-nifty.com
+login jmarten0
+password cC2&yt7OT
+
+machine wired.com
+login mstanlack1
+password gH4={wx=>VixU
+
+machine joomla.org
+login mbutterley2
+password hY5>yKqU&$vq&0
+";
+
+    fn create_netrc_file() -> PathBuf {
+        let dest = std::env::temp_dir().join("mynetrc");
+        if !dest.exists() {
+            std::fs::write(&dest, CONTENT).unwrap();
+        }
+        dest
+    }
+
+    fn check_nrc(nrc: &Netrc) {
+        assert_eq!(nrc.hosts.len(), 3);
+        assert_eq!(
+            nrc.hosts["cocolog-nifty.com"],
+            Authenticator::new("jmarten0", "", "cC2&yt7OT")
+        );
+        assert_eq!(
+            nrc.hosts["wired.com"],
+            Authenticator::new("mstanlack1", "", "gH4={wx=>VixU")
+        );
+        assert_eq!(
+            nrc.hosts["joomla.org"],
+            Authenticator::new("mbutterley2", "", "hY5>yKqU&$vq&0")
+        );
+    }
+
+    #[test]
+    fn test_new_env() {
+        let fi = create_netrc_file();
+        let env = HashMap::from([(String::from("NETRC"), fi.to_string_lossy().into_owned())]);
+        let nrc = Netrc::new_in(&env).unwrap();
+        check_nrc(&nrc);
+    }
+
+    #[test]
+    fn test_new_default() {}
+
+    #[test]
+    fn test_new_env_list() {
+        let fi = create_netrc_file();
+        let paths = [PathBuf::from("/netrc/file/not/exists"), fi.clone()];
+        let list = std::env::join_paths(paths).unwrap();
+        let env = HashMap::from([(String::from("NETRC"), list.to_string_lossy().into_owned())]);
+        assert_eq!(files_in(&env), vec![fi]);
+        let nrc = Netrc::new_in(&env).unwrap();
+        check_nrc(&nrc);
+    }
+
+    #[test]
+    fn test_discover() {
+        let fi = create_netrc_file();
+        let missing = PathBuf::from("/netrc/file/not/exists");
+        let options = DiscoveryOptions {
+            env: false,
+            paths: vec![missing.clone(), fi.clone()],
+            filenames: vec![],
+            ..Default::default()
+        };
+        assert_eq!(Netrc::discover(&options), Some(fi));
+
+        let options = DiscoveryOptions {
+            require_exists: false,
+            ..options
+        };
+        assert_eq!(Netrc::discover(&options), Some(missing));
+
+        let options = DiscoveryOptions {
+            env: false,
+            filenames: vec![],
+            ..Default::default()
+        };
+        assert_eq!(Netrc::discover(&options), None);
+    }
+
+    #[test]
+    fn test_new_in() {
+        let home = std::env::temp_dir().join("netrc-env-home");
+        std::fs::create_dir_all(home.join(".config/netrc.d")).unwrap();
+        std::fs::write(home.join(".netrc"), CONTENT).unwrap();
+        std::fs::write(
+            home.join(".config/netrc.d/other.netrc"),
+            "machine other.com login log",
+        )
+        .unwrap();
+
+        let env = HashMap::from([
+            (String::from("HOME"), home.to_string_lossy().into_owned()),
+            (
+                String::from("USERPROFILE"),
+                home.to_string_lossy().into_owned(),
+            ),
+            (
+                String::from("APPDATA"),
+                home.join(".config").to_string_lossy().into_owned(),
+            ),
+        ]);
+        assert_eq!(
+            Netrc::discover_in(&DiscoveryOptions::default(), &env),
+            Some(home.join(".netrc"))
+        );
+        let nrc = Netrc::new_in(&env).unwrap();
+        assert_eq!(nrc.hosts.len(), 4);
+        assert_eq!(nrc.hosts["other.com"].login, "log");
+
+        let mut env = env;
+        env.insert(String::from("NETRC"), String::from("~/missing:~/.netrc"));
+        #[cfg(not(windows))]
+        assert_eq!(files_in(&env), vec![home.join(".netrc")]);
+    }
+
+    #[test]
+    fn test_from_file_failed() {
+        assert_eq!(
+            Netrc::from_file(Path::new("/netrc/file/not/exists/on/no/netrc"))
+                .unwrap_err()
+                .to_string(),
+            "I/O error: No such file or directory (os error 2)"
+        );
+    }
+
+    #[test]
+    fn test_from_file() {
+        let fi = create_netrc_file();
+        let nrc = Netrc::from_file(fi.as_path()).unwrap();
+        check_nrc(&nrc);
+    }
+
+    #[test]
+    fn test_from_file_encoding() {
+        let fi = std::env::temp_dir().join("mynetrc-latin1");
+        fs::write(&fi, b"machine host.domain.com login log password caf\xe9").unwrap();
+
+        let mut options = ParseOptions {
+            encoding: Encoding::Latin1,
+            ..ParseOptions::default()
+        };
+        let nrc = Netrc::from_file_with(&fi, &options).unwrap();
+        assert_eq!(nrc.hosts["host.domain.com"].password, "café");
+
+        options.encoding = Encoding::Utf8;
+        let err = Netrc::from_file_with(&fi, &options).unwrap_err();
+        assert!(matches!(err, Error::Parsing { parser, .. } if parser.lineno() == 1));
+        assert!(NetrcDocument::from_file_with(&fi, &options).is_err());
+    }
+
+    #[test]
+    fn test_from_file_limits() {
+        let fi = std::env::temp_dir().join("mynetrc-limits");
+        fs::write(&fi, "machine host.domain.com login log password pass").unwrap();
+
+        let mut options = ParseOptions::default();
+        options.limits.max_size = Some(16);
+        let err = Netrc::from_file_with(&fi, &options).unwrap_err();
+        assert!(
+            matches!(err, Error::Parsing { parser, .. } if parser.kind() == ParsingErrorKind::TooLarge)
+        );
+        let err = NetrcDocument::from_file_with(&fi, &options).unwrap_err();
+        assert!(
+            matches!(err, Error::Parsing { parser, .. } if parser.kind() == ParsingErrorKind::TooLarge)
+        );
+    }
+
+    #[test]
+    fn test_reload() {
+        let fi = std::env::temp_dir().join("mynetrc-reload");
+        fs::write(&fi, "machine a login x\nmachine b login y\n").unwrap();
+        let mut nrc = Netrc::from_file(&fi).unwrap();
+
+        fs::write(&fi, "machine b login z\nmachine c login w\n").unwrap();
+        let diff = nrc.reload(&fi).unwrap();
+        assert_eq!(diff.added, ["c"]);
+        assert_eq!(diff.removed, ["a"]);
+        assert_eq!(diff.changed, ["b"]);
+        assert_eq!(nrc.hosts["b"].login, "z");
+        assert!(nrc.reload(&fi).unwrap().is_empty());
+
+        fs::write(&fi, "machine b login w bad").unwrap();
+        assert!(nrc.reload(&fi).is_err());
+        assert_eq!(nrc.hosts["b"].login, "z");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_from_file_async() {
+        let fi = create_netrc_file();
+        let env = HashMap::from([(String::from("NETRC"), fi.to_string_lossy().into_owned())]);
+        check_nrc(&Netrc::new_async_in(env).await.unwrap());
+        check_nrc(&Netrc::from_file_async(&fi).await.unwrap());
+
+        let err = Netrc::from_file_async(Path::new("/netrc/not/found"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Io(e) if e.kind() == ErrorKind::NotFound));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fi = std::env::temp_dir().join("mynetrc-permissions");
+        let options = ParseOptions {
+            check_permissions: true,
+            ..Default::default()
+        };
+        let set_mode = |mode| {
+            std::fs::set_permissions(&fi, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+
+        std::fs::write(&fi, CONTENT).unwrap();
+        set_mode(0o644);
+        assert_eq!(
+            Netrc::from_file_with(&fi, &options)
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "insecure netrc file '{}': the file is accessible by other users",
+                fi.display()
+            )
+        );
+        assert!(Netrc::from_file(&fi).is_ok());
+
+        set_mode(0o600);
+        check_nrc(&Netrc::from_file_with(&fi, &options).unwrap());
+
+        std::fs::write(&fi, "default login anonymous password me@").unwrap();
+        set_mode(0o644);
+        assert!(Netrc::from_file_with(&fi, &options).is_ok());
+    }
+
+    #[cfg(feature = "gpg")]
+    #[test]
+    fn test_from_file_gpg() {
+        let fi = std::env::temp_dir().join("mynetrc.gpg");
+        std::fs::write(&fi, CONTENT).unwrap();
+        match Netrc::from_file(&fi) {
+            Err(Error::Decryption { filename, .. }) => {
+                assert_eq!(filename, fi.display().to_string())
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert!(DiscoveryOptions::default()
+            .filenames
+            .starts_with(&[String::from(".netrc"), String::from(".netrc.gpg")]));
+    }
+
+    #[test]
+    fn test_save() {
+        let fi = std::env::temp_dir().join("mynetrc-save");
+        let _ = std::fs::remove_file(&fi);
+        let nrc: Netrc = CONTENT.parse().unwrap();
+        nrc.save(&fi).unwrap();
+        check_nrc(&Netrc::from_file(&fi).unwrap());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&fi).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Replace an existing file.
+        Netrc::default().save(&fi).unwrap();
+        assert_eq!(Netrc::from_file(&fi).unwrap(), Netrc::default());
+    }
+
+    #[test]
+    fn test_from_dir() {
+        let dir = std::env::temp_dir().join("netrc.d");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("10-main.netrc"), CONTENT).unwrap();
+        std::fs::write(
+            dir.join("20-work.netrc"),
+            "machine wired.com login other password pass",
+        )
+        .unwrap();
+        std::fs::write(dir.join("README"), "not a netrc file").unwrap();
+
+        let nrc = Netrc::from_dir(&dir).unwrap();
+        assert_eq!(nrc.hosts.len(), 3);
+        assert_eq!(
+            nrc.hosts["wired.com"],
+            Authenticator::new("other", "", "pass")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Login of the FTP sessions of the `suppaftp` crate, like ftp(1): the
+//! `USER`, `PASS` and `ACCT` commands are sent with the entry of the host,
+//! and the `init` macro is run once logged in.
+//!
+//! ```no_run
+//! use netrc::Netrc;
+//! use netrc::ftp::login_init;
+//! use suppaftp::FtpStream;
+//!
+//! let nrc = Netrc::new().unwrap();
+//! let mut ftp = FtpStream::connect("ftp.domain.com:21").unwrap();
+//! login_init(&nrc, &mut ftp, "ftp.domain.com", Some(21)).unwrap();
+//! ```
+
+use crate::{CredentialSource, Netrc};
+use std::io;
+use suppaftp::types::{FileType, FormatControl};
+use suppaftp::{FtpError, FtpResult, FtpStream, Status};
+
+/// Log in with the entry of `host`, sending the account with `ACCT` if the
+/// entry has one, and return whether the host has an entry.
+///
+/// Nothing is sent if the host has no entry.
+pub fn login<S>(source: &S, ftp: &mut FtpStream, host: &str, port: Option<u16>) -> FtpResult<bool>
+where
+    S: CredentialSource + ?Sized,
+{
+    let Some(auth) = source.find(host, port, Some("ftp")) else {
+        return Ok(false);
+    };
+    let expected = [Status::LoggedIn, Status::NeedPassword, Status::NeedAccount];
+    let mut res = ftp.custom_command(format!("USER {}", auth.login), &expected)?;
+    if res.status == Status::NeedPassword {
+        res = ftp.custom_command(format!("PASS {}", auth.password), &expected)?;
+    }
+    // The account is sent even if the server does not ask for it, like
+    // ftp(1) does.
+    if res.status == Status::NeedAccount || !auth.account.is_empty() {
+        ftp.custom_command(
+            format!("ACCT {}", auth.account),
+            &[
+                Status::LoggedIn,
+                Status::CommandOk,
+                Status::CommandNotImplemented,
+            ],
+        )?;
+    }
+    Ok(true)
+}
+
+/// Log in like [`login`], then run the `init` macro of the netrc if the host
+/// has an entry.
+pub fn login_init(
+    nrc: &Netrc,
+    ftp: &mut FtpStream,
+    host: &str,
+    port: Option<u16>,
+) -> FtpResult<bool> {
+    if !login(nrc, ftp, host, port)? {
+        return Ok(false);
+    }
+    if let Some(lines) = nrc.macros.get("init") {
+        run_macro(ftp, lines)?;
+    }
+    Ok(true)
+}
+
+/// Run the commands of a macro.
+///
+/// The supported commands are `cd`, `cdup`, `binary` (or `image`), `ascii`,
+/// `mkdir`, `rmdir`, `delete`, `site` and `quote` (or `literal`), which sends
+/// its arguments as is. Any other command fails before the macro is run.
+pub fn run_macro(ftp: &mut FtpStream, lines: &[String]) -> FtpResult<()> {
+    let commands = lines
+        .iter()
+        .filter_map(|line| Command::parse(line).transpose())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|message| {
+            FtpError::ConnectionError(io::Error::new(io::ErrorKind::InvalidInput, message))
+        })?;
+    for command in commands {
+        match command {
+            Command::Cd(path) => ftp.cwd(path)?,
+            Command::Cdup => ftp.cdup()?,
+            Command::Binary => ftp.transfer_type(FileType::Binary)?,
+            Command::Ascii => ftp.transfer_type(FileType::Ascii(FormatControl::Default))?,
+            Command::Mkdir(path) => ftp.mkdir(path)?,
+            Command::Rmdir(path) => ftp.rmdir(path)?,
+            Command::Delete(path) => ftp.rm(path)?,
+            Command::Site(args) => {
+                ftp.site(args)?;
+            }
+            Command::Quote(args) => {
+                ftp.custom_command(args, &[Status::CommandOk, Status::RequestedFileActionOk])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Command of ftp(1) in a macro.
+#[derive(Debug, PartialEq, Eq)]
+enum Command<'a> {
+    Cd(&'a str),
+    Cdup,
+    Binary,
+    Ascii,
+    Mkdir(&'a str),
+    Rmdir(&'a str),
+    Delete(&'a str),
+    Site(&'a str),
+    Quote(&'a str),
+}
+
+impl<'a> Command<'a> {
+    /// Command of a line of a macro, or `None` for an empty line.
+    fn parse(line: &'a str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        let (name, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args = args.trim();
+        let with_args = |command: fn(&'a str) -> Self| match args.is_empty() {
+            true => Err(format!("missing argument of the macro command '{}'", name)),
+            false => Ok(Some(command(args))),
+        };
+        match name {
+            "" => Ok(None),
+            "cd" => with_args(Command::Cd),
+            "cdup" => Ok(Some(Command::Cdup)),
+            "binary" | "bin" | "image" => Ok(Some(Command::Binary)),
+            "ascii" => Ok(Some(Command::Ascii)),
+            "mkdir" => with_args(Command::Mkdir),
+            "rmdir" => with_args(Command::Rmdir),
+            "delete" => with_args(Command::Delete),
+            "site" => with_args(Command::Site),
+            "quote" | "literal" => with_args(Command::Quote),
+            _ => Err(format!("unsupported macro command '{}'", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            Command::parse("cd /pub/data"),
+            Ok(Some(Command::Cd("/pub/data")))
+        );
+        assert_eq!(Command::parse("  binary "), Ok(Some(Command::Binary)));
+        assert_eq!(Command::parse("image"), Ok(Some(Command::Binary)));
+        assert_eq!(Command::parse("ascii"), Ok(Some(Command::Ascii)));
+        assert_eq!(
+            Command::parse("quote  PBSZ 0"),
+            Ok(Some(Command::Quote("PBSZ 0")))
+        );
+        assert_eq!(Command::parse(""), Ok(None));
+        assert!(Command::parse("cd").is_err());
+        assert!(Command::parse("get file.txt").is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Decrypt the netrc files encrypted with GPG, like `~/.authinfo.gpg`.
+
+use crate::netrc::read_limited_from;
+use crate::{Error, Limits, Result};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Whether `file` is encrypted, from its `.gpg` extension.
+pub(crate) fn is_encrypted(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| ext == "gpg")
+}
+
+/// Decrypt `file` with the `gpg` command, which asks the passphrase with the
+/// agent if needed.
+///
+/// Like the plain files, no more than one byte past the maximum size of the
+/// limits is read from the decrypted content, and `gpg` is stopped once it
+/// is reached.
+pub(crate) fn decrypt(file: &Path, limits: &Limits) -> Result<Vec<u8>> {
+    let mut child = decrypt_command(file)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let content = match child.stdout.take() {
+        Some(stdout) => read_limited_from(stdout, limits)?,
+        None => Vec::new(),
+    };
+    if limits.max_size.is_some_and(|max| content.len() > max) {
+        // The content is too large for the parser, which reports it.
+        let _ = child.kill();
+        let _ = child.wait();
+        return Ok(content);
+    }
+
+    let mut stderr = String::new();
+    if let Some(mut err) = child.stderr.take() {
+        let _ = err.read_to_string(&mut stderr);
+    }
+    if !child.wait()?.success() {
+        return Err(Error::Decryption {
+            filename: file.display().to_string(),
+            message: stderr.trim().to_owned(),
+        });
+    }
+    Ok(content)
+}
+
+/// `gpg --decrypt` command of `file`, which can not be an option.
+fn decrypt_command(file: &Path) -> Command {
+    let mut cmd = Command::new("gpg");
+    cmd.args(["--batch", "--quiet", "--decrypt", "--"])
+        .arg(file);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decrypt_command() {
+        let cmd = decrypt_command(Path::new("--help.gpg"));
+        assert_eq!(
+            cmd.get_args().collect::<Vec<_>>(),
+            ["--batch", "--quiet", "--decrypt", "--", "--help.gpg"]
+        );
+    }
+
+    #[test]
+    fn test_is_encrypted() {
+        assert!(is_encrypted(Path::new("/home/user/.authinfo.gpg")));
+        assert!(!is_encrypted(Path::new("/home/user/.netrc")));
+    }
+}
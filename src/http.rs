@@ -0,0 +1,141 @@
+//! Credentials of the requests of the `http` crate, to integrate the netrc
+//! with any HTTP client in a few lines.
+//!
+//! ```
+//! use netrc::Netrc;
+//! use netrc::http::apply_netrc;
+//!
+//! let nrc: Netrc = "machine api.domain.com login log password pass".parse().unwrap();
+//! let mut req = http::Request::get("https://api.domain.com/v1").body(()).unwrap();
+//! apply_netrc(&nrc, &mut req);
+//! assert_eq!(req.headers()["authorization"], "Basic bG9nOnBhc3M=");
+//! ```
+
+use crate::{Authenticator, CredentialSource};
+use ::http::header::AUTHORIZATION;
+use ::http::{HeaderValue, Request, Uri};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Add the `Basic` credentials of the host of the request URI to a request
+/// without `Authorization` header, and return whether they were added.
+///
+/// The credentials are only sent over HTTPS or to a loopback host, see
+/// [`is_secure`]. The URI of the request must be absolute.
+pub fn apply_netrc<S, B>(source: &S, req: &mut Request<B>) -> bool
+where
+    S: CredentialSource + ?Sized,
+{
+    if req.headers().contains_key(AUTHORIZATION) || !is_secure(req.uri()) {
+        return false;
+    }
+    match authorization(source, req.uri()) {
+        Some(value) => {
+            req.headers_mut().insert(AUTHORIZATION, value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// `Authorization` header of the `Basic` credentials of the host of `uri`,
+/// whatever its scheme.
+pub fn authorization<S>(source: &S, uri: &Uri) -> Option<HeaderValue>
+where
+    S: CredentialSource + ?Sized,
+{
+    authenticator(source, uri).and_then(|auth| basic_auth(&auth))
+}
+
+/// Authenticator of the host of `uri`, whatever its scheme.
+///
+/// The default port of the scheme is used if the URI has no port, so that a
+/// `host:443` entry matches `https://host/`.
+pub fn authenticator<S>(source: &S, uri: &Uri) -> Option<Authenticator>
+where
+    S: CredentialSource + ?Sized,
+{
+    let host = uri.host()?;
+    let scheme = uri.scheme_str();
+    let port = uri.port_u16().or(match scheme {
+        Some("https") => Some(443),
+        Some("http") => Some(80),
+        _ => None,
+    });
+    source.find(host, port, scheme)
+}
+
+/// Whether the credentials sent to `uri` can not be read by a third party,
+/// see [`crate::is_secure`]. A relative URI, without scheme, is not secure.
+pub fn is_secure(uri: &Uri) -> bool {
+    match (uri.scheme_str(), uri.host()) {
+        (Some(scheme), Some(host)) => crate::is_secure(scheme, host),
+        _ => false,
+    }
+}
+
+/// `Authorization` header of the `Basic` authentication, marked as
+/// sensitive.
+pub fn basic_auth(auth: &Authenticator) -> Option<HeaderValue> {
+    let credentials = STANDARD.encode(format!("{}:{}", auth.login, auth.password));
+    sensitive(format!("Basic {}", credentials))
+}
+
+/// `Authorization` header of the `Bearer` authentication with the password as
+/// token, marked as sensitive.
+pub fn bearer_auth(auth: &Authenticator) -> Option<HeaderValue> {
+    sensitive(format!("Bearer {}", auth.password))
+}
+
+fn sensitive(value: String) -> Option<HeaderValue> {
+    let mut value = HeaderValue::from_str(&value).ok()?;
+    value.set_sensitive(true);
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Netrc;
+
+    #[test]
+    fn test_apply_netrc() {
+        let nrc: Netrc = "\
+            machine host.domain.com login log password pass
+            machine localhost:8080 login local password pass
+            "
+        .parse()
+        .unwrap();
+        let apply = |uri: &str| {
+            let mut req = Request::get(uri).body(()).unwrap();
+            apply_netrc(&nrc, &mut req);
+            req.headers()
+                .get(AUTHORIZATION)
+                .map(|v| v.to_str().unwrap().to_owned())
+        };
+
+        assert_eq!(
+            apply("https://host.domain.com/api"),
+            Some(String::from("Basic bG9nOnBhc3M="))
+        );
+        assert_eq!(apply("http://host.domain.com/api"), None);
+        assert_eq!(
+            apply("http://localhost:8080/api"),
+            Some(format!("Basic {}", STANDARD.encode("local:pass")))
+        );
+        assert_eq!(apply("/api"), None);
+    }
+
+    #[test]
+    fn test_is_secure() {
+        let secure = |uri: &str| is_secure(&uri.parse().unwrap());
+        assert!(secure("https://host.domain.com/"));
+        assert!(!secure("http://host.domain.com/"));
+        assert!(secure("http://127.0.0.1:8080/"));
+        assert!(secure("http://[::1]/"));
+        assert!(secure("http://LocalHost/"));
+        assert!(!secure("ftp://host.domain.com/"));
+        assert!(!secure("/api"));
+        assert!(!secure("host.domain.com:443"));
+    }
+}
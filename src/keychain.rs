@@ -0,0 +1,206 @@
+//! Look up the passwords in the macOS Keychain.
+//!
+//! The Keychain is queried with the `security` command, so that nothing is
+//! found on the other systems.
+
+use crate::{Authenticator, Error, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Password of an entry to look up in the Keychain, see [`Netrc::resolve`](crate::Netrc::resolve).
+pub const KEYCHAIN_PASSWORD: &str = "keychain";
+
+/// Authenticator of the first internet password of `server`.
+pub(crate) fn find(server: &str, port: Option<u16>) -> Option<Authenticator> {
+    let login = find_account(server, port)?;
+    find_password(server, &login, port).map(|password| Authenticator::new(&login, "", &password))
+}
+
+/// Account of the first internet password of `server`.
+fn find_account(server: &str, port: Option<u16>) -> Option<String> {
+    security(server, None, port, false).and_then(|out| parse_account(&out))
+}
+
+/// Internet password of `server` and `account`.
+pub(crate) fn find_password(server: &str, account: &str, port: Option<u16>) -> Option<String> {
+    security(server, Some(account), port, true)
+        .map(|out| out.strip_suffix('\n').unwrap_or(&out).to_owned())
+}
+
+/// Add or update the internet password of `server` and `account`.
+///
+/// The password is not given on the command line of `security`, where the
+/// other users could read it, but with the command written to the standard
+/// input of `security -i`.
+///
+/// The Secret Service is the keyring on the other systems if it is enabled.
+#[cfg_attr(
+    all(feature = "secret-service", not(target_os = "macos")),
+    allow(dead_code)
+)]
+pub(crate) fn store(server: &str, account: &str, port: Option<u16>, password: &str) -> Result<()> {
+    let (cmd, script) = store_command(server, account, port, password)?;
+    run_interactive(cmd, &script)
+}
+
+/// `security -i` command and the command to write to its standard input to
+/// store a password.
+#[cfg_attr(
+    all(feature = "secret-service", not(target_os = "macos")),
+    allow(dead_code)
+)]
+fn store_command(
+    server: &str,
+    account: &str,
+    port: Option<u16>,
+    password: &str,
+) -> Result<(Command, String)> {
+    let port = port.map(|port| port.to_string());
+    let mut args = vec!["add-internet-password", "-U", "-s", server, "-a", account];
+    if let Some(port) = &port {
+        args.extend(["-P", port]);
+    }
+    args.extend(["-w", password]);
+    if args.iter().any(|arg| arg.contains(['\n', '\r'])) {
+        return Err(Error::Keyring(String::from(
+            "line break in a value to store in the Keychain",
+        )));
+    }
+    let script = args
+        .iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+        + "\n";
+    let mut cmd = Command::new("security");
+    cmd.arg("-i");
+    Ok((cmd, script))
+}
+
+/// Quote an argument of a command of `security -i`.
+#[cfg_attr(
+    all(feature = "secret-service", not(target_os = "macos")),
+    allow(dead_code)
+)]
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Run `security -i` with `script` as its standard input. It reports the
+/// errors of the commands on its error output, and not by its status.
+#[cfg_attr(
+    all(feature = "secret-service", not(target_os = "macos")),
+    allow(dead_code)
+)]
+fn run_interactive(mut cmd: Command, script: &str) -> Result<()> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+    match output.status.success() && stderr.is_empty() {
+        true => Ok(()),
+        false => Err(Error::Keyring(stderr)),
+    }
+}
+
+/// Delete the internet password of `server` and `account`.
+#[cfg_attr(
+    all(feature = "secret-service", not(target_os = "macos")),
+    allow(dead_code)
+)]
+pub(crate) fn delete(server: &str, account: &str, port: Option<u16>) -> Result<()> {
+    let mut cmd = Command::new("security");
+    cmd.args(["delete-internet-password", "-s", server, "-a", account]);
+    if let Some(port) = port {
+        cmd.args(["-P", &port.to_string()]);
+    }
+    run(cmd)
+}
+
+/// Run a `security` command which changes the Keychain.
+#[cfg_attr(
+    all(feature = "secret-service", not(target_os = "macos")),
+    allow(dead_code)
+)]
+fn run(mut cmd: Command) -> Result<()> {
+    let output = cmd.output()?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(Error::Keyring(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        )),
+    }
+}
+
+/// Run `security find-internet-password`, and return its output if an item
+/// is found.
+fn security(
+    server: &str,
+    account: Option<&str>,
+    port: Option<u16>,
+    password: bool,
+) -> Option<String> {
+    let mut cmd = Command::new("security");
+    cmd.args(["find-internet-password", "-s", server]);
+    if let Some(account) = account {
+        cmd.args(["-a", account]);
+    }
+    if let Some(port) = port {
+        cmd.args(["-P", &port.to_string()]);
+    }
+    if password {
+        cmd.arg("-w");
+    }
+    let output = cmd.output().ok().filter(|o| o.status.success())?;
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Extract the account from the attributes printed by `security`, like
+/// `"acct"<blob>="login"`.
+fn parse_account(attributes: &str) -> Option<String> {
+    attributes.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("\"acct\"<blob>=\"")
+            .and_then(|value| value.strip_suffix('"'))
+            .map(str::to_owned)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account() {
+        let attributes = "\
+keychain: \"/Users/me/Library/Keychains/login.keychain-db\"
+version: 512
+class: \"inet\"
+attributes:
+    0x00000007 <blob>=\"host.domain.com\"
+    \"acct\"<blob>=\"log\"
+    \"atyp\"<blob>=\"dflt\"
+    \"srvr\"<blob>=\"host.domain.com\"
+";
+        assert_eq!(parse_account(attributes), Some(String::from("log")));
+        assert_eq!(parse_account("    \"acct\"<blob>=<NULL>\n"), None);
+    }
+
+    #[test]
+    fn test_store_command() {
+        let (cmd, script) = store_command("host.com", "log", Some(8443), "p\"a\\ss").unwrap();
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["-i"]);
+        assert_eq!(
+            script,
+            "\"add-internet-password\" \"-U\" \"-s\" \"host.com\" \"-a\" \"log\" \
+             \"-P\" \"8443\" \"-w\" \"p\\\"a\\\\ss\"\n"
+        );
+        assert!(store_command("host.com", "log", None, "pass\nquit").is_err());
+    }
+}
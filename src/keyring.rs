@@ -0,0 +1,55 @@
+//! Store the passwords in the keyring of the system: the macOS Keychain on
+//! macOS (`keychain` feature), else the freedesktop Secret Service
+//! (`secret-service` feature).
+//!
+//! The passwords stored are found by [`Netrc::resolve`](crate::Netrc::resolve)
+//! for the machines without entry, or for the entries whose password is
+//! [`KEYRING_PASSWORD`]:
+//!
+//! ```no_run
+//! use netrc::{keyring, Authenticator, KEYRING_PASSWORD};
+//!
+//! let auth = Authenticator::new("log", "", "pass");
+//! keyring::store("host.domain.com", None, &auth).unwrap();
+//!
+//! let nrc: netrc::Netrc = format!("machine host.domain.com login log password {}", KEYRING_PASSWORD)
+//!     .parse()
+//!     .unwrap();
+//! assert_eq!(nrc.resolve("host.domain.com", None), Some(auth));
+//! ```
+
+#[cfg(all(
+    feature = "keychain",
+    any(target_os = "macos", not(feature = "secret-service"))
+))]
+use crate::keychain as backend;
+#[cfg(all(
+    feature = "secret-service",
+    not(all(feature = "keychain", target_os = "macos"))
+))]
+use crate::secret_service as backend;
+use crate::{normalize_host, Authenticator, Result};
+
+/// Password of an entry to look up in the keyring, see
+/// [`Netrc::resolve`](crate::Netrc::resolve).
+pub const KEYRING_PASSWORD: &str = "keyring";
+
+/// Add or update the password of the login of a host.
+pub fn store(host: &str, port: Option<u16>, auth: &Authenticator) -> Result<()> {
+    backend::store(&normalize_host(host), &auth.login, port, &auth.password)
+}
+
+/// Password of the login of a host.
+pub fn password(host: &str, port: Option<u16>, login: &str) -> Option<String> {
+    backend::find_password(&normalize_host(host), login, port)
+}
+
+/// Authenticator of the first password of a host, whatever its login.
+pub fn find(host: &str, port: Option<u16>) -> Option<Authenticator> {
+    backend::find(&normalize_host(host), port)
+}
+
+/// Delete the password of the login of a host.
+pub fn delete(host: &str, port: Option<u16>, login: &str) -> Result<()> {
+    backend::delete(&normalize_host(host), login, port)
+}
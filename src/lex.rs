@@ -1,91 +1,277 @@
-use std::collections::VecDeque;
-use std::str::Chars;
+use crate::netrc::{CompatMode, Limits, ParseOptions, ParsingError, ParsingErrorKind, Span};
+use alloc::borrow::Cow;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use memchr::{memchr, memchr3, memchr_iter};
 
 pub struct Lex<'a> {
     pub lineno: u32,
-    pub instream: Chars<'a>,
-    pub pushback: VecDeque<String>,
+    pub pos: usize,
+    /// Content being read, scanned as bytes: the tokens are split on ASCII
+    /// bytes only, so that the slices between them are still `str`.
+    pub source: &'a str,
+    pub pushback: VecDeque<(Cow<'a, str>, Span)>,
+    /// Location of the last token returned by `get_token`.
+    pub span: Span,
+    /// Read the tokens in single quotes, without escapes.
+    pub single_quotes: bool,
+    /// Allow the newlines in the quoted tokens.
+    pub multiline_quotes: bool,
+    /// Dialect of the escapes.
+    pub compat: CompatMode,
+    /// Whether the last token returned by `get_token` was quoted.
+    pub quoted: bool,
+    /// Limits of the tokens and of the macros.
+    pub limits: Limits,
+    /// Error found reading the last tokens, see `check`.
+    pub error: Option<ParsingError>,
+}
+
+/// Whether a byte separates the tokens.
+fn is_space(b: u8) -> bool {
+    matches!(b, b'\n' | b'\t' | b'\r' | b' ')
 }
 
 impl<'a> Lex<'a> {
-    pub fn new(content: &'a str) -> Self {
+    pub fn new(content: &'a str, options: &ParseOptions) -> Self {
+        // Skip the byte order mark of the files saved by some editors, the
+        // positions being still the ones in `content`.
+        let bom = match content.starts_with('\u{feff}') {
+            true => '\u{feff}'.len_utf8(),
+            false => 0,
+        };
         Lex {
             lineno: 1,
-            instream: content.chars(),
+            pos: bom,
+            source: content,
             pushback: VecDeque::new(),
+            span: Span::default(),
+            single_quotes: options.single_quotes,
+            multiline_quotes: options.multiline_quotes,
+            compat: options.compat,
+            quoted: false,
+            limits: options.limits,
+            error: None,
         }
     }
 
-    pub fn read_char(&mut self) -> Option<char> {
-        let ch = self.instream.next();
-        if ch == Some('\n') {
-            self.lineno += 1;
-        }
-        ch
+    fn bytes(&self) -> &'a [u8] {
+        self.source.as_bytes()
+    }
+
+    /// Move to `pos`, counting the lines of the skipped bytes.
+    fn advance(&mut self, pos: usize) {
+        let skipped = &self.bytes()[self.pos..pos];
+        self.lineno += memchr_iter(b'\n', skipped).count() as u32;
+        self.pos = pos;
+    }
+
+    /// Read the character at `pos`, the escaped one after a `\\`.
+    fn read_char(&mut self) -> Option<char> {
+        let c = self.source[self.pos..].chars().next()?;
+        self.advance(self.pos + c.len_utf8());
+        Some(c)
+    }
+
+    /// Whether the whole content has been read.
+    pub fn is_eof(&self) -> bool {
+        self.pushback.is_empty() && self.pos >= self.source.len()
+    }
+
+    /// Read the rest of the line, without its `\n` or `\r\n` end.
+    pub fn read_line(&mut self) -> &'a str {
+        let rest = &self.bytes()[self.pos..];
+        let end = memchr(b'\n', rest).map_or(self.source.len(), |i| self.pos + i);
+        let line = &self.source[self.pos..end];
+        let line = self.limit(0, line.strip_suffix('\r').unwrap_or(line));
+        self.advance((end + 1).min(self.source.len()));
+        line
     }
 
-    pub fn read_line(&mut self) -> String {
-        let mut s = String::new();
-        for ch in &mut self.instream {
-            if ch == '\n' {
-                return s;
+    /// Cut `s` added to a token or a line of `len` bytes if it exceeds the
+    /// maximum length: the rest is then skipped, and the error reported by
+    /// `check`.
+    fn limit<'s>(&mut self, len: usize, s: &'s str) -> &'s str {
+        match self.limits.max_token_len {
+            Some(max) if len + s.len() > max => {
+                let mut fit = max.saturating_sub(len);
+                while !s.is_char_boundary(fit) {
+                    fit -= 1;
+                }
+                self.fail(
+                    self.lineno,
+                    ParsingErrorKind::TokenTooLong,
+                    format!("token longer than {} bytes", max),
+                );
+                &s[..fit]
             }
-            s.push(ch);
+            _ => s,
+        }
+    }
+
+    /// Add a slice of the content to a token, which borrows the content until
+    /// an escape requires a copy.
+    fn push_str(&mut self, token: &mut Cow<'a, str>, s: &'a str) {
+        let s = self.limit(token.len(), s);
+        if token.is_empty() {
+            *token = Cow::Borrowed(s);
+        } else if !s.is_empty() {
+            token.to_mut().push_str(s);
+        }
+    }
+
+    /// Add the character escaped by the `\\` at `pos`, `control` reading
+    /// `\n`, `\r` and `\t` as control characters like curl.
+    fn push_escaped(&mut self, token: &mut Cow<'a, str>, control: bool) {
+        self.pos += 1;
+        let c = match (self.read_char().unwrap_or(' '), control) {
+            ('n', true) => '\n',
+            ('r', true) => '\r',
+            ('t', true) => '\t',
+            (c, _) => c,
+        };
+        let mut buf = [0; 4];
+        let escaped = c.encode_utf8(&mut buf);
+        if self.limit(token.len(), escaped).len() == escaped.len() {
+            token.to_mut().push(c);
         }
-        s
     }
 
-    pub fn get_token(&mut self) -> String {
+    /// Read the next token, borrowed from the content unless it has escapes.
+    pub fn get_token(&mut self) -> Cow<'a, str> {
         let p = self.pushback.pop_front();
-        if let Some(x) = p {
+        if let Some((x, span)) = p {
+            self.span = span;
+            self.quoted = false;
             return x;
         }
-        let mut token = String::new();
+        let mut token = Cow::Borrowed("");
+        self.quoted = false;
+        let bytes = self.bytes();
+        let start = match bytes[self.pos..].iter().position(|&b| !is_space(b)) {
+            Some(i) => self.pos + i,
+            None => {
+                self.advance(bytes.len());
+                return token;
+            }
+        };
+        self.advance(start);
+        self.span = Span {
+            lineno: self.lineno,
+            start,
+            end: start + 1,
+        };
+        match bytes[start] {
+            b'"' => {
+                self.pos += 1;
+                self.read_quoted(&mut token, b'"', true);
+            }
+            b'\'' if self.single_quotes => {
+                self.pos += 1;
+                self.read_quoted(&mut token, b'\'', false);
+            }
+            _ => self.read_word(&mut token),
+        }
+        token
+    }
 
-        while let Some(ch) = self.read_char() {
-            match ch {
-                '\n' | '\t' | '\r' | ' ' => {
-                    continue;
-                }
-                '"' => {
-                    while let Some(ch) = self.read_char() {
-                        match ch {
-                            '"' => {
-                                return token;
-                            }
-                            '\\' => {
-                                token.push(self.read_char().unwrap_or(' '));
-                            }
-                            _ => {
-                                token.push(ch);
-                            }
-                        }
-                    }
+    /// Read an unquoted token, and the space or the line end after it.
+    fn read_word(&mut self, token: &mut Cow<'a, str>) {
+        // curl reads the backslashes of the unquoted tokens verbatim.
+        let escapes = self.compat != CompatMode::Curl;
+        let bytes = self.bytes();
+        loop {
+            let rest = &bytes[self.pos..];
+            let end = rest
+                .iter()
+                .position(|&b| is_space(b) || (b == b'\\' && escapes))
+                .map_or(bytes.len(), |i| self.pos + i);
+            self.push_str(token, &self.source[self.pos..end]);
+            self.pos = end;
+            match bytes.get(end) {
+                Some(b'\\') => self.push_escaped(token, false),
+                _ => break,
+            }
+        }
+        self.span.end = self.pos;
+        // The token ends with the `\r\n` end of line, like with `\n`.
+        let sep = match bytes.get(self.pos..self.pos + 2) {
+            Some(b"\r\n") => 2,
+            _ => 1,
+        };
+        self.advance((self.pos + sep).min(bytes.len()));
+    }
+
+    /// Read a token until the closing `quote`, with the `\\` escapes if
+    /// `escapes` is true.
+    fn read_quoted(&mut self, token: &mut Cow<'a, str>, quote: u8, escapes: bool) {
+        self.quoted = true;
+        let bytes = self.bytes();
+        let escape = if escapes { b'\\' } else { quote };
+        let newline = if self.multiline_quotes { quote } else { b'\n' };
+        let mut closed = false;
+        loop {
+            let rest = &bytes[self.pos..];
+            let Some(i) = memchr3(quote, escape, newline, rest) else {
+                let end = bytes.len();
+                self.push_str(token, &self.source[self.pos..end]);
+                self.advance(end);
+                break;
+            };
+            let end = self.pos + i;
+            self.push_str(token, &self.source[self.pos..end]);
+            self.advance(end);
+            match bytes[end] {
+                b if b == quote => {
+                    self.pos += 1;
+                    closed = true;
+                    break;
                 }
-                _ => {
-                    let c = if ch == '\\' {
-                        self.read_char().unwrap_or(' ')
-                    } else {
-                        ch
-                    };
-                    token.push(c);
-                    while let Some(ch) = self.read_char() {
-                        let c = match ch {
-                            '\n' | '\t' | '\r' | ' ' => {
-                                return token;
-                            }
-                            '\\' => self.read_char().unwrap_or(' '),
-                            _ => ch,
-                        };
-                        token.push(c);
-                    }
+                b'\n' => {
+                    self.advance(end + 1);
+                    break;
                 }
+                _ => self.push_escaped(token, self.compat == CompatMode::Curl),
             }
         }
-        token
+        self.span.end = self.pos;
+        if !closed && !self.multiline_quotes && self.error.is_none() {
+            self.error = Some(ParsingError::new(
+                self.span.lineno,
+                String::from("unterminated quoted token"),
+            ));
+        }
     }
 
-    pub fn push_token(&mut self, token: &str) {
-        self.pushback.push_back(token.to_owned());
+    /// Record the first error found reading the tokens, see `check`.
+    pub fn fail(&mut self, lineno: u32, kind: ParsingErrorKind, message: String) {
+        if self.error.is_none() {
+            self.error = Some(ParsingError::with_kind(lineno, kind, message));
+        }
+    }
+
+    /// Fail with the error found reading the last tokens, like a quote not
+    /// closed on its line.
+    pub fn check(&mut self) -> Result<(), ParsingError> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    pub fn push_token(&mut self, token: Cow<'a, str>) {
+        self.pushback.push_back((token, self.span));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Lex<'_> {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        // The borrowed tokens are wiped with the content.
+        for (token, _) in self.pushback.iter_mut() {
+            if let Cow::Owned(token) = token {
+                token.zeroize();
+            }
+        }
     }
 }
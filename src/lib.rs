@@ -9,6 +9,53 @@ The `reqwest-netrc` crate adds the support of netrc to the `reqwest` crate via t
 $ crago add rust-netrc
 ```
 
+# Features
+
+- `capi`: export the C API of the `capi` module, declared by
+  `include/netrc.h`.
+- `curl`: configure the handles of the `curl` crate with the `curl` module.
+- `foldhash`: hash the machines of [`Map`] with `foldhash`, faster than the
+  default hasher for the large files and the frequent lookups.
+- `ftp`: log in the FTP sessions of the `suppaftp` crate, and run the `init`
+  macro, with the `ftp` module.
+- `gpg`: decrypt the `.gpg` files, like `~/.netrc.gpg` or `~/.authinfo.gpg`,
+  with the `gpg` command.
+- `http`: add the credentials to the requests of the `http` crate with the
+  `http` module.
+- `idna`: match the internationalized domain names with their ASCII form
+  (see [`normalize_host`]).
+- `keychain`: look up the passwords in the macOS Keychain with
+  [`Netrc::resolve`], and store them with the `keyring` module.
+- `notify`: watch the changes of a netrc file with `Netrc::watch`.
+- `onepassword`: read the `op://` passwords, which are 1Password secret
+  references, with [`Netrc::resolve`] and the `op` command.
+- `pass`: read the `pass:<name>` passwords from the `pass` password store
+  with [`Netrc::resolve`].
+- `secret-manager`: find the credentials in AWS Secrets Manager or Google
+  Cloud Secret Manager with `SecretManagerSource`.
+- `secret-service`: look up the passwords of the machines without entry in the
+  freedesktop Secret Service with [`Netrc::resolve`], and store them with the
+  `keyring` module.
+- `sops`: decrypt the netrc files encrypted with SOPS with the `sops` command.
+- `std` (enabled by default): read the netrc files and the environment. The
+  other features but `idna`, `url` and `zeroize` require it. Without it, the
+  crate is `no_std` and only needs `alloc`: the netrc are parsed from strings
+  with [`Netrc::from_bytes`], `str::parse` or [`NetrcDocument`], and looked
+  up, without the `include` directive and with [`Map`] being a `BTreeMap`.
+- `tokio`: read the netrc files without blocking the runtime of `tokio`
+  with `Netrc::new_async` and `Netrc::from_file_async`.
+- `url`: look up the authenticator of an URL with `Netrc::authenticator_for_url`.
+- `vault`: find the credentials in HashiCorp Vault with `VaultSource`.
+- `zeroize`: implement `Zeroize` for [`Authenticator`] and [`Netrc`] to wipe
+  all their values, when they are dropped with `Zeroizing`, and wipe the
+  content of the files once parsed.
+
+# WebAssembly
+
+On `wasm32-unknown-unknown`, the functions reading the environment and the
+files, like [`Netrc::new`] or [`Netrc::from_file`], are not available: parse
+the content with [`Netrc::from_bytes`] or [`str::parse`].
+
 # Example
 
 ```no_run
@@ -29,21 +76,94 @@ println!(
 
 */
 
-pub use netrc::{Authenticator, Netrc};
-use std::fs;
-use std::io;
-use std::io::ErrorKind;
-#[cfg(windows)]
-use std::iter::repeat;
-use std::path::{Path, PathBuf};
-use std::result;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub use builder::NetrcBuilder;
+pub use diff::NetrcDiff;
+pub use document::{Layout, MachineEntry, NetrcDocument};
+#[cfg(feature = "std")]
+pub use env::{Env, EnvPrecedence, SystemEnv};
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub use file::DiscoveryOptions;
+#[cfg(feature = "keychain")]
+pub use keychain::KEYCHAIN_PASSWORD;
+#[cfg(any(feature = "keychain", feature = "secret-service"))]
+pub use keyring::KEYRING_PASSWORD;
+pub use netrc::{
+    is_secure, normalize_host, Authenticator, CompatMode, Encoding, Format, Limits, LookupOptions,
+    Map, MergeStrategy, Netrc, ParseOptions, ParsingError, ParsingErrorKind, Span, Warning,
+    WarningKind,
+};
+#[cfg(feature = "secret-manager")]
+pub use secret_manager::{SecretManager, SecretManagerSource};
+#[cfg(feature = "std")]
+pub use source::{Chain, CredentialSource, EnvSource, MatchKind};
+#[cfg(feature = "std")]
+use std::io::Write;
+pub use token::{tokenize, Token, TokenKind, Tokens};
+#[cfg(feature = "vault")]
+pub use vault::VaultSource;
+#[cfg(feature = "notify")]
+pub use watch::NetrcWatcher;
+
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "curl")]
+pub mod curl;
+mod diff;
+mod document;
+#[cfg(feature = "std")]
+mod env;
+#[cfg(all(
+    feature = "std",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+mod file;
+#[cfg(feature = "ftp")]
+pub mod ftp;
+#[cfg(feature = "gpg")]
+mod gpg;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "keychain")]
+mod keychain;
+#[cfg(any(feature = "keychain", feature = "secret-service"))]
+pub mod keyring;
 mod lex;
 mod netrc;
+#[cfg(feature = "onepassword")]
+mod onepassword;
+#[cfg(feature = "pass")]
+mod pass;
+#[cfg(feature = "std")]
+mod resolve;
+#[cfg(feature = "secret-manager")]
+mod secret_manager;
+#[cfg(feature = "secret-service")]
+mod secret_service;
+#[cfg(feature = "sops")]
+mod sops;
+#[cfg(feature = "std")]
+mod source;
+mod token;
+#[cfg(feature = "vault")]
+mod vault;
+#[cfg(feature = "notify")]
+mod watch;
 
-pub type Result<T> = result::Result<T, Error>;
+#[cfg(feature = "std")]
+pub type Result<T> = std::result::Result<T, Error>;
 
 /// An error that can occur when processing a Netrc file.
+#[cfg(feature = "std")]
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// Wrap `std::io::Error` when we try to open the netrc file.
@@ -53,54 +173,71 @@ pub enum Error {
     /// Parsing error.
     #[error("{parser} in the file '{filename}'")]
     Parsing {
-        parser: netrc::ParsingError,
+        parser: ParsingError,
         filename: String,
     },
+
+    /// A machine or a macro is defined differently in two merged netrc.
+    #[error("conflicting definitions of '{0}'")]
+    Conflict(String),
+
+    /// Invalid netrc built with [`NetrcBuilder`].
+    #[error("invalid netrc: {0}")]
+    Invalid(String),
+
+    /// The netrc file contains passwords but is not private to the user (see
+    /// [`ParseOptions::check_permissions`]).
+    #[error("insecure netrc file '{filename}': {reason}")]
+    Insecure { filename: String, reason: String },
+
+    /// An encrypted netrc file can not be decrypted.
+    #[error("cannot decrypt the file '{filename}': {message}")]
+    Decryption { filename: String, message: String },
+
+    /// The keyring of the system refused a change.
+    #[error("keyring error: {0}")]
+    Keyring(String),
+
+    /// The changes of a netrc file can not be watched.
+    #[error("watch error: {0}")]
+    Watch(String),
 }
 
 impl Netrc {
-    /// Create a new `Netrc` object.
+    /// Create a new `Netrc` object from the content of a file, the invalid
+    /// UTF-8 sequences being replaced like by [`Netrc::from_file`].
     ///
-    /// Look up the `NETRC` environment variable if it is defined else that the
-    /// default `~/.netrc` file.
-    pub fn new() -> Result<Self> {
-        Self::get_file()
-            .ok_or(Error::Io(io::Error::new(
-                ErrorKind::NotFound,
-                "no netrc file found",
-            )))
-            .and_then(|f| Netrc::from_file(f.as_path()))
+    /// Unlike [`Netrc::from_file`], it is available on all the targets,
+    /// `wasm32-unknown-unknown` included, and without the `std` feature.
+    pub fn from_bytes(content: &[u8]) -> core::result::Result<Self, ParsingError> {
+        Netrc::from_bytes_with(content, &ParseOptions::default())
     }
 
-    /// Create a new `Netrc` object from a file.
-    pub fn from_file(file: &Path) -> Result<Self> {
-        String::from_utf8_lossy(&fs::read(file)?)
-            .parse()
-            .map_err(|e| Error::Parsing {
-                parser: e,
-                filename: file.display().to_string(),
-            })
+    /// Create a new `Netrc` object from the content of a file like
+    /// [`Netrc::from_bytes`], decoded and parsed with the given options.
+    pub fn from_bytes_with(
+        content: &[u8],
+        options: &ParseOptions,
+    ) -> core::result::Result<Self, ParsingError> {
+        options.limits.check_size(content.len())?;
+        let source = options.encoding.decode(content)?;
+        let res = Netrc::parse_with(&source, options);
+
+        #[cfg(feature = "zeroize")]
+        if let alloc::borrow::Cow::Owned(mut source) = source {
+            use zeroize::Zeroize;
+
+            source.zeroize();
+        }
+
+        res
     }
 
-    /// Search a netrc file.
-    ///
-    /// Look up the `NETRC` environment variable if it is defined else use the .netrc (or _netrc
-    /// file on windows) in the user's home directory.
-    pub fn get_file() -> Option<PathBuf> {
-        let env_var = std::env::var("NETRC")
-            .map(PathBuf::from)
-            .map(|f| shellexpand::path::tilde(&f).into_owned());
-
-        #[cfg(windows)]
-        let default = std::env::var("USERPROFILE")
-            .into_iter()
-            .flat_map(|home| repeat(home).zip([".netrc", "_netrc"]))
-            .map(|(home, file)| PathBuf::from(home).join(file));
-
-        #[cfg(not(windows))]
-        let default = std::env::var("HOME").map(|home| PathBuf::from(home).join(".netrc"));
-
-        env_var.into_iter().chain(default).find(|f| f.exists())
+    /// Write the netrc to `writer`.
+    #[cfg(feature = "std")]
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(writer, "{}", self)?;
+        Ok(())
     }
 }
 
@@ -108,69 +245,47 @@ impl Netrc {
 mod tests {
     use super::*;
 
-    const CONTENT: &str = "\
-machine cocolog-nifty.com
-login jmarten0
-password cC2&yt7OT
-
-machine wired.com
-login mstanlack1
-password gH4={wx=>VixU
-
-machine joomla.org
-login mbutterley2
-password hY5>yKqU&$vq&0
-";
-
-    fn create_netrc_file() -> PathBuf {
-        let dest = std::env::temp_dir().join("mynetrc");
-        if !dest.exists() {
-            std::fs::write(&dest, CONTENT).unwrap();
-        }
-        dest
-    }
-
-    fn check_nrc(nrc: &Netrc) {
-        assert_eq!(nrc.hosts.len(), 3);
-        assert_eq!(
-            nrc.hosts["cocolog-nifty.com"],
-            Authenticator::new("jmarten0", "", "cC2&yt7OT")
-        );
-        assert_eq!(
-            nrc.hosts["wired.com"],
-            Authenticator::new("mstanlack1", "", "gH4={wx=>VixU")
-        );
+    #[test]
+    fn test_from_bytes() {
+        let nrc =
+            Netrc::from_bytes(b"machine host.domain.com login log password pa\xffss").unwrap();
         assert_eq!(
-            nrc.hosts["joomla.org"],
-            Authenticator::new("mbutterley2", "", "hY5>yKqU&$vq&0")
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log", "", "pa\u{fffd}ss")
         );
+        assert_eq!(Netrc::from_bytes(b"machine").unwrap_err().lineno(), 1);
     }
 
     #[test]
-    fn test_new_env() {
-        let fi = create_netrc_file();
-        std::env::set_var("NETRC", fi);
-        let nrc = Netrc::new().unwrap();
-        check_nrc(&nrc);
-    }
-
-    #[test]
-    fn test_new_default() {}
+    fn test_from_bytes_with_encoding() {
+        let content = b"machine host.domain.com\nlogin log password \x80pa\xe9ss";
+        let password = |encoding| {
+            let options = ParseOptions {
+                encoding,
+                ..ParseOptions::default()
+            };
+            Netrc::from_bytes_with(content, &options)
+                .map(|nrc| nrc.hosts["host.domain.com"].password.clone())
+        };
 
-    #[test]
-    fn test_from_file_failed() {
         assert_eq!(
-            Netrc::from_file(Path::new("/netrc/file/not/exists/on/no/netrc"))
-                .unwrap_err()
-                .to_string(),
-            "I/O error: No such file or directory (os error 2)"
+            password(Encoding::Utf8Lossy).unwrap(),
+            "\u{fffd}pa\u{fffd}ss"
         );
+        assert_eq!(password(Encoding::Latin1).unwrap(), "\u{80}paéss");
+        assert_eq!(password(Encoding::Windows1252).unwrap(), "€paéss");
+        let err = password(Encoding::Utf8).unwrap_err();
+        assert_eq!(err.lineno(), 2);
+        assert_eq!(err.message(), "invalid UTF-8 sequence");
     }
 
     #[test]
-    fn test_from_file() {
-        let fi = create_netrc_file();
-        let nrc = Netrc::from_file(fi.as_path()).unwrap();
-        check_nrc(&nrc);
+    fn test_to_writer() {
+        let nrc: Netrc = "machine host.domain.com login log password pass"
+            .parse()
+            .unwrap();
+        let mut out = Vec::new();
+        nrc.to_writer(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), nrc.to_string());
     }
 }
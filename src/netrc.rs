@@ -1,22 +1,173 @@
 //! This parser and the tests are a translation of the official Python netrc library.
 
 use crate::lex::Lex;
-use std::collections::HashMap;
+use alloc::borrow::{Cow, ToOwned};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv6Addr};
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+/// Map of the entries of a netrc: a `HashMap`, or a `BTreeMap` without the
+/// `std` feature.
+#[cfg(all(feature = "std", not(feature = "foldhash")))]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+
+/// Map of the entries of a netrc: a `HashMap` with the faster, but not
+/// DoS-resistant, hasher of `foldhash`.
+#[cfg(all(feature = "std", feature = "foldhash"))]
+pub type Map<K, V> = std::collections::HashMap<K, V, foldhash::fast::RandomState>;
+
+/// Map of the entries of a netrc: a `HashMap`, or a `BTreeMap` without the
+/// `std` feature.
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
 
 #[derive(Debug)]
 pub struct ParsingError {
     lineno: u32,
     message: String,
+    kind: ParsingErrorKind,
+}
+
+/// Kind of a [`ParsingError`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParsingErrorKind {
+    /// The content is not a valid netrc.
+    Syntax,
+
+    /// The content is larger than [`Limits::max_size`].
+    TooLarge,
+
+    /// A token or a line is longer than [`Limits::max_token_len`].
+    TokenTooLong,
+
+    /// The netrc has more entries than [`Limits::max_entries`].
+    TooManyEntries,
+
+    /// A macro has more lines than [`Limits::max_macro_lines`].
+    TooManyMacroLines,
+}
+
+impl ParsingError {
+    pub(crate) fn new(lineno: u32, message: String) -> Self {
+        ParsingError::with_kind(lineno, ParsingErrorKind::Syntax, message)
+    }
+
+    pub(crate) fn with_kind(lineno: u32, kind: ParsingErrorKind, message: String) -> Self {
+        ParsingError {
+            lineno,
+            message,
+            kind,
+        }
+    }
+
+    /// Kind of the error, to tell the exceeded limits from the invalid
+    /// content.
+    pub fn kind(&self) -> ParsingErrorKind {
+        self.kind
+    }
+
+    /// Line of the error (1-based).
+    pub fn lineno(&self) -> u32 {
+        self.lineno
+    }
+
+    /// Description of the error, without its line.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
-impl std::fmt::Display for ParsingError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "parsing error: {} (line {})", self.message, self.lineno)
     }
 }
 
+/// Problem found by the parser which does not prevent the parsing, like a
+/// token skipped in lenient mode.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Warning {
+    lineno: u32,
+    message: String,
+    kind: WarningKind,
+}
+
+/// Kind of a [`Warning`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WarningKind {
+    /// An unknown token was skipped, in lenient mode or like curl or BSD ftp.
+    UnknownToken,
+
+    /// A `machine` entry follows the `default` entry, which BSD ftp ignores.
+    MachineAfterDefault,
+
+    /// A machine, or the `default` entry, is defined again: the last entry
+    /// shadows the first one, or is ignored like with curl or BSD ftp.
+    DuplicateEntry,
+
+    /// The password of an entry is empty.
+    EmptyPassword,
+
+    /// A name or a value starts or ends with whitespace, or has whitespace
+    /// other than spaces, like tabs or invisible characters, likely pasted by
+    /// mistake.
+    SuspiciousWhitespace,
+}
+
+impl Warning {
+    pub(crate) fn new(lineno: u32, kind: WarningKind, message: String) -> Self {
+        Warning {
+            lineno,
+            message,
+            kind,
+        }
+    }
+
+    /// Kind of the warning.
+    pub fn kind(&self) -> WarningKind {
+        self.kind
+    }
+
+    /// Line of the warning (1-based).
+    pub fn lineno(&self) -> u32 {
+        self.lineno
+    }
+
+    /// Description of the warning, without its line.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl core::fmt::Display for Warning {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "warning: {} (line {})", self.message, self.lineno)
+    }
+}
+
+/// Location of an entry in the netrc source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    /// Line number where the entry starts (1-based).
+    pub lineno: u32,
+
+    /// Byte offset of the start of the entry.
+    pub start: usize,
+
+    /// Byte offset just past the end of the entry.
+    pub end: usize,
+}
+
 /// Authenticators for host.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+///
+/// The `Debug` output masks the account, the password and the values of the
+/// extras, so that they do not leak into the logs or the panic messages.
+#[derive(PartialEq, Eq, Clone, Default)]
 pub struct Authenticator {
     /// Identify a user on the remote machine.
     pub login: String,
@@ -26,6 +177,16 @@ pub struct Authenticator {
 
     /// Supply a password
     pub password: String,
+
+    /// Port or service name, only for the authinfo format.
+    pub port: String,
+
+    /// Protocol, only for the authinfo format.
+    pub protocol: String,
+
+    /// Unknown tokens of the entry, collected when
+    /// [`ParseOptions::extras`] is enabled.
+    pub extras: Map<String, String>,
 }
 
 impl Authenticator {
@@ -35,281 +196,1729 @@ impl Authenticator {
             login: login.to_owned(),
             account: account.to_owned(),
             password: password.to_owned(),
+            port: String::new(),
+            protocol: String::new(),
+            extras: Map::default(),
         }
     }
 }
 
-/// Represents the netrc file.
-#[derive(Debug, Default)]
-pub struct Netrc {
-    /// Dictionary mapping host names to the authentificators.
-    pub hosts: HashMap<String, Authenticator>,
-
-    /// Dictionary mapping macro names to string lists.
-    pub macros: HashMap<String, Vec<String>>,
+impl core::fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let extras: Map<_, _> = self.extras.iter().map(|(k, v)| (k, Redacted(v))).collect();
+        f.debug_struct("Authenticator")
+            .field("login", &self.login)
+            .field("account", &Redacted(&self.account))
+            .field("password", &Redacted(&self.password))
+            .field("port", &self.port)
+            .field("protocol", &self.protocol)
+            .field("extras", &extras)
+            .finish()
+    }
 }
 
-impl std::fmt::Display for Netrc {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut rep = String::new();
-        for (host, attrs) in self.hosts.iter() {
-            rep.push_str(&format!("machine {}\n\tlogin {}\n", host, attrs.login));
-            if !attrs.account.is_empty() {
-                rep.push_str(&format!("\taccount  {}\n", attrs.account));
-            }
-            rep.push_str(&format!("\tpassword  {}\n", attrs.password));
+/// Secret masked in the `Debug` output, unless it is empty.
+struct Redacted<'a>(&'a str);
+
+impl core::fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0.is_empty() {
+            true => write!(f, "\"\""),
+            false => write!(f, "\"***\""),
         }
-        for (macro_, lines) in self.macros.iter() {
-            rep.push_str(&format!("macdef {}\n", macro_));
-            for line in lines.iter() {
-                rep.push_str(&format!("{}\n", line));
-            }
+    }
+}
+
+/// Wipe all the values of the authenticator, the names of the extra tokens
+/// included. Wrap it in [`zeroize::Zeroizing`] to wipe it when it is dropped.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Authenticator {
+    fn zeroize(&mut self) {
+        self.login.zeroize();
+        self.account.zeroize();
+        self.password.zeroize();
+        self.port.zeroize();
+        self.protocol.zeroize();
+        for (mut name, mut value) in core::mem::take(&mut self.extras) {
+            name.zeroize();
+            value.zeroize();
         }
-        write!(f, "{}", rep)
     }
 }
 
-impl std::str::FromStr for Netrc {
-    type Err = ParsingError;
+/// Format of the parsed file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Format {
+    /// The netrc format.
+    #[default]
+    Netrc,
 
-    fn from_str(s: &str) -> Result<Self, ParsingError> {
-        let mut res = Netrc::default();
-        let mut lexer = Lex::new(s);
+    /// The Emacs authinfo format (`~/.authinfo`), a netrc with the additional
+    /// `port` and `protocol` tokens.
+    Authinfo,
+}
 
-        loop {
-            let saved_lineno = lexer.lineno;
-            let tt = lexer.get_token();
-            if tt.is_empty() {
-                break;
-            }
-            if tt.chars().nth(0) == Some('#') {
-                if lexer.lineno == saved_lineno && tt.len() == 1 {
-                    lexer.read_line();
-                }
-                continue;
-            }
+/// Dialect of the netrc files, to parse them like a given program.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum CompatMode {
+    /// Like the `netrc` module of Python: a `#` token is a comment, `user`
+    /// is an alias of `login`, the unknown tokens are errors, and the last
+    /// entry of a machine is used.
+    #[default]
+    Python,
 
-            #[allow(clippy::needless_late_init)]
-            let entryname;
-            match tt.as_str() {
-                "" => {
-                    break;
-                }
-                "machine" => {
-                    entryname = lexer.get_token();
-                }
-                "default" => {
-                    entryname = String::from("default");
-                }
-                "macdef" => {
-                    entryname = lexer.get_token();
-                    let mut v = Vec::new();
-                    loop {
-                        let line = lexer.read_line();
-                        if line.trim().is_empty() {
-                            break;
-                        }
-                        v.push(line.trim().to_owned());
-                    }
-                    res.macros.insert(entryname, v);
-                    continue;
-                }
-                _ => {
-                    return Err(ParsingError {
-                        lineno: lexer.lineno,
-                        message: format!("bad toplevel token '{}'", tt),
-                    });
-                }
-            };
-            if entryname.is_empty() {
-                return Err(ParsingError {
-                    lineno: lexer.lineno,
-                    message: format!("missing '{}' name", tt),
-                });
-            }
+    /// Like curl: a token starting with `#` outside of quotes comments the
+    /// rest of the line, the unknown tokens are ignored, the `\n`, `\r` and
+    /// `\t` escapes are only read in the quoted tokens, and the first entry
+    /// of a machine is used.
+    Curl,
 
-            let mut auth = Authenticator::default();
+    /// Like the `ftp` of the BSD: no comments, `passwd` is an alias of
+    /// `password`, the unknown tokens are ignored, and the first entry of a
+    /// machine is used, the machines following `default` being ignored.
+    Bsd,
+}
 
-            loop {
-                let prev_lineno = lexer.lineno;
-                let tt = lexer.get_token();
-                if tt.starts_with('#') {
-                    if lexer.lineno == prev_lineno {
-                        lexer.read_line();
-                    }
-                    continue;
-                }
-                match tt.as_str() {
-                    "" | "machine" | "default" | "macdef" => {
-                        res.hosts.insert(entryname, auth);
-                        lexer.push_token(&tt);
-                        break;
-                    }
-                    "login" | "user" => {
-                        auth.login = lexer.get_token();
-                    }
-                    "account" => {
-                        auth.account = lexer.get_token();
-                    }
-                    "password" => {
-                        auth.password = lexer.get_token();
-                    }
-                    _ => {
-                        return Err(ParsingError {
-                            lineno: lexer.lineno,
-                            message: format!("bad follower token '{}'", tt),
-                        });
-                    }
-                };
-            }
-        }
+/// Encoding of the netrc files.
+///
+/// The files are always written in UTF-8.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Encoding {
+    /// UTF-8, the invalid sequences being replaced by `U+FFFD`.
+    #[default]
+    Utf8Lossy,
 
-        Ok(res)
-    }
-}
+    /// UTF-8, failing on the invalid sequences.
+    Utf8,
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+    /// ISO-8859-1 (Latin-1), each byte being the character of the same code.
+    Latin1,
 
-    use super::*;
+    /// Windows-1252, the Latin-1 of Windows with `€` and the typographic
+    /// quotes in the `0x80`–`0x9F` range.
+    Windows1252,
+}
 
-    #[test]
-    fn test_toplevel_non_ordered_tokens() {
-        let nrc = Netrc::from_str(
-            "\
-            machine host.domain.com password pass1 login log1 account acct1
-            default login log2 password pass2 account acct2
-        ",
-        )
-        .unwrap();
+/// Characters of the `0x80`–`0x9F` bytes in Windows-1252, the undefined
+/// bytes being the control characters of the same code.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
 
-        assert_eq!(
-            nrc.hosts["host.domain.com"],
-            Authenticator::new("log1", "acct1", "pass1")
-        );
-        assert_eq!(
-            nrc.hosts["default"],
-            Authenticator::new("log2", "acct2", "pass2")
-        );
+impl Encoding {
+    /// Decode the content of a netrc file.
+    ///
+    /// ```
+    /// use netrc::Encoding;
+    ///
+    /// assert_eq!(Encoding::Latin1.decode(b"caf\xe9").unwrap(), "café");
+    /// assert_eq!(Encoding::Windows1252.decode(b"\x80").unwrap(), "€");
+    /// assert!(Encoding::Utf8.decode(b"caf\xe9").is_err());
+    /// ```
+    pub fn decode<'a>(&self, content: &'a [u8]) -> Result<Cow<'a, str>, ParsingError> {
+        match self {
+            Encoding::Utf8Lossy => Ok(String::from_utf8_lossy(content)),
+            Encoding::Utf8 => core::str::from_utf8(content)
+                .map(Cow::Borrowed)
+                .map_err(|e| {
+                    let valid = &content[..e.valid_up_to()];
+                    let lineno = valid.iter().filter(|&&b| b == b'\n').count() as u32 + 1;
+                    ParsingError::new(lineno, String::from("invalid UTF-8 sequence"))
+                }),
+            _ if content.is_ascii() => Ok(Cow::Borrowed(
+                core::str::from_utf8(content).unwrap_or_default(),
+            )),
+            Encoding::Latin1 => Ok(Cow::Owned(content.iter().map(|&b| char::from(b)).collect())),
+            Encoding::Windows1252 => Ok(Cow::Owned(
+                content
+                    .iter()
+                    .map(|&b| match b {
+                        0x80..=0x9f => WINDOWS_1252[usize::from(b - 0x80)],
+                        _ => char::from(b),
+                    })
+                    .collect(),
+            )),
+        }
     }
+}
 
-    #[test]
-    fn test_toplevel_tokens() {
-        let nrc = Netrc::from_str(
-            "\
-            machine host.domain.com login log1 password pass1 account acct1
-            default login log2 password pass2 account acct2
-        ",
-        )
-        .unwrap();
-        assert_eq!(
-            nrc.hosts["host.domain.com"],
-            Authenticator::new("log1", "acct1", "pass1")
-        );
-        assert_eq!(
-            nrc.hosts["default"],
-            Authenticator::new("log2", "acct2", "pass2")
-        );
+/// Normalize a machine name: in lower case and without the trailing dot.
+///
+/// With the `idna` feature, the internationalized domain names are converted
+/// to their ASCII form, so that `bücher.example` and `xn--bcher-kva.example`
+/// are the same machine.
+///
+/// The IPv6 addresses are written in their canonical form, without brackets
+/// unless they are followed by a port.
+///
+/// The port of a `host:port` name is kept.
+///
+/// ```
+/// assert_eq!(netrc::normalize_host("GitHub.com."), "github.com");
+/// assert_eq!(netrc::normalize_host("GitHub.com.:8443"), "github.com:8443");
+/// assert_eq!(netrc::normalize_host("[0:0::1]"), "::1");
+/// assert_eq!(netrc::normalize_host("[0:0::1]:8443"), "[::1]:8443");
+/// ```
+pub fn normalize_host(host: &str) -> String {
+    if host.parse::<Ipv6Addr>().is_ok() {
+        return normalize_ipv6(host);
+    }
+    if let Some((addr, rest)) = host.strip_prefix('[').and_then(|h| h.split_once(']')) {
+        match rest.strip_prefix(':') {
+            None if rest.is_empty() => return normalize_ipv6(addr),
+            Some(port) if is_port(port) => return format!("[{}]:{}", normalize_ipv6(addr), port),
+            _ => {}
+        }
     }
 
-    #[test]
-    fn test_macros() {
-        let nrc = Netrc::from_str(
-            "\
-            macdef macro1
-            line1
-            line2
-
-            macdef macro2
-            line3
-            line4
-            ",
-        )
-        .unwrap();
-        assert_eq!(nrc.macros["macro1"], vec!["line1", "line2"]);
-        assert_eq!(nrc.macros["macro2"], vec!["line3", "line4"]);
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) if is_port(port) => (name, Some(port)),
+        _ => (host, None),
+    };
+    let name = name.strip_suffix('.').unwrap_or(name);
+    #[cfg(feature = "idna")]
+    let mut res = match name.is_ascii() {
+        true => name.to_lowercase(),
+        false => idna::domain_to_ascii(name).unwrap_or_else(|_| name.to_lowercase()),
+    };
+    #[cfg(not(feature = "idna"))]
+    let mut res = name.to_lowercase();
+    if let Some(port) = port {
+        res.push(':');
+        res.push_str(port);
     }
+    res
+}
 
-    #[test]
-    fn test_optional_tokens_machine() {
-        let data = vec![
-            "machine host.domain.com",
-            "machine host.domain.com login",
-            "machine host.domain.com account",
-            "machine host.domain.com password",
-            "machine host.domain.com login \"\" account",
-            "machine host.domain.com login \"\" password",
-            "machine host.domain.com account \"\" password",
-        ];
+/// Whether the credentials sent with `scheme` to `host` can not be read by a
+/// third party: the scheme is encrypted (`https` or `wss`), or it is `http`
+/// or `ws` and the host is `localhost` or a loopback address. The other
+/// schemes, like `ftp` or `smtp`, are not secure.
+///
+/// The IPv6 addresses can be in brackets, like in the URLs.
+///
+/// ```
+/// assert!(netrc::is_secure("https", "domain.com"));
+/// assert!(netrc::is_secure("http", "[::1]"));
+/// assert!(!netrc::is_secure("http", "domain.com"));
+/// assert!(!netrc::is_secure("ftp", "localhost"));
+/// ```
+pub fn is_secure(scheme: &str, host: &str) -> bool {
+    let loopback = match scheme.to_ascii_lowercase().as_str() {
+        "https" | "wss" => return true,
+        "http" | "ws" => true,
+        _ => false,
+    };
+    let host = host.strip_prefix('[').unwrap_or(host);
+    let host = host.strip_suffix(']').unwrap_or(host);
+    loopback
+        && (host.eq_ignore_ascii_case("localhost")
+            || host.parse::<IpAddr>().is_ok_and(|addr| addr.is_loopback()))
+}
 
-        for item in data {
-            let nrc = Netrc::from_str(item).unwrap();
-            assert_eq!(nrc.hosts["host.domain.com"], Authenticator::new("", "", ""));
+fn is_port(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn normalize_ipv6(addr: &str) -> String {
+    addr.parse::<Ipv6Addr>()
+        .map(|a| a.to_string())
+        .unwrap_or_else(|_| addr.to_lowercase())
+}
+
+/// Options of [`Netrc::lookup_with`].
+#[derive(Debug, Clone)]
+pub struct LookupOptions {
+    /// Walk up the parent domains of a machine without entry, like
+    /// `domain.com` for `api.internal.domain.com`, before using the `default`
+    /// entry.
+    pub parent_domains: bool,
+
+    /// Normalize the looked up machine name with [`normalize_host`] (enabled
+    /// by default).
+    pub normalize_hosts: bool,
+
+    /// Fall back to the `default` entry (enabled by default).
+    pub default_entry: bool,
+}
+
+impl Default for LookupOptions {
+    fn default() -> Self {
+        LookupOptions {
+            parent_domains: false,
+            normalize_hosts: true,
+            default_entry: true,
         }
     }
+}
 
-    #[test]
-    fn test_optional_tokens_default() {
-        let data = vec![
-            "default",
-            "default login",
-            "default account",
-            "default password",
-            "default login \"\" account",
-            "default login \"\" password",
-            "default account \"\" password",
-        ];
+/// Limits of the parser, to parse an untrusted netrc without unbounded memory
+/// use (no limit by default).
+///
+/// An exceeded limit fails the parsing with the [`ParsingErrorKind`] of the
+/// limit.
+///
+/// ```
+/// use netrc::{Limits, Netrc, ParseOptions, ParsingErrorKind};
+///
+/// let options = ParseOptions {
+///     limits: Limits {
+///         max_token_len: Some(1024),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+/// let data = format!("machine host.com password {}", "x".repeat(2048));
+/// let err = Netrc::parse_with(&data, &options).unwrap_err();
+/// assert_eq!(err.kind(), ParsingErrorKind::TokenTooLong);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Limits {
+    /// Maximum size of the content in bytes, once decoded to UTF-8. The files
+    /// larger than this size are not read entirely.
+    pub max_size: Option<usize>,
 
-        for item in data {
-            let nrc = Netrc::from_str(item).unwrap();
-            assert_eq!(nrc.hosts["default"], Authenticator::new("", "", ""));
+    /// Maximum length in bytes of a token, of a line of a macro, and of a
+    /// comment.
+    pub max_token_len: Option<usize>,
+
+    /// Maximum number of entries: the machines, the `default` entry and the
+    /// macros, the included ones counted.
+    pub max_entries: Option<usize>,
+
+    /// Maximum number of lines of a macro.
+    pub max_macro_lines: Option<usize>,
+}
+
+impl Limits {
+    /// Fail if the content is larger than the maximum size.
+    pub(crate) fn check_size(&self, len: usize) -> Result<(), ParsingError> {
+        match self.max_size {
+            Some(max) if len > max => Err(ParsingError::with_kind(
+                1,
+                ParsingErrorKind::TooLarge,
+                format!("content larger than {} bytes", max),
+            )),
+            _ => Ok(()),
         }
     }
 
-    #[test]
-    fn test_invalid_tokens() {
-        let data = vec![
-            (
-                "invalid host.domain.com",
-                "parsing error: bad toplevel token 'invalid' (line 1)",
-            ),
-            (
-                "machine host.domain.com invalid",
-                "parsing error: bad follower token 'invalid' (line 1)",
-            ),
-            (
-                "machine host.domain.com login log password pass account acct invalid",
-                "parsing error: bad follower token 'invalid' (line 1)",
-            ),
-            (
-                "default host.domain.com invalid",
-                "parsing error: bad follower token 'host.domain.com' (line 1)",
-            ),
-            (
-                "default host.domain.com login log password pass account acct invalid",
-                "parsing error: bad follower token 'host.domain.com' (line 1)",
-            ),
-        ];
-
-        for (item, msg) in data {
-            let nrc = Netrc::from_str(item);
-            assert_eq!(nrc.unwrap_err().to_string(), msg);
+    /// Fail if the netrc has more entries than the maximum.
+    fn check_entries(&self, nrc: &Netrc, lineno: u32) -> Result<(), ParsingError> {
+        let count = nrc.hosts.len() + nrc.macros.len() + usize::from(nrc.default_entry.is_some());
+        match self.max_entries {
+            Some(max) if count > max => Err(ParsingError::with_kind(
+                lineno,
+                ParsingErrorKind::TooManyEntries,
+                format!("more than {} entries", max),
+            )),
+            _ => Ok(()),
         }
     }
+}
 
-    fn test_token_x(data: &str, token: &str, value: &str) {
-        let nrc = Netrc::from_str(data).unwrap();
-        match token {
-            "login" => {
-                assert_eq!(
-                    nrc.hosts["host.domain.com"],
-                    Authenticator::new(value, "acct", "pass")
-                );
-            }
-            "account" => {
-                assert_eq!(
-                    nrc.hosts["host.domain.com"],
+/// Options of the netrc parser.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Format of the file.
+    pub format: Format,
+
+    /// Dialect of the file (the one of Python by default). The unknown
+    /// tokens are still collected with `extras`.
+    pub compat: CompatMode,
+
+    /// Skip the unknown tokens instead of failing, like the ones of the
+    /// authinfo files or of the extensions of other programs: an unknown
+    /// token of an entry is skipped with the token following it as value.
+    /// The skipped tokens are reported by [`Netrc::parse_with_warnings`].
+    pub lenient: bool,
+
+    /// Fail on a `machine` entry following the `default` entry, which must
+    /// be the last one as specified by ftp. Otherwise, the entry is reported
+    /// by [`Netrc::parse_with_warnings`] (and ignored with
+    /// [`CompatMode::Bsd`]).
+    pub strict_default: bool,
+
+    /// Encoding of the files read by [`Netrc::from_file_with`], the included
+    /// files and [`Netrc::from_bytes_with`] (UTF-8 with the invalid sequences
+    /// replaced by default).
+    pub encoding: Encoding,
+
+    /// Collect the unknown tokens of the machine entries, with the token
+    /// following them as value, into [`Authenticator::extras`] instead of
+    /// failing.
+    pub extras: bool,
+
+    /// Normalize the machine names with [`normalize_host`] (enabled by
+    /// default). Disable it to keep the names as written in the file.
+    pub normalize_hosts: bool,
+
+    /// Allow the `include <path>` toplevel directive, which merges the
+    /// entries of another netrc file. A relative path is relative to the
+    /// directory of the including file, or to the current directory when
+    /// parsing a string. Requires the `std` feature.
+    pub includes: bool,
+
+    /// Maximum nesting of the included files (10 by default).
+    pub max_include_depth: usize,
+
+    /// Allow the newlines in the quoted tokens (enabled by default), the line
+    /// numbers of the errors counting them. Disable it to fail on a quote not
+    /// closed on its line, instead of reading the next lines into the token.
+    pub multiline_quotes: bool,
+
+    /// Read the tokens in single quotes, like `password 'my pass'`, without
+    /// escapes inside them as in the shell (disabled by default).
+    pub single_quotes: bool,
+
+    /// Keep the lines of the macros in [`Netrc::macros`] (enabled by
+    /// default). Disable it to skip the bodies of the macros when only the
+    /// credentials are needed.
+    pub macros: bool,
+
+    /// Limits of the parser (none by default).
+    pub limits: Limits,
+
+    /// Refuse a file accessible by other users, or not owned by the current
+    /// user, when it contains the password of a non-anonymous login, like the
+    /// `netrc` module of Python. Only checked by [`Netrc::from_file_with`], on
+    /// Unix with the mode and the owner of the file, and on Windows with its
+    /// DACL, which must not grant an access to everyone, to the authenticated
+    /// users or to the users of the machine. It is skipped on the other
+    /// platforms.
+    pub check_permissions: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            format: Format::default(),
+            compat: CompatMode::default(),
+            lenient: false,
+            strict_default: false,
+            encoding: Encoding::default(),
+            extras: false,
+            normalize_hosts: true,
+            includes: false,
+            max_include_depth: 10,
+            multiline_quotes: true,
+            single_quotes: false,
+            macros: true,
+            limits: Limits::default(),
+            check_permissions: false,
+        }
+    }
+}
+
+/// Represents the netrc file.
+#[derive(Debug, Default)]
+pub struct Netrc {
+    /// Dictionary mapping host names to the authentificators, without the
+    /// `default` entry: a machine named `default` is a machine like the
+    /// others.
+    pub hosts: Map<String, Authenticator>,
+
+    /// Authenticator of the `default` entry, used for the machines without
+    /// entry.
+    pub default_entry: Option<Authenticator>,
+
+    /// Dictionary mapping macro names to string lists.
+    pub macros: Map<String, Vec<String>>,
+
+    /// Dictionary mapping host names to their location in the source.
+    pub spans: Map<String, Span>,
+
+    /// Dictionary mapping macro names to their location in the source.
+    pub macro_spans: Map<String, Span>,
+
+    /// Location of the `default` entry in the source.
+    pub default_span: Option<Span>,
+}
+
+/// Wipe the machine names, the authenticators and the macros of the netrc,
+/// and forget the locations of the entries. Wrap it in
+/// [`zeroize::Zeroizing`] to wipe it when it is dropped.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Netrc {
+    fn zeroize(&mut self) {
+        for (mut name, mut auth) in core::mem::take(&mut self.hosts) {
+            name.zeroize();
+            auth.zeroize();
+        }
+        if let Some(mut auth) = self.default_entry.take() {
+            auth.zeroize();
+        }
+        for (mut name, mut lines) in core::mem::take(&mut self.macros) {
+            name.zeroize();
+            lines.iter_mut().for_each(zeroize::Zeroize::zeroize);
+        }
+        let spans = core::mem::take(&mut self.spans);
+        let macro_spans = core::mem::take(&mut self.macro_spans);
+        for (mut name, _) in spans.into_iter().chain(macro_spans) {
+            name.zeroize();
+        }
+        self.default_span = None;
+    }
+}
+
+/// How to resolve the conflicts when merging two netrc.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MergeStrategy {
+    /// Keep the existing entries.
+    PreferSelf,
+
+    /// Replace the existing entries by the merged ones.
+    PreferOther,
+
+    /// Fail with [`Error::Conflict`](crate::Error::Conflict) if a machine or a
+    /// macro is defined differently in both netrc.
+    Error,
+}
+
+impl Netrc {
+    /// Return a `(login, account, password)` tuple for `host`, like the
+    /// `authenticators` method of the Python netrc library.
+    ///
+    /// The `default` entry is used if `host` has no entry. Unlike
+    /// [`Netrc::lookup`], `host` must be written exactly like the machine
+    /// name.
+    pub fn authenticators(&self, host: &str) -> Option<(&str, &str, &str)> {
+        self.hosts
+            .get(host)
+            .or(self.default_entry.as_ref())
+            .map(|a| (a.login.as_str(), a.account.as_str(), a.password.as_str()))
+    }
+
+    /// Machines with the `default` entry under the `default` name, like the
+    /// `hosts` of the Python netrc library and of the versions of this crate
+    /// before [`Netrc::default_entry`]. The `default` entry replaces a machine
+    /// named `default`.
+    ///
+    /// ```
+    /// use netrc::Netrc;
+    ///
+    /// let nrc: Netrc = "machine host.com login log\ndefault login anonymous"
+    ///     .parse()
+    ///     .unwrap();
+    /// let hosts = nrc.hosts_with_default();
+    /// assert_eq!(hosts["host.com"].login, "log");
+    /// assert_eq!(hosts["default"].login, "anonymous");
+    /// ```
+    pub fn hosts_with_default(&self) -> Map<String, Authenticator> {
+        let mut hosts = self.hosts.clone();
+        if let Some(auth) = &self.default_entry {
+            hosts.insert(String::from("default"), auth.clone());
+        }
+        hosts
+    }
+
+    /// Get the authenticator of a machine.
+    ///
+    /// The machine name is normalized with [`normalize_host`], like the names
+    /// of the parsed entries, so an IPv6 address can be given with or without
+    /// brackets. With a port, the `host:port` entry is looked up first. Then the `host`
+    /// entry is used, unless it is restricted to another port with the `port`
+    /// token of the authinfo format. If the machine has no entry, the most
+    /// specific wildcard entry (like `*.domain.com`) is used, and finally the
+    /// `default` entry. As specified by ftp, the `default` entry is not used
+    /// for a machine listed in the netrc, even if its entries are restricted
+    /// to other ports.
+    pub fn lookup(&self, host: &str, port: Option<u16>) -> Option<&Authenticator> {
+        self.lookup_with(host, port, &LookupOptions::default())
+    }
+
+    /// Get the authenticator of a machine like [`Netrc::lookup`], with the
+    /// given options.
+    ///
+    /// The parent domains are tried from the most specific, each one after
+    /// the wildcard entry of the same level.
+    pub fn lookup_with(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        options: &LookupOptions,
+    ) -> Option<&Authenticator> {
+        let normalized;
+        let host = if options.normalize_hosts {
+            normalized = normalize_host(host);
+            normalized.as_str()
+        } else {
+            host
+        };
+        let is_ip = host.parse::<IpAddr>().is_ok();
+        self.lookup_host(host, port)
+            .or_else(|| {
+                host.match_indices('.')
+                    .filter(|_| !is_ip)
+                    .find_map(|(i, _)| {
+                        self.lookup_host(&format!("*{}", &host[i..]), port)
+                            .or_else(|| {
+                                options
+                                    .parent_domains
+                                    .then(|| self.lookup_host(&host[i + 1..], port))
+                                    .flatten()
+                            })
+                    })
+            })
+            .or_else(|| options.default_entry.then(|| self.fallback(host)).flatten())
+    }
+
+    /// Get the `default` entry for `host`, which is only used for the
+    /// machines without any entry: a machine with entries restricted to other
+    /// ports or protocols does not fall back to it.
+    pub(crate) fn fallback(&self, host: &str) -> Option<&Authenticator> {
+        let prefix = match host.contains(':') {
+            true => format!("[{}]:", host),
+            false => format!("{}:", host),
+        };
+        let listed = self.hosts.contains_key(host)
+            || self.hosts.keys().any(|name| name.starts_with(&prefix));
+        match listed {
+            true => None,
+            false => self.default_entry.as_ref(),
+        }
+    }
+
+    /// Get the authenticator for the host and the port of an URL, like
+    /// [`Netrc::lookup`].
+    ///
+    /// The default port of the scheme is used if the URL has no port, so that
+    /// a `host:443` entry matches `https://host/`.
+    #[cfg(feature = "url")]
+    pub fn authenticator_for_url(&self, url: &url::Url) -> Option<&Authenticator> {
+        url.host_str()
+            .and_then(|host| self.lookup(host, url.port_or_known_default()))
+    }
+
+    /// Get the authenticator of the `host` entry, without fallback.
+    fn lookup_host(&self, host: &str, port: Option<u16>) -> Option<&Authenticator> {
+        port.and_then(|p| match host.contains(':') {
+            true => self.hosts.get(&format!("[{}]:{}", host, p)),
+            false => self.hosts.get(&format!("{}:{}", host, p)),
+        })
+        .or_else(|| {
+            self.hosts.get(host).filter(|auth| match port {
+                Some(p) => auth.port.is_empty() || auth.port == p.to_string(),
+                None => true,
+            })
+        })
+    }
+
+    /// Expand a macro with its arguments, like the `$` command of ftp.
+    ///
+    /// In the lines of the macro, `$1` to `$9` are replaced by the arguments
+    /// (by nothing if missing), and `\` escapes the next character, like `\$`
+    /// for a `$`. If the macro uses `$i`, its lines are repeated for each
+    /// argument, `$i` being replaced by it. Return `None` if the macro is not
+    /// defined.
+    ///
+    /// ```
+    /// let nrc: netrc::Netrc = "macdef get\nget $i\nbye\n".parse().unwrap();
+    /// assert_eq!(
+    ///     nrc.expand_macro("get", &["a.txt", "b.txt"]).unwrap(),
+    ///     ["get a.txt", "bye", "get b.txt", "bye"]
+    /// );
+    /// ```
+    pub fn expand_macro(&self, name: &str, args: &[&str]) -> Option<Vec<String>> {
+        let lines = self.macros.get(name)?;
+        let looping = lines.iter().any(|line| uses_loop_argument(line));
+        if !looping {
+            return Some(lines.iter().map(|l| expand_line(l, args, None)).collect());
+        }
+        Some(
+            args.iter()
+                .flat_map(|arg| lines.iter().map(move |l| expand_line(l, args, Some(arg))))
+                .collect(),
+        )
+    }
+
+    /// Merge the machines and macros of `other` into this netrc.
+    ///
+    /// With [`MergeStrategy::Error`], nothing is merged if a conflict is found.
+    #[cfg(feature = "std")]
+    pub fn merge(&mut self, other: Netrc, strategy: MergeStrategy) -> crate::Result<()> {
+        if strategy == MergeStrategy::Error {
+            let host = other
+                .hosts
+                .iter()
+                .find(|(host, auth)| self.hosts.get(*host).is_some_and(|a| a != *auth))
+                .map(|(host, _)| host);
+            let default = match (&self.default_entry, &other.default_entry) {
+                (Some(a), Some(b)) if a != b => Some(String::from("default")),
+                _ => None,
+            };
+            let macro_ = other
+                .macros
+                .iter()
+                .find(|(name, lines)| self.macros.get(*name).is_some_and(|l| l != *lines))
+                .map(|(name, _)| name);
+            if let Some(name) = host.cloned().or(default).or(macro_.cloned()) {
+                return Err(crate::Error::Conflict(name));
+            }
+        }
+
+        self.merge_entries(other, strategy == MergeStrategy::PreferSelf);
+        Ok(())
+    }
+
+    /// Merge the entries of `other`, the location of a merged entry is the one
+    /// in `other`.
+    #[cfg(feature = "std")]
+    fn merge_entries(&mut self, mut other: Netrc, prefer_self: bool) {
+        for (host, auth) in other.hosts {
+            if !(prefer_self && self.hosts.contains_key(&host)) {
+                match other.spans.remove(&host) {
+                    Some(span) => self.spans.insert(host.clone(), span),
+                    None => self.spans.remove(&host),
+                };
+                self.hosts.insert(host, auth);
+            }
+        }
+        if other.default_entry.is_some() && !(prefer_self && self.default_entry.is_some()) {
+            self.default_entry = other.default_entry;
+            self.default_span = other.default_span;
+        }
+        for (name, lines) in other.macros {
+            if !(prefer_self && self.macros.contains_key(&name)) {
+                match other.macro_spans.remove(&name) {
+                    Some(span) => self.macro_spans.insert(name.clone(), span),
+                    None => self.macro_spans.remove(&name),
+                };
+                self.macros.insert(name, lines);
+            }
+        }
+    }
+}
+
+/// Parse the netrc file included with `path`.
+#[cfg(feature = "std")]
+fn include(
+    path: &str,
+    lineno: u32,
+    options: &ParseOptions,
+    file: Option<&Path>,
+    includes: &mut Vec<PathBuf>,
+    warnings: &mut Vec<Warning>,
+) -> Result<Netrc, ParsingError> {
+    let fail = |message| Err(ParsingError::new(lineno, message));
+    if path.is_empty() {
+        return fail(String::from("missing 'include' path"));
+    }
+    let path = shellexpand::path::tilde(Path::new(path)).into_owned();
+    let path = match file.and_then(|f| f.parent()) {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path,
+    };
+    let err = |e: std::io::Error| {
+        ParsingError::new(
+            lineno,
+            format!("cannot include '{}': {}", path.display(), e),
+        )
+    };
+
+    let canonical = fs::canonicalize(&path).map_err(err)?;
+    if includes.contains(&canonical) {
+        return fail(format!("include cycle with '{}'", path.display()));
+    }
+    if includes.len() >= options.max_include_depth {
+        return fail(format!(
+            "too many nested includes (maximum {})",
+            options.max_include_depth
+        ));
+    }
+    let content = read_limited(&canonical, &options.limits).map_err(err)?;
+
+    includes.push(canonical);
+    let count = warnings.len();
+    let res = options
+        .limits
+        .check_size(content.len())
+        .and_then(|_| options.encoding.decode(&content))
+        .and_then(|source| Netrc::parse_source(&source, options, warnings, Some(&path), includes))
+        .map_err(|e| {
+            ParsingError::with_kind(
+                lineno,
+                e.kind,
+                format!("{} in the included file '{}'", e, path.display()),
+            )
+        });
+    includes.pop();
+    for warning in &mut warnings[count..] {
+        warning.lineno = lineno;
+        warning.message = format!(
+            "{} in the included file '{}'",
+            warning.message,
+            path.display()
+        );
+    }
+    res
+}
+
+/// Read a file, without reading more than one byte past the maximum size of
+/// the limits.
+#[cfg(feature = "std")]
+pub(crate) fn read_limited(file: &Path, limits: &Limits) -> std::io::Result<Vec<u8>> {
+    match limits.max_size {
+        Some(_) => read_limited_from(fs::File::open(file)?, limits),
+        None => fs::read(file),
+    }
+}
+
+/// Read `reader` to the end, without reading more than one byte past the
+/// maximum size of the limits.
+#[cfg(feature = "std")]
+pub(crate) fn read_limited_from<R: std::io::Read>(
+    mut reader: R,
+    limits: &Limits,
+) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut content = Vec::new();
+    match limits.max_size {
+        Some(max) => reader.take(max as u64 + 1).read_to_end(&mut content)?,
+        None => reader.read_to_end(&mut content)?,
+    };
+    Ok(content)
+}
+
+/// Skip the comment started by the token `tt`, if any, `lineno` being the
+/// line of the lexer before reading the token.
+pub(crate) fn skip_comment(
+    lexer: &mut Lex,
+    tt: &str,
+    lineno: u32,
+    toplevel: bool,
+    compat: CompatMode,
+) -> bool {
+    if !tt.starts_with('#') {
+        return false;
+    }
+    match compat {
+        CompatMode::Python => {
+            if lexer.lineno == lineno && (tt.len() == 1 || !toplevel) {
+                lexer.read_line();
+            }
+            true
+        }
+        CompatMode::Curl if !lexer.quoted => {
+            // The token does not end the line.
+            if lexer.lineno == lexer.span.lineno {
+                lexer.read_line();
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Name of an entry in the warnings, `None` for the `default` entry.
+fn entry_name(name: &str, is_default: bool) -> Option<&str> {
+    match is_default {
+        true => None,
+        false => Some(name),
+    }
+}
+
+/// Description of an entry in the warnings.
+fn describe_entry(name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("machine '{}'", name),
+        None => String::from("the 'default' entry"),
+    }
+}
+
+/// Warning about an entry defined again at `lineno`, after the entry at
+/// `previous` if it is in the same file.
+fn duplicate_warning(
+    name: Option<&str>,
+    previous: Option<Span>,
+    lineno: u32,
+    ignored: bool,
+) -> Warning {
+    let at = previous.map_or(String::new(), |span| format!(" at line {}", span.lineno));
+    let outcome = match ignored {
+        true => "this entry is ignored",
+        false => "shadowed by this entry",
+    };
+    Warning::new(
+        lineno,
+        WarningKind::DuplicateEntry,
+        format!(
+            "{} already defined{}, {}",
+            describe_entry(name),
+            at,
+            outcome
+        ),
+    )
+}
+
+/// Whether a name or a value starts or ends with whitespace, or has
+/// whitespace other than spaces, like a tab or a zero width space.
+fn has_suspicious_whitespace(value: &str) -> bool {
+    value.trim() != value
+        || value.chars().any(|c| {
+            c != ' '
+                && (c.is_whitespace()
+                    || c.is_control()
+                    || matches!(c, '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}'))
+        })
+}
+
+/// Warning about the `value` of the `field` of an entry read at `lineno`.
+fn check_value(field: &str, value: &str, lineno: u32, name: Option<&str>) -> Option<Warning> {
+    if field == "password" && value.is_empty() {
+        return Some(Warning::new(
+            lineno,
+            WarningKind::EmptyPassword,
+            format!("empty password for {}", describe_entry(name)),
+        ));
+    }
+    if has_suspicious_whitespace(value) {
+        return Some(Warning::new(
+            lineno,
+            WarningKind::SuspiciousWhitespace,
+            format!(
+                "suspicious whitespace in the {} of {}",
+                field,
+                describe_entry(name)
+            ),
+        ));
+    }
+    None
+}
+
+/// Whether the token `tt` ends the current entry and starts another one.
+pub(crate) fn is_entry_start(tt: &str, options: &ParseOptions) -> bool {
+    matches!(tt, "" | "machine" | "default" | "macdef") || (tt == "include" && options.includes)
+}
+
+/// Skip the value of the unknown follower token `tt` in lenient mode, unless
+/// the value starts another entry.
+pub(crate) fn skip_value(lexer: &mut Lex, tt: &str, options: &ParseOptions) -> Warning {
+    let lineno = lexer.span.lineno;
+    let value = lexer.get_token();
+    if is_entry_start(&value, options) {
+        lexer.push_token(value);
+    }
+    Warning::new(
+        lineno,
+        WarningKind::UnknownToken,
+        format!("unknown token '{}' skipped", tt),
+    )
+}
+
+/// Field of an entry set by the follower token `tt`, in the dialect of the
+/// options.
+pub(crate) fn follower_field(tt: &str, options: &ParseOptions) -> Option<&'static str> {
+    match (tt, options.compat) {
+        ("login", _) | ("user", CompatMode::Python) => Some("login"),
+        ("account", _) => Some("account"),
+        ("password", _) | ("passwd", CompatMode::Bsd) => Some("password"),
+        ("port", _) if options.format == Format::Authinfo => Some("port"),
+        ("protocol", _) if options.format == Format::Authinfo => Some("protocol"),
+        _ => None,
+    }
+}
+
+/// Whether a line of a macro uses the `$i` argument, outside of an escape.
+fn uses_loop_argument(line: &str) -> bool {
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '$' if chars.clone().next() == Some('i') => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Expand a line of a macro, `arg` being the argument of the `$i` loop.
+fn expand_line(line: &str, args: &[&str], arg: Option<&str>) -> String {
+    let mut res = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(_)) => res.extend(chars.next()),
+            ('$', Some(&d @ '1'..='9')) => {
+                chars.next();
+                let n = d as usize - '1' as usize;
+                res.push_str(args.get(n).copied().unwrap_or_default());
+            }
+            ('$', Some('i')) => {
+                chars.next();
+                res.push_str(arg.unwrap_or_default());
+            }
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Read the body of a macro, after its name, until a blank line or the end of
+/// the file, and return its lines without the surrounding whitespace, with
+/// the end of the last one.
+///
+/// The rest of the `macdef` line is the first line of the body, like in the
+/// `netrc` module of Python, unless it is blank.
+pub(crate) fn read_macro(lexer: &mut Lex) -> (Vec<String>, Option<usize>) {
+    let mut lines = Vec::new();
+    let mut end = None;
+    let mut first = lexer.lineno == lexer.span.lineno;
+    while !lexer.is_eof() {
+        let (start, lineno) = (lexer.pos, lexer.lineno);
+        let line = lexer.read_line();
+        if line.trim().is_empty() {
+            if first {
+                first = false;
+                continue;
+            }
+            break;
+        }
+        first = false;
+        if lexer.limits.max_macro_lines == Some(lines.len()) {
+            lexer.fail(
+                lineno,
+                ParsingErrorKind::TooManyMacroLines,
+                format!("more than {} lines in a macro", lines.len()),
+            );
+            break;
+        }
+        end = Some(start + line.trim_end().len());
+        lines.push(line.trim().to_owned());
+    }
+    (lines, end)
+}
+
+impl PartialEq for Netrc {
+    /// Two netrc are equal if they define the same hosts and macros, wherever
+    /// they are located in the source.
+    fn eq(&self, other: &Self) -> bool {
+        self.hosts == other.hosts
+            && self.default_entry == other.default_entry
+            && self.macros == other.macros
+    }
+}
+
+impl Eq for Netrc {}
+
+/// Quote a token if it cannot be written verbatim.
+pub(crate) fn quote(token: &str) -> String {
+    if !token.is_empty()
+        && !token.starts_with('\'')
+        && !token
+            .chars()
+            .any(|c| matches!(c, '\n' | '\t' | '\r' | ' ' | '"' | '\\'))
+    {
+        return token.to_owned();
+    }
+    let mut s = String::from('"');
+    for c in token.chars() {
+        if c == '"' || c == '\\' {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s.push('"');
+    s
+}
+
+impl core::fmt::Display for Netrc {
+    /// Serialize the netrc with the tokens quoted when necessary, so that the
+    /// output parses back to an equal `Netrc` (with the authinfo format if
+    /// some authenticators have a port or a protocol, and with
+    /// [`ParseOptions::extras`] if some have extra tokens).
+    ///
+    /// The machines are sorted by name, with the `default` entry last.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut hosts: Vec<_> = self.hosts.iter().collect();
+        hosts.sort_by_key(|(h, _)| *h);
+        let default = self.default_entry.as_ref().map(|attrs| (None, attrs));
+
+        for (host, attrs) in hosts.into_iter().map(|(h, a)| (Some(h), a)).chain(default) {
+            match host {
+                Some(host) => writeln!(f, "machine {}", quote(host))?,
+                None => writeln!(f, "default")?,
+            }
+            if !attrs.login.is_empty() {
+                writeln!(f, "\tlogin {}", quote(&attrs.login))?;
+            }
+            if !attrs.account.is_empty() {
+                writeln!(f, "\taccount {}", quote(&attrs.account))?;
+            }
+            if !attrs.password.is_empty() {
+                writeln!(f, "\tpassword {}", quote(&attrs.password))?;
+            }
+            if !attrs.port.is_empty() {
+                writeln!(f, "\tport {}", quote(&attrs.port))?;
+            }
+            if !attrs.protocol.is_empty() {
+                writeln!(f, "\tprotocol {}", quote(&attrs.protocol))?;
+            }
+            let mut extras: Vec<_> = attrs.extras.iter().collect();
+            extras.sort();
+            for (key, value) in extras {
+                writeln!(f, "\t{} {}", quote(key), quote(value))?;
+            }
+        }
+
+        let mut macros: Vec<_> = self.macros.iter().collect();
+        macros.sort_by_key(|(name, _)| *name);
+        for (name, lines) in macros {
+            writeln!(f, "macdef {}", quote(name))?;
+            for line in lines.iter() {
+                writeln!(f, "{}", line)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::str::FromStr for Netrc {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, ParsingError> {
+        Netrc::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl Netrc {
+    /// Parse a netrc with the given options.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, ParsingError> {
+        Netrc::parse_with_warnings(s, options).map(|(nrc, _)| nrc)
+    }
+
+    /// Parse a netrc with the given options like [`Netrc::parse_with`], and
+    /// return the warnings of the parser with it: the skipped tokens, the
+    /// entries defined again, the empty passwords, ... (see [`WarningKind`]).
+    ///
+    /// ```
+    /// use netrc::{Netrc, ParseOptions};
+    ///
+    /// let options = ParseOptions {
+    ///     lenient: true,
+    ///     ..Default::default()
+    /// };
+    /// let (nrc, warnings) =
+    ///     Netrc::parse_with_warnings("machine host.com port 22 login log", &options).unwrap();
+    /// assert_eq!(nrc.hosts["host.com"].login, "log");
+    /// assert_eq!(warnings[0].message(), "unknown token 'port' skipped");
+    /// ```
+    pub fn parse_with_warnings(
+        s: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Warning>), ParsingError> {
+        let mut warnings = Vec::new();
+
+        #[cfg(feature = "std")]
+        let nrc = Netrc::parse_source(s, options, &mut warnings, None, &mut Vec::new())?;
+
+        #[cfg(not(feature = "std"))]
+        let nrc = Netrc::parse_source(s, options, &mut warnings)?;
+
+        Ok((nrc, warnings))
+    }
+
+    /// Parse the content of `file`, `includes` being the stack of the files
+    /// currently included.
+    pub(crate) fn parse_source(
+        s: &str,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        #[cfg(feature = "std")] file: Option<&Path>,
+        #[cfg(feature = "std")] includes: &mut Vec<PathBuf>,
+    ) -> Result<Self, ParsingError> {
+        options.limits.check_size(s.len())?;
+        let mut res = Netrc::default();
+        let mut lexer = Lex::new(s, options);
+        let mut after_default = false;
+
+        loop {
+            let saved_lineno = lexer.lineno;
+            let tt = lexer.get_token();
+            lexer.check()?;
+            if tt.is_empty() {
+                break;
+            }
+            let mut span = lexer.span;
+            if skip_comment(&mut lexer, &tt, saved_lineno, true, options.compat) {
+                continue;
+            }
+
+            #[allow(clippy::needless_late_init)]
+            let entryname;
+            match &*tt {
+                "" => {
+                    break;
+                }
+                "machine" => {
+                    if after_default {
+                        let message = String::from("'machine' entry after the 'default' entry");
+                        if options.strict_default {
+                            return Err(ParsingError::new(span.lineno, message));
+                        }
+                        warnings.push(Warning::new(
+                            span.lineno,
+                            WarningKind::MachineAfterDefault,
+                            message,
+                        ));
+                    }
+                    let name = lexer.get_token();
+                    if has_suspicious_whitespace(&name) {
+                        warnings.push(Warning::new(
+                            span.lineno,
+                            WarningKind::SuspiciousWhitespace,
+                            format!(
+                                "suspicious whitespace in the machine name '{}'",
+                                name.escape_debug()
+                            ),
+                        ));
+                    }
+                    entryname = match options.normalize_hosts {
+                        true => normalize_host(&name),
+                        false => name.into_owned(),
+                    };
+                    span.end = lexer.span.end;
+                }
+                "default" => {
+                    entryname = String::from("default");
+                }
+                #[cfg(feature = "std")]
+                "include" if options.includes => {
+                    let lineno = span.lineno;
+                    let path = lexer.get_token();
+                    lexer.check()?;
+                    let mut included = include(&path, lineno, options, file, includes, warnings)?;
+                    // The locations are in another file.
+                    included.spans.clear();
+                    included.macro_spans.clear();
+                    included.default_span = None;
+                    res.merge_entries(included, false);
+                    options.limits.check_entries(&res, lineno)?;
+                    continue;
+                }
+                "macdef" => {
+                    entryname = lexer.get_token().into_owned();
+                    if entryname.is_empty() {
+                        return Err(ParsingError::new(
+                            lexer.lineno,
+                            String::from("missing 'macdef' name"),
+                        ));
+                    }
+                    span.end = lexer.span.end;
+                    let (lines, end) = read_macro(&mut lexer);
+                    span.end = end.unwrap_or(span.end);
+                    lexer.check()?;
+                    if options.macros {
+                        res.macro_spans.insert(entryname.clone(), span);
+                        res.macros.insert(entryname, lines);
+                        options.limits.check_entries(&res, span.lineno)?;
+                    }
+                    continue;
+                }
+                _ if options.lenient || options.compat != CompatMode::Python => {
+                    warnings.push(Warning::new(
+                        span.lineno,
+                        WarningKind::UnknownToken,
+                        format!("unknown token '{}' skipped", tt),
+                    ));
+                    continue;
+                }
+                _ => {
+                    return Err(ParsingError::new(
+                        lexer.lineno,
+                        format!("bad toplevel token '{}'", tt),
+                    ));
+                }
+            };
+            lexer.check()?;
+            if entryname.is_empty() {
+                return Err(ParsingError::new(
+                    lexer.lineno,
+                    format!("missing '{}' name", tt),
+                ));
+            }
+
+            let mut auth = Authenticator::default();
+            let is_default = tt == "default";
+
+            loop {
+                let prev_lineno = lexer.lineno;
+                let tt = lexer.get_token();
+                lexer.check()?;
+                if skip_comment(&mut lexer, &tt, prev_lineno, false, options.compat) {
+                    continue;
+                }
+                if is_entry_start(&tt, options) {
+                    // Python uses the last entry of a machine, curl and ftp
+                    // stop at the first one, or at the default.
+                    let exists = match is_default {
+                        true => res.default_entry.is_some(),
+                        false => res.hosts.contains_key(&entryname),
+                    };
+                    let ignored = match options.compat {
+                        CompatMode::Python => false,
+                        CompatMode::Curl => exists,
+                        CompatMode::Bsd => exists || after_default,
+                    };
+                    if exists {
+                        let previous = match is_default {
+                            true => res.default_span,
+                            false => res.spans.get(&entryname).copied(),
+                        };
+                        warnings.push(duplicate_warning(
+                            entry_name(&entryname, is_default),
+                            previous,
+                            span.lineno,
+                            ignored,
+                        ));
+                    }
+                    match (ignored, is_default) {
+                        (true, _) => {}
+                        (false, true) => {
+                            res.default_span = Some(span);
+                            res.default_entry = Some(auth);
+                        }
+                        (false, false) => {
+                            res.spans.insert(entryname.clone(), span);
+                            res.hosts.insert(entryname, auth);
+                        }
+                    }
+                    options.limits.check_entries(&res, span.lineno)?;
+                    after_default |= is_default;
+                    lexer.push_token(tt);
+                    break;
+                }
+                match follower_field(&tt, options) {
+                    Some(field) => {
+                        let value = lexer.get_token().into_owned();
+                        span.end = lexer.span.end;
+                        let name = entry_name(&entryname, is_default);
+                        warnings.extend(check_value(field, &value, lexer.span.lineno, name));
+                        match field {
+                            "login" => auth.login = value,
+                            "account" => auth.account = value,
+                            "password" => auth.password = value,
+                            "port" => auth.port = value,
+                            _ => auth.protocol = value,
+                        }
+                    }
+                    None if options.extras => {
+                        let value = lexer.get_token().into_owned();
+                        span.end = lexer.span.end;
+                        auth.extras.insert(tt.into_owned(), value);
+                    }
+                    None if options.lenient => {
+                        warnings.push(skip_value(&mut lexer, &tt, options));
+                    }
+                    None if options.compat != CompatMode::Python => {
+                        warnings.push(Warning::new(
+                            lexer.span.lineno,
+                            WarningKind::UnknownToken,
+                            format!("unknown token '{}' skipped", tt),
+                        ));
+                    }
+                    None => {
+                        return Err(ParsingError::new(
+                            lexer.lineno,
+                            format!("bad follower token '{}'", tt),
+                        ));
+                    }
+                };
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_toplevel_non_ordered_tokens() {
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com password pass1 login log1 account acct1
+            default login log2 password pass2 account acct2
+        ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log1", "acct1", "pass1")
+        );
+        assert_eq!(
+            nrc.default_entry,
+            Some(Authenticator::new("log2", "acct2", "pass2"))
+        );
+    }
+
+    #[test]
+    fn test_toplevel_tokens() {
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com login log1 password pass1 account acct1
+            default login log2 password pass2 account acct2
+        ",
+        )
+        .unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log1", "acct1", "pass1")
+        );
+        assert_eq!(
+            nrc.default_entry,
+            Some(Authenticator::new("log2", "acct2", "pass2"))
+        );
+    }
+
+    #[test]
+    fn test_macros() {
+        let nrc = Netrc::from_str(
+            "\
+            macdef macro1
+            line1
+            line2
+
+            macdef macro2
+            line3
+            line4
+            ",
+        )
+        .unwrap();
+        assert_eq!(nrc.macros["macro1"], vec!["line1", "line2"]);
+        assert_eq!(nrc.macros["macro2"], vec!["line3", "line4"]);
+    }
+
+    #[test]
+    fn test_bom() {
+        let data = "\u{feff}machine host.domain.com login log password pass";
+        let nrc = Netrc::from_str(data).unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log", "", "pass")
+        );
+        let span = nrc.spans["host.domain.com"];
+        assert_eq!(&data[span.start..span.end], &data[3..]);
+        assert!(Netrc::from_str("\u{feff}# comment\nmachine host.domain.com").is_ok());
+    }
+
+    #[test]
+    fn test_crlf() {
+        let data = "\
+machine host.domain.com login log password pass\r
+# comment\r
+machine other.com login other # comment\r
+\r
+macdef init\r
+cd /pub\r
+\r
+";
+        let nrc = Netrc::from_str(data).unwrap();
+        assert_eq!(nrc.hosts["host.domain.com"].password, "pass");
+        assert_eq!(nrc.hosts["other.com"].login, "other");
+        assert_eq!(nrc.macros["init"], vec!["cd /pub"]);
+        assert_eq!(nrc.spans["host.domain.com"].end, 47);
+
+        let mut lexer = Lex::new("line\r\nnext", &ParseOptions::default());
+        assert_eq!(lexer.read_line(), "line");
+        assert_eq!(lexer.read_line(), "next");
+    }
+
+    #[test]
+    fn test_borrowed_tokens() {
+        let data = r#"login "a b" pass\ word "a\"b" \"#;
+        let mut lexer = Lex::new(data, &ParseOptions::default());
+        assert!(matches!(lexer.get_token(), Cow::Borrowed("login")));
+        assert!(matches!(lexer.get_token(), Cow::Borrowed("a b")));
+        assert!(matches!(lexer.get_token(), Cow::Owned(s) if s == "pass word"));
+        assert!(matches!(lexer.get_token(), Cow::Owned(s) if s == "a\"b"));
+        assert!(matches!(lexer.get_token(), Cow::Owned(s) if s == " "));
+        assert!(matches!(lexer.get_token(), Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn test_multiline_quotes() {
+        let data = "machine host.domain.com password \"line1\nline2\"\nlogin log invalid";
+        let nrc =
+            Netrc::from_str("machine host.domain.com password \"line1\nline2\" login log").unwrap();
+        assert_eq!(nrc.hosts["host.domain.com"].password, "line1\nline2");
+        assert_eq!(
+            Netrc::from_str(data).unwrap_err().to_string(),
+            "parsing error: bad follower token 'invalid' (line 3)"
+        );
+
+        let options = ParseOptions {
+            multiline_quotes: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            Netrc::parse_with(data, &options).unwrap_err().to_string(),
+            "parsing error: unterminated quoted token (line 1)"
+        );
+        assert_eq!(
+            Netrc::parse_with("machine host.domain.com\nlogin \"log", &options)
+                .unwrap_err()
+                .lineno(),
+            2
+        );
+        assert!(Netrc::parse_with("machine host.domain.com login \"my log\"\n", &options).is_ok());
+    }
+
+    #[test]
+    fn test_single_quotes() {
+        let data = "machine host.domain.com login 'my log' password 'p@ss \\\"word'";
+        let options = ParseOptions {
+            single_quotes: true,
+            ..Default::default()
+        };
+        let nrc = Netrc::parse_with(data, &options).unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("my log", "", "p@ss \\\"word")
+        );
+        assert_eq!(Netrc::parse_with(&nrc.to_string(), &options).unwrap(), nrc);
+
+        let nrc = Netrc::from_str("machine host.domain.com password 'pass'").unwrap();
+        assert_eq!(nrc.hosts["host.domain.com"].password, "'pass'");
+        assert_eq!(Netrc::parse_with(&nrc.to_string(), &options).unwrap(), nrc);
+    }
+
+    #[test]
+    fn test_compat_modes() {
+        let data = "\
+machine host.domain.com login log password \"a\\tb\" #comment login other
+machine host.domain.com login second
+default login anonymous
+machine other.com user me passwd pass
+";
+        let parse = |compat| {
+            let options = ParseOptions {
+                compat,
+                ..Default::default()
+            };
+            Netrc::parse_with(data, &options)
+        };
+
+        assert_eq!(
+            parse(CompatMode::Python).unwrap_err().message(),
+            "bad follower token 'passwd'"
+        );
+
+        let nrc = parse(CompatMode::Curl).unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log", "", "a\tb")
+        );
+        assert_eq!(nrc.hosts["other.com"], Authenticator::default());
+        assert_eq!(nrc.default_entry.unwrap().login, "anonymous");
+
+        let nrc = parse(CompatMode::Bsd).unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("other", "", "atb")
+        );
+        assert_eq!(nrc.default_entry.unwrap().login, "anonymous");
+        assert!(!nrc.hosts.contains_key("other.com"));
+
+        let options = ParseOptions {
+            compat: CompatMode::Curl,
+            ..Default::default()
+        };
+        let nrc = Netrc::parse_with("machine host.com login l\\og password \"#pass\"", &options);
+        assert_eq!(
+            nrc.unwrap().hosts["host.com"],
+            Authenticator::new("l\\og", "", "#pass")
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let data = "\
+version
+machine host.domain.com port 22 login log password pass
+vendor ext
+machine other.com login other protocol
+machine last.com login last
+";
+        assert_eq!(
+            Netrc::from_str(data).unwrap_err().message(),
+            "bad toplevel token 'version'"
+        );
+
+        let options = ParseOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let (nrc, warnings) = Netrc::parse_with_warnings(data, &options).unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log", "", "pass")
+        );
+        assert_eq!(nrc.hosts["other.com"].login, "other");
+        assert_eq!(nrc.hosts["last.com"].login, "last");
+        let warnings: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "warning: unknown token 'version' skipped (line 1)",
+                "warning: unknown token 'port' skipped (line 2)",
+                "warning: unknown token 'vendor' skipped (line 3)",
+                "warning: unknown token 'protocol' skipped (line 4)",
+            ]
+        );
+        assert_eq!(Netrc::parse_with(data, &options).unwrap(), nrc);
+    }
+
+    #[test]
+    fn test_expand_macro() {
+        let nrc = Netrc::from_str(
+            "\
+macdef init
+cd $1
+get $2 \\$HOME/$3
+echo \\\\ $0 $
+
+macdef mget
+get $i
+",
+        )
+        .unwrap();
+        assert_eq!(
+            nrc.expand_macro("init", &["/pub", "file.txt"]).unwrap(),
+            ["cd /pub", "get file.txt $HOME/", "echo \\ $0 $"]
+        );
+        assert_eq!(
+            nrc.expand_macro("mget", &["a.txt", "b.txt"]).unwrap(),
+            ["get a.txt", "get b.txt"]
+        );
+        assert!(nrc.expand_macro("mget", &[]).unwrap().is_empty());
+        assert_eq!(nrc.expand_macro("other", &[]), None);
+    }
+
+    #[test]
+    fn test_macro_termination() {
+        let nrc = Netrc::from_str("macdef init\ncd /pub\nbinary").unwrap();
+        assert_eq!(nrc.macros["init"], vec!["cd /pub", "binary"]);
+        assert_eq!(nrc.macro_spans["init"].end, 26);
+
+        let nrc =
+            Netrc::from_str("macdef init  \r\ncd /pub\r\n\r\nmachine host.domain.com").unwrap();
+        assert_eq!(nrc.macros["init"], vec!["cd /pub"]);
+        assert!(nrc.hosts.contains_key("host.domain.com"));
+
+        let nrc = Netrc::from_str("macdef empty").unwrap();
+        assert_eq!(nrc.macros["empty"], Vec::<String>::new());
+
+        let err = Netrc::from_str("machine host.domain.com\nmacdef").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "parsing error: missing 'macdef' name (line 2)"
+        );
+
+        let options = ParseOptions {
+            macros: false,
+            ..Default::default()
+        };
+        let nrc = Netrc::parse_with(
+            "macdef init\ncd /pub\n\nmachine host.domain.com login log",
+            &options,
+        )
+        .unwrap();
+        assert!(nrc.macros.is_empty());
+        assert_eq!(nrc.hosts["host.domain.com"].login, "log");
+    }
+
+    #[test]
+    fn test_optional_tokens_machine() {
+        let data = vec![
+            "machine host.domain.com",
+            "machine host.domain.com login",
+            "machine host.domain.com account",
+            "machine host.domain.com password",
+            "machine host.domain.com login \"\" account",
+            "machine host.domain.com login \"\" password",
+            "machine host.domain.com account \"\" password",
+        ];
+
+        for item in data {
+            let nrc = Netrc::from_str(item).unwrap();
+            assert_eq!(nrc.hosts["host.domain.com"], Authenticator::new("", "", ""));
+        }
+    }
+
+    #[test]
+    fn test_optional_tokens_default() {
+        let data = vec![
+            "default",
+            "default login",
+            "default account",
+            "default password",
+            "default login \"\" account",
+            "default login \"\" password",
+            "default account \"\" password",
+        ];
+
+        for item in data {
+            let nrc = Netrc::from_str(item).unwrap();
+            assert_eq!(nrc.default_entry, Some(Authenticator::new("", "", "")));
+        }
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        let data = vec![
+            (
+                "invalid host.domain.com",
+                "parsing error: bad toplevel token 'invalid' (line 1)",
+            ),
+            (
+                "machine host.domain.com invalid",
+                "parsing error: bad follower token 'invalid' (line 1)",
+            ),
+            (
+                "machine host.domain.com login log password pass account acct invalid",
+                "parsing error: bad follower token 'invalid' (line 1)",
+            ),
+            (
+                "default host.domain.com invalid",
+                "parsing error: bad follower token 'host.domain.com' (line 1)",
+            ),
+            (
+                "default host.domain.com login log password pass account acct invalid",
+                "parsing error: bad follower token 'host.domain.com' (line 1)",
+            ),
+        ];
+
+        for (item, msg) in data {
+            let nrc = Netrc::from_str(item);
+            assert_eq!(nrc.unwrap_err().to_string(), msg);
+        }
+    }
+
+    fn test_token_x(data: &str, token: &str, value: &str) {
+        let nrc = Netrc::from_str(data).unwrap();
+        match token {
+            "login" => {
+                assert_eq!(
+                    nrc.hosts["host.domain.com"],
+                    Authenticator::new(value, "acct", "pass")
+                );
+            }
+            "account" => {
+                assert_eq!(
+                    nrc.hosts["host.domain.com"],
                     Authenticator::new("log", value, "pass")
                 );
             }
@@ -324,291 +1933,1095 @@ mod tests {
     }
 
     #[test]
-    fn test_token_value_quotes() {
+    fn test_token_value_quotes() {
+        test_token_x(
+            "\
+            machine host.domain.com login \"log\" password pass account acct
+            ",
+            "login",
+            "log",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login log password pass account \"acct\"
+            ",
+            "account",
+            "acct",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login log password \"pass\" account acct
+            ",
+            "password",
+            "pass",
+        );
+    }
+
+    #[test]
+    fn test_token_value_escape() {
+        test_token_x(
+            r#"machine host.domain.com login \"log password pass account acct"#,
+            "login",
+            "\"log",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login \"\\\"log\" password pass account acct
+            ",
+            "login",
+            "\"log",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login log password pass account \\\"acct
+            ",
+            "account",
+            "\"acct",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login log password pass account \"\\\"acct\"
+            ",
+            "account",
+            "\"acct",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login log password \\\"pass account acct
+            ",
+            "password",
+            "\"pass",
+        );
+        test_token_x(
+            "\
+            machine host.domain.com login log password \"\\\"pass\" account acct
+            ",
+            "password",
+            "\"pass",
+        );
+    }
+
+    #[test]
+    fn test_token_value_whitespace() {
+        test_token_x(
+            r#"machine host.domain.com login "lo g" password pass account acct"#,
+            "login",
+            "lo g",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password "pas s" account acct"#,
+            "password",
+            "pas s",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password pass account "acc t""#,
+            "account",
+            "acc t",
+        );
+    }
+
+    #[test]
+    fn test_token_value_non_ascii() {
+        test_token_x(
+            r#"machine host.domain.com login ¡¢ password pass account acct"#,
+            "login",
+            "¡¢",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password pass account ¡¢"#,
+            "account",
+            "¡¢",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password ¡¢ account acct"#,
+            "password",
+            "¡¢",
+        );
+        test_token_x(
+            r#"machine host.domain.com login \¡\¢ password pass account acct"#,
+            "login",
+            "¡¢",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password "¡\¢" account acct"#,
+            "password",
+            "¡¢",
+        );
+    }
+
+    #[test]
+    fn test_many_machines() {
+        let data: String = (0..5000)
+            .map(|i| format!("machine host{i}.com\r\n  login user{i} password \"p\\\"{i}\"\r\n"))
+            .collect();
+        let nrc = Netrc::from_str(&data).unwrap();
+        assert_eq!(nrc.hosts.len(), 5000);
+        assert_eq!(nrc.hosts["host4999.com"].login, "user4999");
+        assert_eq!(nrc.hosts["host4999.com"].password, "p\"4999");
+        assert_eq!(nrc.spans["host4999.com"].lineno, 9999);
+        assert_eq!(nrc.spans["host4999.com"].end, data.len() - 2);
+    }
+
+    #[test]
+    fn test_token_value_leading_hash() {
+        test_token_x(
+            r#"machine host.domain.com login #log password pass account acct"#,
+            "login",
+            "#log",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password pass account #acct"#,
+            "account",
+            "#acct",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password #pass account acct"#,
+            "password",
+            "#pass",
+        );
+    }
+
+    #[test]
+    fn test_token_value_trailing_hash() {
+        test_token_x(
+            r#"machine host.domain.com login log# password pass account acct"#,
+            "login",
+            "log#",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password pass account acct#"#,
+            "account",
+            "acct#",
+        );
+        test_token_x(
+            r#"machine host.domain.com login log password pass# account acct"#,
+            "password",
+            "pass#",
+        );
+    }
+
+    #[test]
+    fn test_token_value_internal_hash() {
         test_token_x(
-            "\
-            machine host.domain.com login \"log\" password pass account acct
-            ",
+            r#"machine host.domain.com login lo#g password pass account acct"#,
             "login",
-            "log",
+            "lo#g",
         );
         test_token_x(
-            "\
-            machine host.domain.com login log password pass account \"acct\"
-            ",
+            r#"machine host.domain.com login log password pass account ac#ct"#,
             "account",
-            "acct",
+            "ac#ct",
         );
         test_token_x(
-            "\
-            machine host.domain.com login log password \"pass\" account acct
-            ",
+            r#"machine host.domain.com login log password pa#ss account acct"#,
             "password",
-            "pass",
+            "pa#ss",
+        );
+    }
+
+    fn test_comment(data: &str) {
+        let nrc = Netrc::from_str(data).unwrap();
+        assert_eq!(
+            nrc.hosts["foo.domain.com"],
+            Authenticator::new("bar", "", "pass")
+        );
+        assert_eq!(
+            nrc.hosts["bar.domain.com"],
+            Authenticator::new("foo", "", "pass")
         );
     }
 
     #[test]
-    fn test_token_value_escape() {
-        test_token_x(
-            r#"machine host.domain.com login \"log password pass account acct"#,
-            "login",
-            "\"log",
+    fn test_comment_before_machine_line() {
+        test_comment(
+            r#"# comment
+            machine foo.domain.com login bar password pass
+            machine bar.domain.com login foo password pass
+            "#,
         );
-        test_token_x(
-            "\
-            machine host.domain.com login \"\\\"log\" password pass account acct
-            ",
-            "login",
-            "\"log",
+    }
+    #[test]
+    fn test_comment_before_machine_line_no_space() {
+        test_comment(
+            r#"#comment
+            machine foo.domain.com login bar password pass
+            machine bar.domain.com login foo password pass
+            "#,
         );
-        test_token_x(
+    }
+
+    #[test]
+    fn test_comment_before_machine_line_hash_only() {
+        test_comment(
+            r#"#
+            machine foo.domain.com login bar password pass
+            machine bar.domain.com login foo password pass
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_comment_after_machine_line() {
+        test_comment(
+            r#"machine foo.domain.com login bar password pass
+            # comment
+            machine bar.domain.com login foo password pass
+            "#,
+        );
+        test_comment(
+            r#"machine foo.domain.com login bar password pass
+            machine bar.domain.com login foo password pass
+            # comment
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_comment_after_machine_line_no_space() {
+        test_comment(
+            r#"machine foo.domain.com login bar password pass
+            #comment
+            machine bar.domain.com login foo password pass
+            "#,
+        );
+        test_comment(
+            r#"machine foo.domain.com login bar password pass
+            machine bar.domain.com login foo password pass
+            #comment
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_comment_after_machine_line_hash_only() {
+        test_comment(
+            r#"machine foo.domain.com login bar password pass
+            #
+            machine bar.domain.com login foo password pass
+            "#,
+        );
+        test_comment(
+            r#"machine foo.domain.com login bar password pass
+            machine bar.domain.com login foo password pass
+            #
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_comment_at_end_of_machine_line() {
+        test_comment(
+            r#"machine foo.domain.com login bar password pass # comment
+            machine bar.domain.com login foo password pass
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_comment_at_end_of_machine_line_no_space() {
+        test_comment(
+            r#"machine foo.domain.com login bar password pass #comment
+            machine bar.domain.com login foo password pass
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_comment_at_end_of_machine_line_pass_has_hash() {
+        let nrc = Netrc::from_str(
+            r#"machine foo.domain.com login bar password #pass #comment
+            machine bar.domain.com login foo password pass
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            nrc.hosts["foo.domain.com"],
+            Authenticator::new("bar", "", "#pass")
+        );
+        assert_eq!(
+            nrc.hosts["bar.domain.com"],
+            Authenticator::new("foo", "", "pass")
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let data = "\
+# comment
+machine foo.domain.com login bar password pass
+
+macdef init
+cd /pub
+
+default login \"anonymous\" password me@
+";
+        let nrc = Netrc::from_str(data).unwrap();
+
+        let span = nrc.spans["foo.domain.com"];
+        assert_eq!(span.lineno, 2);
+        assert_eq!(
+            &data[span.start..span.end],
+            "machine foo.domain.com login bar password pass"
+        );
+
+        let span = nrc.macro_spans["init"];
+        assert_eq!(span.lineno, 4);
+        assert_eq!(&data[span.start..span.end], "macdef init\ncd /pub");
+
+        let span = nrc.default_span.unwrap();
+        assert_eq!(span.lineno, 7);
+        assert_eq!(
+            &data[span.start..span.end],
+            "default login \"anonymous\" password me@"
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut nrc = Netrc::default();
+        nrc.hosts.insert(
+            "host.domain.com".to_owned(),
+            Authenticator::new("lo g", "", "p\"a\\s s"),
+        );
+        nrc.hosts.insert(
+            "other.domain.com".to_owned(),
+            Authenticator::new("#log", "acct", "pass#"),
+        );
+        nrc.default_entry = Some(Authenticator::new("", "", "pass"));
+        nrc.macros.insert(
+            "init".to_owned(),
+            vec!["cd /pub".to_owned(), "bin".to_owned()],
+        );
+        nrc.macros.insert("empty".to_owned(), vec![]);
+
+        let output = nrc.to_string();
+        assert_eq!(
+            output,
             "\
-            machine host.domain.com login log password pass account \\\"acct
-            ",
-            "account",
-            "\"acct",
+machine host.domain.com
+\tlogin \"lo g\"
+\tpassword \"p\\\"a\\\\s s\"
+machine other.domain.com
+\tlogin #log
+\taccount acct
+\tpassword pass#
+default
+\tpassword pass
+macdef empty
+
+macdef init
+cd /pub
+bin
+
+"
         );
-        test_token_x(
+        assert_eq!(Netrc::from_str(&output).unwrap(), nrc);
+    }
+
+    #[test]
+    fn test_merge() {
+        let system = || {
+            Netrc::from_str(
+                "\
+                machine host.domain.com login log1 password pass1
+                default login anonymous password me@
+                macdef init
+                cd /pub
+                ",
+            )
+            .unwrap()
+        };
+        let user = || {
+            Netrc::from_str(
+                "\
+                machine host.domain.com login log2 password pass2
+                machine other.domain.com login log3 password pass3
+                ",
+            )
+            .unwrap()
+        };
+
+        let mut nrc = system();
+        nrc.merge(user(), MergeStrategy::PreferSelf).unwrap();
+        assert_eq!(nrc.hosts.len(), 2);
+        assert_eq!(nrc.default_entry, system().default_entry);
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log1", "", "pass1")
+        );
+        assert_eq!(
+            nrc.hosts["other.domain.com"],
+            Authenticator::new("log3", "", "pass3")
+        );
+        assert_eq!(nrc.macros["init"], vec!["cd /pub"]);
+
+        let mut nrc = system();
+        nrc.merge(user(), MergeStrategy::PreferOther).unwrap();
+        assert_eq!(
+            nrc.hosts["host.domain.com"],
+            Authenticator::new("log2", "", "pass2")
+        );
+        assert_eq!(
+            nrc.spans["host.domain.com"],
+            user().spans["host.domain.com"]
+        );
+
+        let mut nrc = system();
+        assert_eq!(
+            nrc.merge(user(), MergeStrategy::Error)
+                .unwrap_err()
+                .to_string(),
+            "conflicting definitions of 'host.domain.com'"
+        );
+        assert_eq!(nrc, system());
+
+        let mut nrc = system();
+        nrc.merge(system(), MergeStrategy::Error).unwrap();
+        assert_eq!(nrc, system());
+    }
+
+    #[test]
+    fn test_extras() {
+        let data = "machine host.domain.com login log port 8443 protocol \"h t\" password pass";
+        assert_eq!(
+            Netrc::from_str(data).unwrap_err().to_string(),
+            "parsing error: bad follower token 'port' (line 1)"
+        );
+
+        let options = ParseOptions {
+            extras: true,
+            ..Default::default()
+        };
+        let nrc = Netrc::parse_with(data, &options).unwrap();
+        let mut auth = Authenticator::new("log", "", "pass");
+        auth.extras.insert("port".to_owned(), "8443".to_owned());
+        auth.extras.insert("protocol".to_owned(), "h t".to_owned());
+        assert_eq!(nrc.hosts["host.domain.com"], auth);
+        assert_eq!(Netrc::parse_with(&nrc.to_string(), &options).unwrap(), nrc);
+    }
+
+    #[test]
+    fn test_authinfo() {
+        let data = "\
+            machine imap.domain.com login log port imaps password pass
+            machine irc.domain.com login log protocol irc password \"p s\"
+            default login anonymous
+        ";
+        assert_eq!(
+            Netrc::from_str(data).unwrap_err().to_string(),
+            "parsing error: bad follower token 'port' (line 1)"
+        );
+
+        let options = ParseOptions {
+            format: Format::Authinfo,
+            ..Default::default()
+        };
+        let nrc = Netrc::parse_with(data, &options).unwrap();
+        let auth = &nrc.hosts["imap.domain.com"];
+        assert_eq!(
+            (auth.login.as_str(), auth.password.as_str()),
+            ("log", "pass")
+        );
+        assert_eq!(auth.port, "imaps");
+        let auth = &nrc.hosts["irc.domain.com"];
+        assert_eq!(auth.protocol, "irc");
+        assert_eq!(auth.password, "p s");
+        assert_eq!(
+            nrc.default_entry,
+            Some(Authenticator::new("anonymous", "", ""))
+        );
+        assert_eq!(Netrc::parse_with(&nrc.to_string(), &options).unwrap(), nrc);
+    }
+
+    #[test]
+    fn test_lookup() {
+        let nrc = Netrc::from_str(
             "\
-            machine host.domain.com login log password pass account \"\\\"acct\"
+            machine host.domain.com login log1 password pass1
+            machine host.domain.com:8443 login log2 password pass2
+            default login anonymous password me@
             ",
-            "account",
-            "\"acct",
-        );
-        test_token_x(
+        )
+        .unwrap();
+
+        let login = |host, port| nrc.lookup(host, port).map(|a| a.login.as_str());
+        assert_eq!(login("host.domain.com", None), Some("log1"));
+        assert_eq!(login("host.domain.com", Some(443)), Some("log1"));
+        assert_eq!(login("host.domain.com", Some(8443)), Some("log2"));
+        assert_eq!(login("other.domain.com", Some(8443)), Some("anonymous"));
+
+        let options = LookupOptions {
+            default_entry: false,
+            ..Default::default()
+        };
+        assert!(nrc
+            .lookup_with("other.domain.com", None, &options)
+            .is_none());
+
+        let nrc = Netrc::parse_with(
+            "machine imap.domain.com login log port 993",
+            &ParseOptions {
+                format: Format::Authinfo,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(nrc.lookup("imap.domain.com", Some(993)).is_some());
+        assert!(nrc.lookup("imap.domain.com", None).is_some());
+        assert!(nrc.lookup("imap.domain.com", Some(143)).is_none());
+    }
+
+    #[test]
+    fn test_lookup_default() {
+        let nrc = Netrc::parse_with(
             "\
-            machine host.domain.com login log password \\\"pass account acct
+            machine imap.domain.com login log port 993
+            machine smtp.domain.com:465 login log
+            machine [::1]:8080 login log
+            default login anonymous
             ",
-            "password",
-            "\"pass",
+            &ParseOptions {
+                format: Format::Authinfo,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // The default entry is only used for the machines without entry.
+        let login = |host, port| nrc.lookup(host, port).map(|a| a.login.as_str());
+        assert_eq!(login("imap.domain.com", Some(143)), None);
+        assert_eq!(login("smtp.domain.com", Some(25)), None);
+        assert_eq!(login("smtp.domain.com", None), None);
+        assert_eq!(login("[::1]", Some(80)), None);
+        assert_eq!(login("::2", Some(80)), Some("anonymous"));
+        assert_eq!(login("pop.domain.com", Some(995)), Some("anonymous"));
+    }
+
+    #[test]
+    fn test_limits() {
+        let data = "\
+machine host.domain.com login log password pass
+machine other.domain.com login log # comment
+macdef init
+cd /pub
+bin
+
+";
+        let parse = |limits| {
+            let options = ParseOptions {
+                limits,
+                ..Default::default()
+            };
+            Netrc::parse_with(data, &options).map_err(|e| (e.kind(), e.lineno()))
+        };
+
+        assert!(parse(Limits {
+            max_size: Some(data.len()),
+            max_token_len: Some(16),
+            max_entries: Some(3),
+            max_macro_lines: Some(2),
+        })
+        .is_ok());
+        assert_eq!(
+            parse(Limits {
+                max_size: Some(data.len() - 1),
+                ..Default::default()
+            }),
+            Err((ParsingErrorKind::TooLarge, 1))
+        );
+        assert_eq!(
+            parse(Limits {
+                max_token_len: Some(15),
+                ..Default::default()
+            }),
+            Err((ParsingErrorKind::TokenTooLong, 2))
+        );
+        assert_eq!(
+            parse(Limits {
+                max_token_len: Some(8),
+                ..Default::default()
+            })
+            .unwrap_err()
+            .0,
+            ParsingErrorKind::TokenTooLong
         );
-        test_token_x(
-            "\
-            machine host.domain.com login log password \"\\\"pass\" account acct
-            ",
-            "password",
-            "\"pass",
+        assert_eq!(
+            parse(Limits {
+                max_entries: Some(2),
+                ..Default::default()
+            }),
+            Err((ParsingErrorKind::TooManyEntries, 3))
+        );
+        assert_eq!(
+            parse(Limits {
+                max_macro_lines: Some(1),
+                ..Default::default()
+            }),
+            Err((ParsingErrorKind::TooManyMacroLines, 5))
+        );
+        assert_eq!(
+            Netrc::from_str("machine").unwrap_err().kind(),
+            ParsingErrorKind::Syntax
         );
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn test_token_value_whitespace() {
-        test_token_x(
-            r#"machine host.domain.com login "lo g" password pass account acct"#,
-            "login",
-            "lo g",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password "pas s" account acct"#,
-            "password",
-            "pas s",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password pass account "acc t""#,
-            "account",
-            "acc t",
-        );
+    fn test_read_limited_from() {
+        let read = |max_size| {
+            let limits = Limits {
+                max_size,
+                ..Default::default()
+            };
+            read_limited_from(&b"machine host"[..], &limits).unwrap()
+        };
+        assert_eq!(read(None), b"machine host");
+        assert_eq!(read(Some(12)), b"machine host");
+        assert_eq!(read(Some(4)), b"machi");
     }
 
     #[test]
-    fn test_token_value_non_ascii() {
-        test_token_x(
-            r#"machine host.domain.com login ¡¢ password pass account acct"#,
-            "login",
-            "¡¢",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password pass account ¡¢"#,
-            "account",
-            "¡¢",
+    fn test_default_machine() {
+        let data = "\
+            machine default login real
+            default login anonymous
+            ";
+        let nrc = Netrc::from_str(data).unwrap();
+        assert_eq!(nrc.hosts["default"], Authenticator::new("real", "", ""));
+        assert_eq!(
+            nrc.default_entry,
+            Some(Authenticator::new("anonymous", "", ""))
         );
-        test_token_x(
-            r#"machine host.domain.com login log password ¡¢ account acct"#,
-            "password",
-            "¡¢",
+        assert_eq!(nrc.lookup("default", None).unwrap().login, "real");
+        assert_eq!(nrc.lookup("other", None).unwrap().login, "anonymous");
+        assert_eq!(nrc.authenticators("other"), Some(("anonymous", "", "")));
+        assert_eq!(nrc.hosts_with_default()["default"].login, "anonymous");
+        assert_eq!(Netrc::from_str(&nrc.to_string()).unwrap(), nrc);
+
+        let mut other = Netrc::from_str("default login other").unwrap();
+        assert_eq!(
+            other
+                .merge(nrc, MergeStrategy::Error)
+                .unwrap_err()
+                .to_string(),
+            "conflicting definitions of 'default'"
         );
     }
 
     #[test]
-    fn test_token_value_leading_hash() {
-        test_token_x(
-            r#"machine host.domain.com login #log password pass account acct"#,
-            "login",
-            "#log",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password pass account #acct"#,
-            "account",
-            "#acct",
+    fn test_warnings() {
+        let data = "\
+            machine host.com login log password \"\"
+            machine \"other.com \" login \"log\tin\" password \"pass\u{200b}\"
+            machine host.com login log2 password pass
+            default password pass
+            default login anonymous
+            ";
+        let (nrc, warnings) = Netrc::parse_with_warnings(data, &ParseOptions::default()).unwrap();
+        assert_eq!(nrc.hosts["host.com"].login, "log2");
+        assert_eq!(nrc.default_entry.unwrap().login, "anonymous");
+        let warnings: Vec<_> = warnings
+            .iter()
+            .map(|w| (w.kind(), w.lineno(), w.message()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    WarningKind::EmptyPassword,
+                    1,
+                    "empty password for machine 'host.com'"
+                ),
+                (
+                    WarningKind::SuspiciousWhitespace,
+                    2,
+                    "suspicious whitespace in the machine name 'other.com '"
+                ),
+                (
+                    WarningKind::SuspiciousWhitespace,
+                    2,
+                    "suspicious whitespace in the login of machine 'other.com '"
+                ),
+                (
+                    WarningKind::SuspiciousWhitespace,
+                    2,
+                    "suspicious whitespace in the password of machine 'other.com '"
+                ),
+                (
+                    WarningKind::DuplicateEntry,
+                    3,
+                    "machine 'host.com' already defined at line 1, shadowed by this entry"
+                ),
+                (
+                    WarningKind::DuplicateEntry,
+                    5,
+                    "the 'default' entry already defined at line 4, shadowed by this entry"
+                ),
+            ]
         );
-        test_token_x(
-            r#"machine host.domain.com login log password #pass account acct"#,
-            "password",
-            "#pass",
+
+        let options = ParseOptions {
+            compat: CompatMode::Curl,
+            ..Default::default()
+        };
+        let data = "machine host.com login log1\nmachine host.com login log2";
+        let (nrc, warnings) = Netrc::parse_with_warnings(data, &options).unwrap();
+        assert_eq!(nrc.hosts["host.com"].login, "log1");
+        assert_eq!(
+            warnings[0].to_string(),
+            "warning: machine 'host.com' already defined at line 1, this entry is ignored (line 2)"
         );
     }
 
     #[test]
-    fn test_token_value_trailing_hash() {
-        test_token_x(
-            r#"machine host.domain.com login log# password pass account acct"#,
-            "login",
-            "log#",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password pass account acct#"#,
-            "account",
-            "acct#",
+    fn test_strict_default() {
+        let data = "\
+            machine host.domain.com login log1
+            default login anonymous
+            machine other.domain.com login log2
+            ";
+        let (nrc, warnings) = Netrc::parse_with_warnings(data, &ParseOptions::default()).unwrap();
+        assert_eq!(nrc.hosts["other.domain.com"].login, "log2");
+        assert_eq!(
+            warnings,
+            vec![Warning::new(
+                3,
+                WarningKind::MachineAfterDefault,
+                String::from("'machine' entry after the 'default' entry")
+            )]
         );
-        test_token_x(
-            r#"machine host.domain.com login log password pass# account acct"#,
-            "password",
-            "pass#",
+
+        let options = ParseOptions {
+            strict_default: true,
+            ..Default::default()
+        };
+        let err = Netrc::parse_with(data, &options).unwrap_err();
+        assert_eq!(err.lineno(), 3);
+        assert!(
+            Netrc::parse_with("machine host.domain.com\ndefault\nmacdef init\n", &options).is_ok()
         );
     }
 
     #[test]
-    fn test_token_value_internal_hash() {
-        test_token_x(
-            r#"machine host.domain.com login lo#g password pass account acct"#,
-            "login",
-            "lo#g",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password pass account ac#ct"#,
-            "account",
-            "ac#ct",
-        );
-        test_token_x(
-            r#"machine host.domain.com login log password pa#ss account acct"#,
-            "password",
-            "pa#ss",
-        );
+    fn test_lookup_wildcard() {
+        let nrc = Netrc::from_str(
+            "\
+            machine *.domain.com login log1
+            machine *.corp.domain.com login log2
+            machine *.corp.domain.com:8443 login log3
+            machine host.corp.domain.com login log4
+            default login anonymous
+            ",
+        )
+        .unwrap();
+
+        let login = |host, port| nrc.lookup(host, port).map(|a| a.login.as_str());
+        assert_eq!(login("www.domain.com", None), Some("log1"));
+        assert_eq!(login("a.b.domain.com", None), Some("log1"));
+        assert_eq!(login("www.corp.domain.com", None), Some("log2"));
+        assert_eq!(login("www.corp.domain.com", Some(8443)), Some("log3"));
+        assert_eq!(login("host.corp.domain.com", Some(8443)), Some("log4"));
+        assert_eq!(login("domain.com", None), Some("anonymous"));
+        assert_eq!(login("www.domain.org", None), Some("anonymous"));
     }
 
-    fn test_comment(data: &str) {
-        let nrc = Netrc::from_str(data).unwrap();
-        assert_eq!(
-            nrc.hosts["foo.domain.com"],
-            Authenticator::new("bar", "", "pass")
-        );
+    #[test]
+    fn test_lookup_parent_domains() {
+        let nrc = Netrc::from_str(
+            "\
+            machine domain.com login log1
+            machine *.domain.com login log2
+            machine internal.domain.com login log3
+            default login anonymous
+            ",
+        )
+        .unwrap();
+
+        let login = |host, parent_domains| {
+            let options = LookupOptions {
+                parent_domains,
+                ..Default::default()
+            };
+            nrc.lookup_with(host, None, &options)
+                .map(|a| a.login.as_str())
+        };
+        assert_eq!(login("api.internal.domain.com", true), Some("log3"));
+        assert_eq!(login("api.internal.domain.com", false), Some("log2"));
+        assert_eq!(login("api.domain.org", true), Some("anonymous"));
+
+        let nrc = Netrc::from_str("machine domain.com login log1").unwrap();
+        let options = LookupOptions {
+            parent_domains: true,
+            ..Default::default()
+        };
         assert_eq!(
-            nrc.hosts["bar.domain.com"],
-            Authenticator::new("foo", "", "pass")
+            nrc.lookup_with("api.internal.domain.com", None, &options)
+                .map(|a| a.login.as_str()),
+            Some("log1")
         );
+        assert!(nrc.lookup("api.internal.domain.com", None).is_none());
     }
 
     #[test]
-    fn test_comment_before_machine_line() {
-        test_comment(
-            r#"# comment
-            machine foo.domain.com login bar password pass
-            machine bar.domain.com login foo password pass
-            "#,
-        );
+    fn test_is_secure() {
+        assert!(is_secure("https", "domain.com"));
+        assert!(is_secure("WSS", "domain.com"));
+        assert!(is_secure("http", "LocalHost"));
+        assert!(is_secure("ws", "127.0.0.2"));
+        assert!(is_secure("http", "[::1]"));
+        assert!(is_secure("http", "::1"));
+        assert!(!is_secure("http", "domain.com"));
+        assert!(!is_secure("http", "192.168.1.1"));
+        assert!(!is_secure("ftp", "localhost"));
+        assert!(!is_secure("smtp", "domain.com"));
+        assert!(!is_secure("", "domain.com"));
     }
+
     #[test]
-    fn test_comment_before_machine_line_no_space() {
-        test_comment(
-            r#"#comment
-            machine foo.domain.com login bar password pass
-            machine bar.domain.com login foo password pass
-            "#,
+    fn test_normalize_hosts() {
+        let data = "\
+            machine GitHub.com. login log1
+            machine Host.Domain.com:8443 login log2
+        ";
+        let nrc = Netrc::from_str(data).unwrap();
+        assert_eq!(nrc.hosts["github.com"].login, "log1");
+        assert_eq!(nrc.hosts["host.domain.com:8443"].login, "log2");
+        assert_eq!(nrc.spans["github.com"].lineno, 1);
+
+        let login = |nrc: &Netrc, host: &str, options: &LookupOptions| {
+            nrc.lookup_with(host, None, options)
+                .map(|a| a.login.clone())
+        };
+        let raw = LookupOptions {
+            normalize_hosts: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            login(&nrc, "GITHUB.COM.", &Default::default()),
+            Some("log1".to_owned())
         );
+        assert_eq!(login(&nrc, "GITHUB.COM.", &raw), None);
+
+        let options = ParseOptions {
+            normalize_hosts: false,
+            ..Default::default()
+        };
+        let nrc = Netrc::parse_with(data, &options).unwrap();
+        assert_eq!(nrc.hosts["GitHub.com."].login, "log1");
+        assert_eq!(login(&nrc, "GitHub.com.", &raw), Some("log1".to_owned()));
     }
 
+    #[cfg(feature = "idna")]
     #[test]
-    fn test_comment_before_machine_line_hash_only() {
-        test_comment(
-            r#"#
-            machine foo.domain.com login bar password pass
-            machine bar.domain.com login foo password pass
-            "#,
+    fn test_normalize_hosts_idna() {
+        assert_eq!(normalize_host("Bücher.example."), "xn--bcher-kva.example");
+        assert_eq!(
+            normalize_host("*.bücher.example:8443"),
+            "*.xn--bcher-kva.example:8443"
         );
+
+        let nrc = Netrc::from_str("machine bücher.example login log").unwrap();
+        assert!(nrc.lookup("xn--bcher-kva.example", None).is_some());
+        assert!(nrc.lookup("BÜCHER.example", None).is_some());
     }
 
     #[test]
-    fn test_comment_after_machine_line() {
-        test_comment(
-            r#"machine foo.domain.com login bar password pass
-            # comment
-            machine bar.domain.com login foo password pass
-            "#,
-        );
-        test_comment(
-            r#"machine foo.domain.com login bar password pass
-            machine bar.domain.com login foo password pass
-            # comment
-            "#,
-        );
+    fn test_lookup_ipv6() {
+        let nrc = Netrc::from_str(
+            "\
+            machine ::1 login log1
+            machine [0::1]:8080 login log2
+            machine FE80:0:0:0:0:0:0:1 login log3
+            machine *.0.0.1 login log4
+            ",
+        )
+        .unwrap();
+        assert!(nrc.hosts.contains_key("::1"));
+        assert!(nrc.hosts.contains_key("[::1]:8080"));
+        assert!(nrc.hosts.contains_key("fe80::1"));
+
+        let login = |host, port| nrc.lookup(host, port).map(|a| a.login.as_str());
+        assert_eq!(login("[::1]", None), Some("log1"));
+        assert_eq!(login("0:0::1", Some(443)), Some("log1"));
+        assert_eq!(login("[::1]", Some(8080)), Some("log2"));
+        assert_eq!(login("[fe80::0:1]", None), Some("log3"));
+        assert_eq!(login("127.0.0.1", None), None);
     }
 
+    #[cfg(feature = "url")]
     #[test]
-    fn test_comment_after_machine_line_no_space() {
-        test_comment(
-            r#"machine foo.domain.com login bar password pass
-            #comment
-            machine bar.domain.com login foo password pass
-            "#,
+    fn test_authenticator_for_url() {
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com login log1
+            machine host.domain.com:443 login log2
+            machine ::1 login log3
+            machine *.domain.com login log4
+            default login anonymous
+            ",
+        )
+        .unwrap();
+
+        let login = |url: &str| {
+            nrc.authenticator_for_url(&url::Url::parse(url).unwrap())
+                .map(|a| a.login.clone())
+        };
+        assert_eq!(
+            login("http://Host.Domain.com/path").as_deref(),
+            Some("log1")
         );
-        test_comment(
-            r#"machine foo.domain.com login bar password pass
-            machine bar.domain.com login foo password pass
-            #comment
-            "#,
+        assert_eq!(
+            login("https://host.domain.com/path").as_deref(),
+            Some("log2")
         );
+        assert_eq!(
+            login("http://host.domain.com:443/").as_deref(),
+            Some("log2")
+        );
+        assert_eq!(login("http://[::1]:8080/").as_deref(), Some("log3"));
+        assert_eq!(login("ftp://www.domain.com/").as_deref(), Some("log4"));
+        assert_eq!(login("http://domain.org/").as_deref(), Some("anonymous"));
+        assert_eq!(login("file:///etc/hosts"), None);
     }
 
     #[test]
-    fn test_comment_after_machine_line_hash_only() {
-        test_comment(
-            r#"machine foo.domain.com login bar password pass
-            #
-            machine bar.domain.com login foo password pass
-            "#,
+    fn test_authenticators() {
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com login log1 account acct1 password pass1
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            nrc.authenticators("host.domain.com"),
+            Some(("log1", "acct1", "pass1"))
         );
-        test_comment(
-            r#"machine foo.domain.com login bar password pass
-            machine bar.domain.com login foo password pass
-            #
-            "#,
+        assert_eq!(nrc.authenticators("other.domain.com"), None);
+
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com login log1 account acct1 password pass1
+            default login log2 password pass2
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            nrc.authenticators("other.domain.com"),
+            Some(("log2", "", "pass2"))
         );
     }
 
     #[test]
-    fn test_comment_at_end_of_machine_line() {
-        test_comment(
-            r#"machine foo.domain.com login bar password pass # comment
-            machine bar.domain.com login foo password pass
-            "#,
+    fn test_debug_redacted() {
+        let mut auth = Authenticator::new("log", "", "pass");
+        auth.extras
+            .insert(String::from("token"), String::from("secret"));
+        assert_eq!(
+            format!("{:?}", auth),
+            "Authenticator { login: \"log\", account: \"\", password: \"***\", \
+             port: \"\", protocol: \"\", extras: {\"token\": \"***\"} }"
         );
     }
 
+    #[cfg(feature = "zeroize")]
     #[test]
-    fn test_comment_at_end_of_machine_line_no_space() {
-        test_comment(
-            r#"machine foo.domain.com login bar password pass #comment
-            machine bar.domain.com login foo password pass
-            "#,
-        );
+    fn test_zeroize() {
+        use zeroize::Zeroize;
+
+        let mut nrc = Netrc::from_str(
+            "machine host.domain.com login log password pass\n\
+             default login anonymous\n\
+             macdef init\ncd /pub\n\n",
+        )
+        .unwrap();
+        let auth = nrc.hosts.get_mut("host.domain.com").unwrap();
+        auth.port = String::from("22");
+        auth.protocol = String::from("ssh");
+        auth.extras
+            .insert(String::from("token"), String::from("secret"));
+        auth.zeroize();
+        assert_eq!(*auth, Authenticator::default());
+
+        nrc.zeroize();
+        assert!(nrc.hosts.is_empty());
+        assert_eq!(nrc.default_entry, None);
+        assert!(nrc.macros.is_empty());
+        assert!(nrc.spans.is_empty());
+        assert!(nrc.macro_spans.is_empty());
+        assert_eq!(nrc.default_span, None);
+
+        // The fields can still be moved out of an authenticator.
+        let Authenticator { login, .. } = Authenticator::new("log", "", "pass");
+        let auth = zeroize::Zeroizing::new(Authenticator::new("log", "", "pass"));
+        assert_eq!(login, auth.login);
     }
 
     #[test]
-    fn test_comment_at_end_of_machine_line_pass_has_hash() {
-        let nrc = Netrc::from_str(
-            r#"machine foo.domain.com login bar password #pass #comment
-            machine bar.domain.com login foo password pass
-        "#,
+    fn test_include() {
+        let dir = std::env::temp_dir().join("netrc-include");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(
+            dir.join("main"),
+            "\
+            machine host.domain.com login log1
+            include sub/work
+            machine other.domain.com login log2
+            ",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("sub").join("work"),
+            "machine host.domain.com login log3\nmachine work.domain.com login log4",
         )
         .unwrap();
+        std::fs::write(dir.join("cycle"), "include cycle").unwrap();
+
+        let options = ParseOptions {
+            includes: true,
+            ..Default::default()
+        };
+        let parse = |file: &str, options: &ParseOptions| {
+            let file = dir.join(file);
+            let content = std::fs::read_to_string(&file).unwrap();
+            let mut warnings = Vec::new();
+            Netrc::parse_source(
+                &content,
+                options,
+                &mut warnings,
+                Some(&file),
+                &mut vec![file.clone()],
+            )
+        };
+
+        let nrc = parse("main", &options).unwrap();
+        assert_eq!(nrc.hosts.len(), 3);
+        assert_eq!(nrc.hosts["host.domain.com"].login, "log3");
+        assert_eq!(nrc.hosts["work.domain.com"].login, "log4");
+        assert_eq!(nrc.hosts["other.domain.com"].login, "log2");
+        assert!(!nrc.spans.contains_key("host.domain.com"));
+        assert_eq!(nrc.spans["other.domain.com"].lineno, 3);
+
         assert_eq!(
-            nrc.hosts["foo.domain.com"],
-            Authenticator::new("bar", "", "#pass")
+            parse("main", &ParseOptions::default())
+                .unwrap_err()
+                .to_string(),
+            "parsing error: bad follower token 'include' (line 2)"
         );
         assert_eq!(
-            nrc.hosts["bar.domain.com"],
-            Authenticator::new("foo", "", "pass")
+            parse("cycle", &options).unwrap_err().to_string(),
+            format!(
+                "parsing error: include cycle with '{}' (line 1)",
+                dir.join("cycle").display()
+            )
+        );
+        let options = ParseOptions {
+            includes: true,
+            max_include_depth: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse("main", &options).unwrap_err().to_string(),
+            "parsing error: too many nested includes (maximum 1) (line 2)"
         );
     }
 }
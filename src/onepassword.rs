@@ -0,0 +1,68 @@
+//! Read the passwords referring to 1Password secrets.
+
+use std::process::Command;
+
+/// Prefix of the 1Password secret references, like `op://vault/item/field`.
+pub(crate) const PREFIX: &str = "op://";
+
+/// Value of a secret reference, read with the 1Password CLI, `op`.
+pub(crate) fn read(reference: &str) -> Option<String> {
+    output(read_command(reference)?)
+}
+
+/// `op read` command of `reference`, which must be a secret reference.
+fn read_command(reference: &str) -> Option<Command> {
+    if !reference.starts_with(PREFIX) {
+        return None;
+    }
+    let mut cmd = Command::new("op");
+    cmd.args(["read", "--no-newline", reference]);
+    Some(cmd)
+}
+
+/// Standard output of `cmd`, if it succeeds.
+fn output(mut cmd: Command) -> Option<String> {
+    let output = cmd.output().ok().filter(|o| o.status.success())?;
+    String::from_utf8(output.stdout).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_command() {
+        let cmd = read_command("op://vault/item/password").unwrap();
+        assert_eq!(
+            cmd.get_args().collect::<Vec<_>>(),
+            ["read", "--no-newline", "op://vault/item/password"]
+        );
+        assert!(read_command("--help").is_none());
+        assert!(read_command("pass:web/github.com").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Stub of `op`, put on the `PATH` of the command only.
+        let dir = std::env::temp_dir().join("netrc-onepassword");
+        std::fs::create_dir_all(&dir).unwrap();
+        let op = dir.join("op");
+        std::fs::write(
+            &op,
+            "#!/bin/sh\n[ \"$3\" = op://vault/item/password ] && printf secret\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&op, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let read = |reference| {
+            let mut cmd = read_command(reference).unwrap();
+            cmd.env("PATH", &dir);
+            output(cmd)
+        };
+        assert_eq!(read("op://vault/item/password").as_deref(), Some("secret"));
+        assert_eq!(read("op://vault/item/other"), None);
+    }
+}
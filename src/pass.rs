@@ -0,0 +1,51 @@
+//! Read the passwords of the standard Unix password manager, `pass`.
+
+use std::process::Command;
+
+/// Prefix of the passwords read from the password store, like
+/// `pass:web/github.com`.
+pub(crate) const PREFIX: &str = "pass:";
+
+/// Password of the `name` entry of the password store.
+pub(crate) fn show(name: &str) -> Option<String> {
+    let output = show_command(name)?
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    String::from_utf8(output.stdout).ok().map(first_line)
+}
+
+/// `pass show` command of the `name` entry, which can not be an option. An
+/// empty name, which would list the whole store, is rejected.
+fn show_command(name: &str) -> Option<Command> {
+    if name.is_empty() {
+        return None;
+    }
+    let mut cmd = Command::new("pass");
+    cmd.args(["show", "--", name]);
+    Some(cmd)
+}
+
+/// The password is the first line of an entry, the next ones are metadata.
+fn first_line(content: String) -> String {
+    content.lines().next().unwrap_or_default().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_command() {
+        let cmd = show_command("--help").unwrap();
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["show", "--", "--help"]);
+        assert!(show_command("").is_none());
+    }
+
+    #[test]
+    fn test_first_line() {
+        assert_eq!(first_line(String::from("pass\nlogin: log\n")), "pass");
+        assert_eq!(first_line(String::from("pass\r\n")), "pass");
+        assert_eq!(first_line(String::new()), "");
+    }
+}
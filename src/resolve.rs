@@ -0,0 +1,146 @@
+//! Resolution of the credentials with the backends enabled by the features.
+
+use crate::{normalize_host, Authenticator, LookupOptions, MatchKind, Netrc};
+
+impl Netrc {
+    /// Get the authenticator of a machine like [`Netrc::lookup`], completed
+    /// by the credential backends enabled by the features.
+    ///
+    /// If the machine has an entry, its password is resolved when it refers
+    /// to a backend:
+    ///
+    /// - `keychain`, read from the macOS Keychain (`keychain` feature),
+    /// - `keyring`, read from the keyring of the system (`keychain` or
+    ///   `secret-service` feature, see the `keyring` module),
+    /// - `pass:<name>`, read from the `pass` password store (`pass` feature),
+    /// - `op://<vault>/<item>/<field>`, read from 1Password (`onepassword`
+    ///   feature).
+    ///
+    /// Otherwise, the backends are searched in this order before the
+    /// `default` entry:
+    ///
+    /// - the macOS Keychain (`keychain` feature),
+    /// - the Secret Service (`secret-service` feature).
+    ///
+    /// `None` is returned if the password of the entry can not be resolved.
+    pub fn resolve(&self, host: &str, port: Option<u16>) -> Option<Authenticator> {
+        self.resolve_with(host, port, &LookupOptions::default())
+    }
+
+    /// Get the authenticator of a machine like [`Netrc::resolve`], with the
+    /// given lookup options.
+    pub fn resolve_with(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        options: &LookupOptions,
+    ) -> Option<Authenticator> {
+        self.resolve_match(host, port, options)
+            .map(|(auth, _)| auth)
+    }
+
+    /// Get the authenticator of a machine like [`Netrc::resolve_with`], with
+    /// the kind of its entry.
+    pub(crate) fn resolve_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        options: &LookupOptions,
+    ) -> Option<(Authenticator, MatchKind)> {
+        let server = normalize_host(host);
+        let entry_options = LookupOptions {
+            default_entry: false,
+            ..options.clone()
+        };
+
+        match self.lookup_with(host, port, &entry_options) {
+            Some(auth) => {
+                resolve_password(auth, &server, port).map(|auth| (auth, MatchKind::Exact))
+            }
+            None => match find(&server, port) {
+                Some(auth) => Some((auth, MatchKind::Exact)),
+                None => options
+                    .default_entry
+                    .then(|| self.resolve_default(host, port))
+                    .flatten()
+                    .map(|auth| (auth, MatchKind::Default)),
+            },
+        }
+    }
+
+    /// Get the `default` entry, with its password resolved for `host`.
+    pub(crate) fn resolve_default(&self, host: &str, port: Option<u16>) -> Option<Authenticator> {
+        let server = normalize_host(host);
+        self.fallback(&server)
+            .and_then(|auth| resolve_password(auth, &server, port))
+    }
+}
+
+/// Replace the password of `auth` if it refers to a backend.
+#[allow(unused_variables)]
+fn resolve_password(
+    auth: &Authenticator,
+    server: &str,
+    port: Option<u16>,
+) -> Option<Authenticator> {
+    let with = |password: Option<String>| {
+        password.map(|password| {
+            let mut auth = auth.clone();
+            auth.password = password;
+            auth
+        })
+    };
+    #[cfg(feature = "keychain")]
+    if auth.password == crate::KEYCHAIN_PASSWORD {
+        return with(crate::keychain::find_password(server, &auth.login, port));
+    }
+    #[cfg(any(feature = "keychain", feature = "secret-service"))]
+    if auth.password == crate::KEYRING_PASSWORD {
+        return with(crate::keyring::password(server, port, &auth.login));
+    }
+    #[cfg(feature = "pass")]
+    if let Some(name) = auth.password.strip_prefix(crate::pass::PREFIX) {
+        return with(crate::pass::show(name));
+    }
+    #[cfg(feature = "onepassword")]
+    if auth.password.starts_with(crate::onepassword::PREFIX) {
+        return with(crate::onepassword::read(&auth.password));
+    }
+    Some(auth.clone())
+}
+
+/// Search the authenticator of a machine without entry in the backends.
+#[allow(unused_variables)]
+fn find(server: &str, port: Option<u16>) -> Option<Authenticator> {
+    let found = None;
+    #[cfg(feature = "keychain")]
+    let found = found.or_else(|| crate::keychain::find(server, port));
+    #[cfg(feature = "secret-service")]
+    let found = found.or_else(|| crate::secret_service::find(server, port));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_resolve() {
+        let nrc = Netrc::from_str(
+            "\
+            machine host.domain.com login log password pass
+            default login anonymous password me@
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            nrc.resolve("Host.Domain.com", Some(443)),
+            Some(Authenticator::new("log", "", "pass"))
+        );
+        assert_eq!(
+            nrc.resolve("nonexistent.invalid", None),
+            Some(Authenticator::new("anonymous", "", "me@"))
+        );
+    }
+}
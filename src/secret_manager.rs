@@ -0,0 +1,367 @@
+//! Credentials stored in a cloud secret manager.
+
+use crate::{normalize_host, Authenticator, CredentialSource};
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// Cloud secret manager.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SecretManager {
+    /// AWS Secrets Manager, read with the `aws` command.
+    Aws,
+
+    /// Google Cloud Secret Manager, read with the `gcloud` command.
+    Gcp,
+}
+
+/// Source of the credentials stored in a cloud secret manager.
+///
+/// The secret of a machine is found with a name template, where `{host}` is
+/// replaced by the normalized machine name, and `{host_id}` by the same name
+/// with the characters other than the letters and the digits replaced by `-`
+/// (the Google Cloud secrets names can not contain dots).
+///
+/// A secret is a JSON object with the `username` and `password` fields by
+/// default, its other fields being ignored, or else the password itself. A
+/// malformed JSON object gives no credentials.
+///
+/// The commands are configured with their environment or their
+/// configuration files, like the credentials and the region.
+///
+/// ```no_run
+/// use netrc::{CredentialSource, Netrc, SecretManager, SecretManagerSource};
+///
+/// let source = SecretManagerSource::new(SecretManager::Aws, "netrc/{host}")
+///     .chain(Netrc::new().unwrap());
+/// let auth = source.find("host.domain.com", None, None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecretManagerSource {
+    manager: SecretManager,
+    name: String,
+    login_field: String,
+    password_field: String,
+}
+
+impl SecretManagerSource {
+    /// Create a source of the secrets of `manager` named by the template.
+    pub fn new(manager: SecretManager, name: &str) -> Self {
+        SecretManagerSource {
+            manager,
+            name: name.to_owned(),
+            login_field: String::from("username"),
+            password_field: String::from("password"),
+        }
+    }
+
+    /// Set the fields of the login and the password in the JSON secrets.
+    pub fn fields(mut self, login: &str, password: &str) -> Self {
+        self.login_field = login.to_owned();
+        self.password_field = password.to_owned();
+        self
+    }
+
+    /// Find the authenticator of `host` like [`CredentialSource::find`],
+    /// without blocking the asynchronous tasks: the secret manager is queried
+    /// in a thread.
+    pub fn find_async(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> impl Future<Output = Option<Authenticator>> + Send + 'static {
+        let source = self.clone();
+        let host = host.to_owned();
+        let scheme = scheme.map(str::to_owned);
+        Blocking::spawn(move || source.find(&host, port, scheme.as_deref()))
+    }
+
+    /// Name of the secret of `host`.
+    fn name(&self, host: &str) -> String {
+        let host = normalize_host(host);
+        let host_id: String = host
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c,
+                false => '-',
+            })
+            .collect();
+        self.name
+            .replace("{host_id}", &host_id)
+            .replace("{host}", &host)
+    }
+
+    /// Value of the secret `name`.
+    fn secret(&self, name: &str) -> Option<String> {
+        let mut cmd = match self.manager {
+            SecretManager::Aws => {
+                let mut cmd = Command::new("aws");
+                cmd.args(["secretsmanager", "get-secret-value", "--secret-id", name])
+                    .args(["--query", "SecretString", "--output", "text"]);
+                cmd
+            }
+            SecretManager::Gcp => {
+                let mut cmd = Command::new("gcloud");
+                cmd.args(["secrets", "versions", "access", "latest"])
+                    .arg(format!("--secret={}", name));
+                cmd
+            }
+        };
+        let output = cmd.output().ok().filter(|o| o.status.success())?;
+        let secret = String::from_utf8(output.stdout).ok()?;
+        Some(match self.manager {
+            // The AWS CLI adds a newline to the text output.
+            SecretManager::Aws => secret.strip_suffix('\n').unwrap_or(&secret).to_owned(),
+            SecretManager::Gcp => secret,
+        })
+    }
+
+    /// Authenticator of a secret: a JSON object, or else the password itself.
+    /// A malformed JSON object gives no credentials, rather than sending it
+    /// whole as the password.
+    fn authenticator(&self, secret: &str) -> Option<Authenticator> {
+        if !secret.trim_start().starts_with('{') {
+            return Some(Authenticator::new("", "", secret));
+        }
+        let fields = parse_object(secret)?;
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str())
+                .unwrap_or_default()
+        };
+        Some(Authenticator::new(
+            field(&self.login_field),
+            "",
+            field(&self.password_field),
+        ))
+    }
+}
+
+impl CredentialSource for SecretManagerSource {
+    fn find(&self, host: &str, _port: Option<u16>, _scheme: Option<&str>) -> Option<Authenticator> {
+        self.secret(&self.name(host))
+            .and_then(|secret| self.authenticator(&secret))
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+/// Maximum nesting of the JSON values skipped.
+const MAX_DEPTH: usize = 32;
+
+/// Parse the string fields of a JSON object, like
+/// `{"username": "log", "password": "pass", "port": 5432}`, the other values
+/// being skipped.
+fn parse_object(s: &str) -> Option<Vec<(String, String)>> {
+    let mut chars = s.trim().chars().peekable();
+    if chars.peek() != Some(&'{') {
+        return None;
+    }
+    let mut fields = Vec::new();
+    members(&mut chars, '}', |chars| {
+        let key = member_key(chars)?;
+        match chars.peek() {
+            Some('"') => fields.push((key, parse_string(chars)?)),
+            _ => skip_value(chars, 1)?,
+        }
+        Some(())
+    })?;
+    chars.next().is_none().then_some(fields)
+}
+
+fn skip_ws(chars: &mut Chars) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// Parse the members of an object or an array, from its opening character to
+/// its `close` one.
+fn members(
+    chars: &mut Chars,
+    close: char,
+    mut member: impl FnMut(&mut Chars) -> Option<()>,
+) -> Option<()> {
+    chars.next()?;
+    skip_ws(chars);
+    if chars.next_if_eq(&close).is_some() {
+        return Some(());
+    }
+    loop {
+        skip_ws(chars);
+        member(chars)?;
+        skip_ws(chars);
+        match chars.next()? {
+            ',' => continue,
+            c if c == close => return Some(()),
+            _ => return None,
+        }
+    }
+}
+
+/// Parse the key of an object member and its colon.
+fn member_key(chars: &mut Chars) -> Option<String> {
+    let key = parse_string(chars)?;
+    skip_ws(chars);
+    if chars.next()? != ':' {
+        return None;
+    }
+    skip_ws(chars);
+    Some(key)
+}
+
+/// Skip a JSON value, failing if it is malformed or nested too deeply.
+fn skip_value(chars: &mut Chars, depth: usize) -> Option<()> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    match chars.peek()? {
+        '"' => parse_string(chars).map(drop),
+        '{' => members(chars, '}', |chars| {
+            member_key(chars)?;
+            skip_value(chars, depth + 1)
+        }),
+        '[' => members(chars, ']', |chars| skip_value(chars, depth + 1)),
+        _ => {
+            let mut literal = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || "+-.".contains(*c)) {
+                literal.push(c);
+            }
+            let valid = matches!(literal.as_str(), "true" | "false" | "null")
+                || (literal.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+                    && literal.parse::<f64>().is_ok());
+            valid.then_some(())
+        }
+    }
+}
+
+/// Parse a JSON string, with its escapes and the surrogate pairs of the
+/// characters outside of the Basic Multilingual Plane.
+fn parse_string(chars: &mut Chars) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+    let hex4 = |chars: &mut Chars| -> Option<u32> {
+        let code: String = chars.by_ref().take(4).collect();
+        match code.len() == 4 && code.chars().all(|c| c.is_ascii_hexdigit()) {
+            true => u32::from_str_radix(&code, 16).ok(),
+            false => None,
+        }
+    };
+    let mut res = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(res),
+            '\\' => match chars.next()? {
+                'n' => res.push('\n'),
+                't' => res.push('\t'),
+                'r' => res.push('\r'),
+                'b' => res.push('\u{8}'),
+                'f' => res.push('\u{c}'),
+                'u' => {
+                    let mut code = hex4(chars)?;
+                    if (0xD800..0xDC00).contains(&code) {
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        let low = hex4(chars)?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return None;
+                        }
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    res.push(char::from_u32(code)?);
+                }
+                c @ ('"' | '\\' | '/') => res.push(c),
+                _ => return None,
+            },
+            c => res.push(c),
+        }
+    }
+}
+
+/// Future of the result of a blocking function run in a thread.
+struct Blocking<T> {
+    state: Arc<Mutex<(Option<T>, Option<Waker>)>>,
+}
+
+impl<T: Send + 'static> Blocking<T> {
+    fn spawn<F: FnOnce() -> T + Send + 'static>(f: F) -> Self {
+        let state = Arc::new(Mutex::new((None, None::<Waker>)));
+        let shared = state.clone();
+        std::thread::spawn(move || {
+            let res = f();
+            let mut state = shared.lock().unwrap();
+            state.0 = Some(res);
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+        });
+        Blocking { state }
+    }
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap();
+        match state.0.take() {
+            Some(res) => Poll::Ready(res),
+            None => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        let source = SecretManagerSource::new(SecretManager::Gcp, "netrc-{host_id}");
+        assert_eq!(source.name("Host.Domain.com"), "netrc-host-domain-com");
+        let source = SecretManagerSource::new(SecretManager::Aws, "netrc/{host}");
+        assert_eq!(source.name("Host.Domain.com"), "netrc/host.domain.com");
+    }
+
+    #[test]
+    fn test_authenticator() {
+        let source = SecretManagerSource::new(SecretManager::Aws, "{host}");
+        let auth = |secret: &str| source.authenticator(secret);
+        assert_eq!(
+            auth(r#"{"username": "log", "password": "p\"ass"}"#),
+            Some(Authenticator::new("log", "", "p\"ass"))
+        );
+        assert_eq!(
+            auth(r#"{"password":"pass"}"#),
+            Some(Authenticator::new("", "", "pass"))
+        );
+        assert_eq!(auth("pass"), Some(Authenticator::new("", "", "pass")));
+        assert_eq!(
+            auth(r#"{"username": "log", "password": "pass", "port": 5432, "ssl": true}"#),
+            Some(Authenticator::new("log", "", "pass"))
+        );
+        assert_eq!(
+            auth(r#"{"options": {"a": [1, -2.5e3, null, "x"]}, "password": "pass"}"#),
+            Some(Authenticator::new("", "", "pass"))
+        );
+        assert_eq!(
+            auth(r#"{"password": "\uD83D\uDE00\u00e9"}"#),
+            Some(Authenticator::new("", "", "\u{1F600}\u{e9}"))
+        );
+        assert_eq!(auth(r#"{"password": 1"#), None);
+        assert_eq!(auth(r#"{"password": "\uD83D"}"#), None);
+        assert_eq!(auth(r#"{"password": "pass", "port": 54x}"#), None);
+        assert_eq!(
+            auth(&format!("{{\"a\": {}1{}}}", "[".repeat(64), "]".repeat(64))),
+            None
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Look up the network passwords of the freedesktop Secret Service (GNOME
+//! Keyring, KWallet).
+//!
+//! The Secret Service is queried with the `secret-tool` command of libsecret,
+//! so that nothing is found if it is not installed.
+
+use crate::{Authenticator, Error, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Schema of the items stored, the one of the network passwords.
+const SCHEMA: &str = "org.gnome.keyring.NetworkPassword";
+
+/// Authenticator of the first network password of `server`, restricted to
+/// `port` if the item has one.
+pub(crate) fn find(server: &str, port: Option<u16>) -> Option<Authenticator> {
+    let output = Command::new("secret-tool")
+        .args(["search", "--all", "--unlock", "server", server])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let items = parse_items(&String::from_utf8_lossy(&output.stdout));
+    items
+        .into_iter()
+        .find(|item| match (item.port, port) {
+            (Some(item_port), Some(port)) => item_port == port,
+            _ => true,
+        })
+        .map(|item| Authenticator::new(&item.user, "", &item.secret))
+}
+
+/// Password of the item of `server` and `user`.
+pub(crate) fn find_password(server: &str, user: &str, port: Option<u16>) -> Option<String> {
+    let output = Command::new("secret-tool")
+        .arg("lookup")
+        .args(attributes(server, user, port))
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let password = String::from_utf8(output.stdout).ok()?;
+    Some(password.strip_suffix('\n').unwrap_or(&password).to_owned())
+}
+
+/// Add or update the item of `server` and `user`.
+pub(crate) fn store(server: &str, user: &str, port: Option<u16>, password: &str) -> Result<()> {
+    let mut child = store_command(server, user, port)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // The password is read from the standard input, until its end.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(password.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(Error::Keyring(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        )),
+    }
+}
+
+/// `secret-tool store` command of the item of `server` and `user`, reading
+/// the password from its standard input rather than from its arguments.
+fn store_command(server: &str, user: &str, port: Option<u16>) -> Command {
+    let mut cmd = Command::new("secret-tool");
+    cmd.args(["store", "--label", &format!("{}@{}", user, server)])
+        .args(["xdg:schema", SCHEMA])
+        .args(attributes(server, user, port));
+    cmd
+}
+
+/// Delete the item of `server` and `user`.
+pub(crate) fn delete(server: &str, user: &str, port: Option<u16>) -> Result<()> {
+    let output = Command::new("secret-tool")
+        .arg("clear")
+        .args(attributes(server, user, port))
+        .output()?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(Error::Keyring(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        )),
+    }
+}
+
+/// Attributes identifying the item of `server` and `user`.
+fn attributes(server: &str, user: &str, port: Option<u16>) -> Vec<String> {
+    let mut attributes = vec![
+        String::from("server"),
+        server.to_owned(),
+        String::from("user"),
+        user.to_owned(),
+    ];
+    if let Some(port) = port {
+        attributes.extend([String::from("port"), port.to_string()]);
+    }
+    attributes
+}
+
+/// Item printed by `secret-tool search`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Item {
+    user: String,
+    secret: String,
+    port: Option<u16>,
+}
+
+/// Parse the items printed by `secret-tool search`, each one starting with
+/// its path in brackets followed by `key = value` lines.
+fn parse_items(output: &str) -> Vec<Item> {
+    let mut items: Vec<Item> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with('[') {
+            items.push(Item::default());
+            continue;
+        }
+        let (Some(item), Some((key, value))) = (items.last_mut(), line.split_once(" = ")) else {
+            continue;
+        };
+        match key.trim() {
+            "secret" => item.secret = value.to_owned(),
+            "attribute.user" => item.user = value.to_owned(),
+            "attribute.port" => item.port = value.parse().ok().filter(|p| *p != 0),
+            _ => {}
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            attributes("host.domain.com", "log", Some(8443)),
+            ["server", "host.domain.com", "user", "log", "port", "8443"]
+        );
+        assert_eq!(
+            attributes("host.domain.com", "log", None),
+            ["server", "host.domain.com", "user", "log"]
+        );
+    }
+
+    #[test]
+    fn test_store_command() {
+        let cmd = store_command("host.domain.com", "log", Some(8443));
+        assert_eq!(
+            cmd.get_args().collect::<Vec<_>>(),
+            [
+                "store",
+                "--label",
+                "log@host.domain.com",
+                "xdg:schema",
+                SCHEMA,
+                "server",
+                "host.domain.com",
+                "user",
+                "log",
+                "port",
+                "8443"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let output = "\
+[/org/freedesktop/secrets/collection/login/1]
+label = log@host.domain.com
+secret = pass = word
+created = 2024-01-01 10:00:00
+schema = org.gnome.keyring.NetworkPassword
+attribute.protocol = https
+attribute.server = host.domain.com
+attribute.user = log
+[/org/freedesktop/secrets/collection/login/2]
+secret = pass2
+attribute.port = 8443
+attribute.user = log2
+";
+        assert_eq!(
+            parse_items(output),
+            vec![
+                Item {
+                    user: String::from("log"),
+                    secret: String::from("pass = word"),
+                    port: None,
+                },
+                Item {
+                    user: String::from("log2"),
+                    secret: String::from("pass2"),
+                    port: Some(8443),
+                },
+            ]
+        );
+    }
+}
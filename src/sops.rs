@@ -0,0 +1,59 @@
+//! Decrypt the netrc files encrypted with SOPS.
+//!
+//! A netrc file is encrypted by SOPS as a binary file, which is stored as a
+//! JSON document with the encrypted `data` and the `sops` metadata. The
+//! values encrypted separately with `ENC[...]` markers are not supported, as
+//! SOPS needs the metadata of the whole file to decrypt them.
+
+use crate::{Error, Result};
+use std::path::Path;
+use std::process::Command;
+
+/// Whether `content` is a file encrypted by SOPS.
+pub(crate) fn is_encrypted(content: &[u8]) -> bool {
+    let content = String::from_utf8_lossy(content);
+    content.trim_start().starts_with('{')
+        && content.contains("\"sops\"")
+        && content.contains("\"data\": \"ENC[")
+}
+
+/// Decrypt `file` with the `sops` command.
+pub(crate) fn decrypt(file: &Path) -> Result<Vec<u8>> {
+    let output = Command::new("sops")
+        .args([
+            "--decrypt",
+            "--input-type",
+            "binary",
+            "--output-type",
+            "binary",
+        ])
+        .arg(file)
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Decryption {
+            filename: file.display().to_string(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_encrypted() {
+        let content = r#"{
+	"data": "ENC[AES256_GCM,data:AbCd,iv:EfGh,tag:IjKl,type:str]",
+	"sops": {
+		"age": [],
+		"lastmodified": "2024-01-01T10:00:00Z",
+		"mac": "ENC[AES256_GCM,data:MnOp,iv:QrSt,tag:UvWx,type:str]",
+		"version": "3.8.1"
+	}
+}"#;
+        assert!(is_encrypted(content.as_bytes()));
+        assert!(!is_encrypted(b"machine host login log password pass"));
+    }
+}
@@ -0,0 +1,261 @@
+//! Sources of credentials, to combine the netrc with other backends.
+
+use crate::env::host_var;
+use crate::{Authenticator, Env, LookupOptions, Netrc, SystemEnv};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A source of credentials for the machines.
+///
+/// The sources are combined with [`chain`](CredentialSource::chain), the
+/// first one finding an authenticator taking precedence:
+///
+/// ```
+/// use netrc::{CredentialSource, EnvSource, Netrc};
+///
+/// let nrc: Netrc = "default login anonymous".parse().unwrap();
+/// let source = EnvSource::new().chain(nrc);
+/// let auth = source.find("host.domain.com", None, Some("https")).unwrap();
+/// ```
+pub trait CredentialSource {
+    /// Find the authenticator of `host`, optionally restricted to a port and a
+    /// scheme (like `https`).
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator>;
+
+    /// Find the authenticator of `host` like
+    /// [`find`](CredentialSource::find), with the kind of its entry. The
+    /// authenticators are exact matches unless the source tells otherwise.
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        self.find(host, port, scheme)
+            .map(|auth| (auth, MatchKind::Exact))
+    }
+
+    /// Combine this source with `other`, used if this one finds nothing.
+    fn chain<S: CredentialSource>(self, other: S) -> Chain<Self, S>
+    where
+        Self: Sized,
+    {
+        Chain(self, other)
+    }
+}
+
+/// Kind of the entry of an authenticator found by a [`CredentialSource`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MatchKind {
+    /// An entry of the host, like a machine of the netrc or a wildcard entry
+    /// matching it, or the credentials of another backend.
+    Exact,
+
+    /// The `default` entry of the netrc, used for the machines without
+    /// entry.
+    Default,
+}
+
+impl MatchKind {
+    /// Name of the kind, `exact` or `default`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchKind::Exact => "exact",
+            MatchKind::Default => "default",
+        }
+    }
+}
+
+/// The netrc finds the authenticators with [`Netrc::resolve`], restricted to
+/// the entries without protocol or with the protocol of the scheme.
+///
+/// As the `default` entry is used for the machines without entry, a netrc
+/// with a `default` entry should be the last source of a chain.
+impl CredentialSource for Netrc {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        self.find_match(host, port, scheme).map(|(auth, _)| auth)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        self.resolve_match(host, port, &LookupOptions::default())
+            .filter(|(auth, _)| match scheme {
+                Some(scheme) => {
+                    auth.protocol.is_empty() || auth.protocol.eq_ignore_ascii_case(scheme)
+                }
+                None => true,
+            })
+    }
+}
+
+/// Combination of two sources, created by [`CredentialSource::chain`].
+#[derive(Debug, Clone)]
+pub struct Chain<A, B>(pub A, pub B);
+
+impl<A: CredentialSource, B: CredentialSource> CredentialSource for Chain<A, B> {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        self.0
+            .find(host, port, scheme)
+            .or_else(|| self.1.find(host, port, scheme))
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        self.0
+            .find_match(host, port, scheme)
+            .or_else(|| self.1.find_match(host, port, scheme))
+    }
+}
+
+/// Source of the `NETRC_<HOST>_LOGIN` and `NETRC_<HOST>_PASSWORD` variables
+/// (see [`Netrc::resolve_with_env`]).
+#[derive(Debug, Clone, Default)]
+pub struct EnvSource<E = SystemEnv> {
+    env: E,
+}
+
+impl EnvSource {
+    /// Create a source of the variables of the process.
+    pub fn new() -> Self {
+        EnvSource { env: SystemEnv }
+    }
+}
+
+impl<E: Env> EnvSource<E> {
+    /// Create a source of the variables of `env`.
+    pub fn with_env(env: E) -> Self {
+        EnvSource { env }
+    }
+}
+
+impl<E: Env> CredentialSource for EnvSource<E> {
+    fn find(&self, host: &str, _port: Option<u16>, _scheme: Option<&str>) -> Option<Authenticator> {
+        let login = host_var(&self.env, host, "LOGIN");
+        let password = host_var(&self.env, host, "PASSWORD");
+        if login.is_none() && password.is_none() {
+            return None;
+        }
+        Some(Authenticator::new(
+            &login.unwrap_or_default(),
+            "",
+            &password.unwrap_or_default(),
+        ))
+    }
+}
+
+impl<S: CredentialSource + ?Sized> CredentialSource for &S {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        (**self).find(host, port, scheme)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        (**self).find_match(host, port, scheme)
+    }
+}
+
+impl<S: CredentialSource + ?Sized> CredentialSource for Box<S> {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        (**self).find(host, port, scheme)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        (**self).find_match(host, port, scheme)
+    }
+}
+
+impl<S: CredentialSource + ?Sized> CredentialSource for Rc<S> {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        (**self).find(host, port, scheme)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        (**self).find_match(host, port, scheme)
+    }
+}
+
+impl<S: CredentialSource + ?Sized> CredentialSource for Arc<S> {
+    fn find(&self, host: &str, port: Option<u16>, scheme: Option<&str>) -> Option<Authenticator> {
+        (**self).find(host, port, scheme)
+    }
+
+    fn find_match(
+        &self,
+        host: &str,
+        port: Option<u16>,
+        scheme: Option<&str>,
+    ) -> Option<(Authenticator, MatchKind)> {
+        (**self).find_match(host, port, scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Format, ParseOptions};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_chain() {
+        let nrc = Netrc::parse_with(
+            "\
+            machine host.domain.com login log1 protocol ftp
+            default login anonymous
+            ",
+            &ParseOptions {
+                format: Format::Authinfo,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let env = HashMap::from([(
+            String::from("NETRC_OTHER_COM_PASSWORD"),
+            String::from("pass"),
+        )]);
+        let source = EnvSource::with_env(env).chain(&nrc);
+        let login = |host, scheme| source.find(host, None, scheme).map(|a| a.login.clone());
+
+        assert_eq!(
+            login("host.domain.com", Some("ftp")).as_deref(),
+            Some("log1")
+        );
+        assert_eq!(login("host.domain.com", None).as_deref(), Some("log1"));
+        assert_eq!(login("host.domain.com", Some("https")), None);
+        assert_eq!(
+            login("domain.com", Some("https")).as_deref(),
+            Some("anonymous")
+        );
+        assert_eq!(
+            source.find("other.com", None, None),
+            Some(Authenticator::new("", "", "pass"))
+        );
+
+        let kind = |host| source.find_match(host, None, None).map(|(_, kind)| kind);
+        assert_eq!(kind("host.domain.com"), Some(MatchKind::Exact));
+        assert_eq!(kind("other.com"), Some(MatchKind::Exact));
+        assert_eq!(kind("domain.com"), Some(MatchKind::Default));
+        assert_eq!(Netrc::default().find_match("domain.com", None, None), None);
+    }
+}
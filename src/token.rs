@@ -0,0 +1,476 @@
+//! Lossless token stream of a netrc source.
+//!
+//! Unlike the parser, [`tokenize`] keeps every byte of the source: the
+//! concatenation of the texts of the tokens is the source itself, so that
+//! formatters, linters and syntax highlighters can work on the raw tokens.
+
+use crate::netrc::{CompatMode, ParseOptions, Span};
+use alloc::borrow::Cow;
+use alloc::string::String;
+
+/// Kind of a [`Token`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    /// An unquoted token, like a keyword, a machine name or a value.
+    Word,
+
+    /// A token in double quotes, or in single quotes with
+    /// `ParseOptions::single_quotes`, quotes included.
+    Quoted,
+
+    /// A comment, from the `#` to the end of the line (without it).
+    Comment,
+
+    /// Spaces, tabs, line ends, and the byte order mark of the source.
+    Whitespace,
+
+    /// A line of the body of a macro, without its line end.
+    MacroLine,
+}
+
+/// A token of a netrc source, located by its byte offsets and its
+/// line and column.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    start: usize,
+    line: u32,
+    column: u32,
+    compat: CompatMode,
+}
+
+impl<'a> Token<'a> {
+    /// Kind of the token.
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    /// Source text of the token, with its quotes and escapes.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Byte offset of the start of the token.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset just past the end of the token.
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Line of the start of the token (1-based).
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Column of the start of the token, in characters (1-based).
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// Location of the token.
+    pub fn span(&self) -> Span {
+        Span {
+            lineno: self.line,
+            start: self.start,
+            end: self.end(),
+        }
+    }
+
+    /// Value of the token as read by the parser: the words and the quoted
+    /// tokens are unescaped and unquoted, and the macro lines trimmed.
+    ///
+    /// ```
+    /// let tokens: Vec<_> = netrc::tokenize(r#"password "a \"b\"""#)
+    ///     .filter(|t| t.kind() != netrc::TokenKind::Whitespace)
+    ///     .collect();
+    /// assert_eq!(tokens[1].text(), r#""a \"b\"""#);
+    /// assert_eq!(tokens[1].value(), r#"a "b""#);
+    /// ```
+    pub fn value(&self) -> Cow<'a, str> {
+        match self.kind {
+            TokenKind::Word if self.compat != CompatMode::Curl => unescape(self.text, false),
+            TokenKind::Quoted => {
+                let quote = self.text.chars().next().unwrap_or('"');
+                let inner = &self.text[1..];
+                let inner = inner.strip_suffix(quote).unwrap_or(inner);
+                match quote {
+                    '"' => unescape(inner, self.compat == CompatMode::Curl),
+                    _ => Cow::Borrowed(inner),
+                }
+            }
+            TokenKind::MacroLine => Cow::Borrowed(self.text.trim()),
+            _ => Cow::Borrowed(self.text),
+        }
+    }
+}
+
+/// Remove the `\\` escapes of a token, `control` reading `\n`, `\r` and `\t`
+/// as control characters like curl.
+fn unescape(s: &str, control: bool) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+    let mut value = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match (chars.next(), control) {
+            (Some('n'), true) => value.push('\n'),
+            (Some('r'), true) => value.push('\r'),
+            (Some('t'), true) => value.push('\t'),
+            (Some(c), _) => value.push(c),
+            (None, _) => value.push(' '),
+        }
+    }
+    Cow::Owned(value)
+}
+
+/// What the next token is expected to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// A keyword, or a comment.
+    Key,
+    /// The value of a keyword.
+    Value,
+    /// The name of a macro.
+    MacroName,
+    /// The body of a macro.
+    MacroBody,
+}
+
+/// Iterator over the tokens of a netrc source, see [`tokenize`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    source: &'a str,
+    pos: usize,
+    line: u32,
+    line_start: usize,
+    state: State,
+    compat: CompatMode,
+    single_quotes: bool,
+    multiline_quotes: bool,
+}
+
+/// Split a netrc source into its tokens, with the default options.
+///
+/// ```
+/// use netrc::TokenKind;
+///
+/// let source = "machine example.com # work\n  login alice";
+/// let tokens: Vec<_> = netrc::tokenize(source).collect();
+/// assert_eq!(tokens.iter().map(|t| t.text()).collect::<String>(), source);
+///
+/// let comment = tokens.iter().find(|t| t.kind() == TokenKind::Comment).unwrap();
+/// assert_eq!(comment.text(), "# work");
+/// assert_eq!((comment.line(), comment.column()), (1, 21));
+///
+/// let login = tokens.iter().find(|t| t.text() == "login").unwrap();
+/// assert_eq!((login.start(), login.line(), login.column()), (29, 2, 3));
+/// ```
+pub fn tokenize(source: &str) -> Tokens<'_> {
+    Tokens::with_options(source, &ParseOptions::default())
+}
+
+impl<'a> Tokens<'a> {
+    /// Split a netrc source into its tokens, quoted and commented like the
+    /// parser with `options`.
+    pub fn with_options(source: &'a str, options: &ParseOptions) -> Self {
+        Tokens {
+            source,
+            pos: 0,
+            line: 1,
+            line_start: 0,
+            state: State::Key,
+            compat: options.compat,
+            single_quotes: options.single_quotes,
+            multiline_quotes: options.multiline_quotes,
+        }
+    }
+
+    /// End of the spaces and the line ends from `pos`, with the byte order
+    /// mark at the start of the source.
+    fn whitespace_end(&self) -> usize {
+        let rest = &self.source[self.pos..];
+        let bom = match self.pos == 0 && rest.starts_with('\u{feff}') {
+            true => '\u{feff}'.len_utf8(),
+            false => 0,
+        };
+        let trimmed = rest[bom..].trim_start_matches([' ', '\t', '\r', '\n']);
+        self.pos + rest.len() - trimmed.len()
+    }
+
+    /// End of the line from `pos`, without its `\n` or `\r\n` end.
+    fn line_end(&self) -> usize {
+        let rest = &self.source[self.pos..];
+        let line = rest.split('\n').next().unwrap_or(rest);
+        self.pos + line.strip_suffix('\r').unwrap_or(line).len()
+    }
+
+    /// End of the unquoted token from `pos`.
+    fn word_end(&self) -> usize {
+        let escapes = self.compat != CompatMode::Curl;
+        let mut chars = self.source[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' => return self.pos + i,
+                '\\' if escapes => {
+                    chars.next();
+                }
+                _ => (),
+            }
+        }
+        self.source.len()
+    }
+
+    /// End of the token in `quote` from `pos`, after the closing quote, or
+    /// before the line end if the quotes cannot span several lines.
+    fn quoted_end(&self, quote: char) -> usize {
+        let escapes = quote == '"';
+        let mut chars = self.source[self.pos..].char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                _ if c == quote => return self.pos + i + 1,
+                '\n' if !self.multiline_quotes => {
+                    let token = &self.source[self.pos..self.pos + i];
+                    return self.pos + token.strip_suffix('\r').map_or(i, str::len);
+                }
+                '\\' if escapes => {
+                    chars.next();
+                }
+                _ => (),
+            }
+        }
+        self.source.len()
+    }
+
+    /// Kind and end of the next token, updating the state.
+    fn next_token(&mut self) -> (TokenKind, usize) {
+        if self.state == State::MacroBody {
+            let end = self.line_end();
+            if !self.source[self.pos..end].trim().is_empty() {
+                return (TokenKind::MacroLine, end);
+            }
+            // A blank line ends the macro, unlike the blank rest of the line
+            // of its name or the end of one of its lines.
+            if self.pos == self.line_start {
+                self.state = State::Key;
+            }
+            let end = self.source[end..]
+                .find('\n')
+                .map_or(self.source.len(), |i| end + i + 1);
+            return (TokenKind::Whitespace, end);
+        }
+        let end = self.whitespace_end();
+        if end > self.pos {
+            return (TokenKind::Whitespace, end);
+        }
+        let c = self.source[self.pos..].chars().next().unwrap_or(' ');
+        if c == '"' || (c == '\'' && self.single_quotes) {
+            self.state = self.after(None);
+            return (TokenKind::Quoted, self.quoted_end(c));
+        }
+        if c == '#' && self.state == State::Key && self.compat != CompatMode::Bsd {
+            return (TokenKind::Comment, self.line_end());
+        }
+        let end = self.word_end();
+        self.state = self.after(Some(&self.source[self.pos..end]));
+        (TokenKind::Word, end)
+    }
+
+    /// State after a token, `word` being its text if it is unquoted.
+    fn after(&self, word: Option<&str>) -> State {
+        match (self.state, word) {
+            (State::Key, Some("default")) => State::Key,
+            (State::Key, Some("macdef")) => State::MacroName,
+            (State::Key, _) => State::Value,
+            (State::MacroName, _) => State::MacroBody,
+            _ => State::Key,
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.pos >= self.source.len() {
+            return None;
+        }
+        let (kind, end) = self.next_token();
+        let text = &self.source[self.pos..end];
+        let column = self.source[self.line_start..self.pos].chars().count() + 1;
+        let token = Token {
+            kind,
+            text,
+            start: self.pos,
+            line: self.line,
+            column: column as u32,
+            compat: self.compat,
+        };
+        for (i, _) in text.match_indices('\n') {
+            self.line += 1;
+            self.line_start = self.pos + i + 1;
+        }
+        self.pos = end;
+        Some(token)
+    }
+}
+
+impl core::iter::FusedIterator for Tokens<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+    use alloc::vec::Vec;
+
+    fn kinds<'a>(s: &'a str, options: &ParseOptions) -> Vec<(TokenKind, &'a str)> {
+        let tokens: Vec<_> = Tokens::with_options(s, options).collect();
+        assert_eq!(tokens.iter().map(|t| t.text()).collect::<String>(), s);
+        tokens
+            .into_iter()
+            .filter(|t| t.kind() != TokenKind::Whitespace)
+            .map(|t| (t.kind(), t.text()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lossless() {
+        let s = "\u{feff}# header\r\nmachine \"my host\" login a\\ b # note\n\
+                 default\tpassword #secret\n\nmacdef init\ncd /pub\n  get file\n\n\
+                 machine other\n";
+        let options = ParseOptions::default();
+        assert_eq!(
+            kinds(s, &options),
+            [
+                (TokenKind::Comment, "# header"),
+                (TokenKind::Word, "machine"),
+                (TokenKind::Quoted, "\"my host\""),
+                (TokenKind::Word, "login"),
+                (TokenKind::Word, "a\\ b"),
+                (TokenKind::Comment, "# note"),
+                (TokenKind::Word, "default"),
+                (TokenKind::Word, "password"),
+                (TokenKind::Word, "#secret"),
+                (TokenKind::Word, "macdef"),
+                (TokenKind::Word, "init"),
+                (TokenKind::MacroLine, "cd /pub"),
+                (TokenKind::MacroLine, "  get file"),
+                (TokenKind::Word, "machine"),
+                (TokenKind::Word, "other"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let s = "machine été\n  login \"a\\\"b\"";
+        let tokens: Vec<_> = tokenize(s)
+            .filter(|t| t.kind() != TokenKind::Whitespace)
+            .collect();
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.start(), t.end(), t.line(), t.column()))
+            .collect();
+        assert_eq!(
+            spans,
+            [(0, 7, 1, 1), (8, 13, 1, 9), (16, 21, 2, 3), (22, 28, 2, 9)]
+        );
+        assert_eq!(tokens[3].value(), "a\"b");
+        assert_eq!(
+            tokens[1].span(),
+            Span {
+                lineno: 1,
+                start: 8,
+                end: 13
+            }
+        );
+    }
+
+    #[test]
+    fn test_macro() {
+        let s = "macdef init cd /pub\nget file\n  \nmachine host";
+        let tokens: Vec<_> = tokenize(s).collect();
+        let lines: Vec<_> = tokens
+            .iter()
+            .filter(|t| t.kind() == TokenKind::MacroLine)
+            .map(|t| (t.value(), t.line()))
+            .collect();
+        assert_eq!(lines, [("cd /pub".into(), 1), ("get file".into(), 2)]);
+        assert_eq!(
+            tokens.last().map(|t| (t.kind(), t.line())),
+            Some((TokenKind::Word, 4))
+        );
+
+        // The blank rest of the line of the name does not end the macro.
+        let s = "macdef init  \r\ncd /pub\r\n\r\nmachine host";
+        let options = ParseOptions::default();
+        assert_eq!(
+            kinds(s, &options),
+            [
+                (TokenKind::Word, "macdef"),
+                (TokenKind::Word, "init"),
+                (TokenKind::MacroLine, "cd /pub"),
+                (TokenKind::Word, "machine"),
+                (TokenKind::Word, "host"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let mut options = ParseOptions::default();
+        assert_eq!(
+            kinds("machine 'a' password #p # c\\n", &options),
+            [
+                (TokenKind::Word, "machine"),
+                (TokenKind::Word, "'a'"),
+                (TokenKind::Word, "password"),
+                (TokenKind::Word, "#p"),
+                (TokenKind::Comment, "# c\\n"),
+            ]
+        );
+        options.multiline_quotes = false;
+        assert_eq!(
+            kinds("login \"x\r\ny\"", &options),
+            [
+                (TokenKind::Word, "login"),
+                (TokenKind::Quoted, "\"x"),
+                (TokenKind::Word, "y\""),
+            ]
+        );
+
+        let s = "machine 'a b' login \"x\ny\" password #p # c\\n";
+
+        options.single_quotes = true;
+        options.multiline_quotes = true;
+        options.compat = CompatMode::Bsd;
+        assert_eq!(
+            kinds(s, &options),
+            [
+                (TokenKind::Word, "machine"),
+                (TokenKind::Quoted, "'a b'"),
+                (TokenKind::Word, "login"),
+                (TokenKind::Quoted, "\"x\ny\""),
+                (TokenKind::Word, "password"),
+                (TokenKind::Word, "#p"),
+                (TokenKind::Word, "#"),
+                (TokenKind::Word, "c\\n"),
+            ]
+        );
+
+        options.compat = CompatMode::Curl;
+        let tokens: Vec<_> = Tokens::with_options("password \"a\\tb\" login a\\tb", &options)
+            .filter(|t| t.kind() != TokenKind::Whitespace)
+            .map(|t| t.value())
+            .collect();
+        assert_eq!(tokens, ["password", "a\tb", "login", "a\\tb"]);
+    }
+}
@@ -0,0 +1,92 @@
+//! Credentials stored in the KV secrets engine of HashiCorp Vault.
+
+use crate::{normalize_host, Authenticator, CredentialSource};
+use std::process::Command;
+
+/// Source of the credentials stored in the KV secrets engine of Vault, read
+/// with the `vault` command.
+///
+/// The secret of a machine is found with a path template, where `{host}` is
+/// replaced by the normalized machine name. The `vault` command is configured
+/// with its environment, like `VAULT_ADDR` and `VAULT_TOKEN`.
+///
+/// ```no_run
+/// use netrc::{CredentialSource, Netrc, VaultSource};
+///
+/// let source = VaultSource::new("netrc/{host}").chain(Netrc::new().unwrap());
+/// let auth = source.find("host.domain.com", None, None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VaultSource {
+    mount: String,
+    path: String,
+    login_field: String,
+    password_field: String,
+}
+
+impl VaultSource {
+    /// Create a source of the secrets of the `secret` mount, with the `login`
+    /// and `password` fields.
+    pub fn new(path: &str) -> Self {
+        VaultSource {
+            mount: String::from("secret"),
+            path: path.to_owned(),
+            login_field: String::from("login"),
+            password_field: String::from("password"),
+        }
+    }
+
+    /// Set the mount of the KV secrets engine.
+    pub fn mount(mut self, mount: &str) -> Self {
+        self.mount = mount.to_owned();
+        self
+    }
+
+    /// Set the fields of the login and the password in the secrets.
+    pub fn fields(mut self, login: &str, password: &str) -> Self {
+        self.login_field = login.to_owned();
+        self.password_field = password.to_owned();
+        self
+    }
+
+    /// Path of the secret of `host`.
+    fn path(&self, host: &str) -> String {
+        self.path.replace("{host}", &normalize_host(host))
+    }
+
+    /// Field of the secret at `path`.
+    fn field(&self, path: &str, field: &str) -> Option<String> {
+        let output = Command::new("vault")
+            .args(["kv", "get"])
+            .arg(format!("-mount={}", self.mount))
+            .arg(format!("-field={}", field))
+            .arg(path)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())?;
+        String::from_utf8(output.stdout).ok()
+    }
+}
+
+impl CredentialSource for VaultSource {
+    fn find(&self, host: &str, _port: Option<u16>, _scheme: Option<&str>) -> Option<Authenticator> {
+        let path = self.path(host);
+        let password = self.field(&path, &self.password_field)?;
+        let login = self.field(&path, &self.login_field).unwrap_or_default();
+        Some(Authenticator::new(&login, "", &password))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        let source = VaultSource::new("netrc/{host}/creds");
+        assert_eq!(
+            source.path("Host.Domain.com."),
+            "netrc/host.domain.com/creds"
+        );
+    }
+}
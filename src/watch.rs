@@ -0,0 +1,165 @@
+//! Notification of the changes of a netrc file, with the `notify` crate.
+//!
+//! The directory of the file is watched rather than the file, so that the
+//! file is still watched once replaced by a rename, like by [`Netrc::save`].
+
+use crate::{Error, Netrc, ParseOptions, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+
+/// Watcher of a netrc file, returned by [`Netrc::watch`].
+///
+/// The file is not watched anymore once the watcher is dropped.
+pub struct NetrcWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl std::fmt::Debug for NetrcWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetrcWatcher").finish_non_exhaustive()
+    }
+}
+
+/// Hash of the content of the file, to ignore the events not changing it.
+/// The content itself is not kept, as it contains the passwords.
+fn content_hash(file: &Path) -> Option<u64> {
+    let content = fs::read(file).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+
+    #[cfg(feature = "zeroize")]
+    {
+        use zeroize::Zeroize;
+
+        let mut content = content;
+        content.zeroize();
+    }
+
+    Some(hasher.finish())
+}
+
+fn watch_error(e: notify::Error) -> Error {
+    Error::Watch(e.to_string())
+}
+
+impl Netrc {
+    /// Watch a netrc file, and call `callback` with the file parsed again
+    /// each time its content changes.
+    ///
+    /// The callback is called from the thread of the watcher, and not for the
+    /// current content of the file. The machines which changed are given by
+    /// [`Netrc::diff`] with the previous netrc. A channel can turn the
+    /// notifications into a stream:
+    ///
+    /// ```no_run
+    /// use netrc::Netrc;
+    /// use std::path::Path;
+    /// use std::sync::mpsc;
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// let file = Path::new("/home/me/.netrc");
+    /// let _watcher = Netrc::watch(file, move |nrc| {
+    ///     let _ = tx.send(nrc);
+    /// })
+    /// .unwrap();
+    /// for nrc in rx {
+    ///     println!("{:?}", nrc.map(|nrc| nrc.hosts.len()));
+    /// }
+    /// ```
+    pub fn watch<F>(file: &Path, callback: F) -> Result<NetrcWatcher>
+    where
+        F: FnMut(Result<Netrc>) + Send + 'static,
+    {
+        Netrc::watch_with(file, &ParseOptions::default(), callback)
+    }
+
+    /// Watch a netrc file like [`Netrc::watch`], parsed with the given
+    /// options.
+    pub fn watch_with<F>(
+        file: &Path,
+        options: &ParseOptions,
+        mut callback: F,
+    ) -> Result<NetrcWatcher>
+    where
+        F: FnMut(Result<Netrc>) + Send + 'static,
+    {
+        // Follow the symbolic links to watch the directory of the target.
+        let file = fs::canonicalize(file)?;
+        let (dir, name) = match (file.parent(), file.file_name()) {
+            (Some(dir), Some(name)) => (dir.to_path_buf(), name.to_os_string()),
+            _ => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid netrc file name",
+                )))
+            }
+        };
+        let options = options.clone();
+        let mut last = content_hash(&file);
+
+        let handler = move |res: notify::Result<Event>| {
+            let event = match res {
+                Ok(event) => event,
+                Err(e) => return callback(Err(watch_error(e))),
+            };
+            let changed = matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) && event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == Some(name.as_os_str()));
+            if !changed {
+                return;
+            }
+            let hash = content_hash(&file);
+            if hash.is_some() && hash == last {
+                return;
+            }
+            last = hash;
+            callback(Netrc::from_file_with(&file, &options));
+        };
+
+        let mut watcher = notify::recommended_watcher(handler).map_err(watch_error)?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        Ok(NetrcWatcher { _watcher: watcher })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_watch() {
+        let dir = std::env::temp_dir().join("netrc-watch");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("netrc");
+        fs::write(&file, "machine host.domain.com login log password old").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let _watcher = Netrc::watch(&file, move |nrc| {
+            let _ = tx.send(nrc);
+        })
+        .unwrap();
+
+        let nrc: Netrc = "machine host.domain.com login log password new"
+            .parse()
+            .unwrap();
+        nrc.save(&file).unwrap();
+        let nrc = rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap();
+        assert_eq!(nrc.hosts["host.domain.com"].password, "new");
+
+        // The same content is not notified again.
+        fs::write(&file, nrc.to_string()).unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use netrc::{CredentialSource, Netrc, Result};
+use std::path::Path;
+use std::sync::Arc;
+use surf::http::auth::BasicAuth;
+use surf::http::headers::AUTHORIZATION;
+use surf::middleware::{Middleware, Next};
+use surf::{Client, Request, Response, Url};
+
+/// Middleware of `surf` adding the netrc credentials to the requests without
+/// `Authorization` header.
+///
+/// The credentials are sent over HTTPS or to a loopback host only, unless
+/// [`https_only`](NetrcMiddleware::https_only) is disabled.
+///
+/// ```no_run
+/// use surf::Client;
+/// use surf_netrc::NetrcMiddleware;
+///
+/// # fn wrap(client: Client) {
+/// let client = client.with(NetrcMiddleware::new().unwrap());
+/// # }
+/// ```
+pub struct NetrcMiddleware<S = Netrc> {
+    source: Arc<S>,
+    https_only: bool,
+    auth_scheme: AuthScheme,
+}
+
+/// Authentication scheme of the netrc credentials.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum AuthScheme {
+    /// `Basic` authentication with the login and the password.
+    #[default]
+    Basic,
+
+    /// `Bearer` authentication with the password as token.
+    Bearer,
+}
+
+impl NetrcMiddleware {
+    pub fn new() -> Result<Self> {
+        Netrc::new().map(NetrcMiddleware::from_source)
+    }
+
+    pub fn from_file(file: &Path) -> Result<Self> {
+        Netrc::from_file(file).map(NetrcMiddleware::from_source)
+    }
+}
+
+impl<S: CredentialSource> NetrcMiddleware<S> {
+    /// Create a middleware finding the credentials in any source, like a
+    /// chain of a netrc and other backends.
+    pub fn from_source(source: S) -> Self {
+        NetrcMiddleware::from_arc(Arc::new(source))
+    }
+
+    /// Create a middleware sharing its source with the rest of the
+    /// application.
+    pub fn from_arc(source: Arc<S>) -> Self {
+        NetrcMiddleware {
+            source,
+            https_only: true,
+            auth_scheme: AuthScheme::default(),
+        }
+    }
+
+    /// Send the netrc credentials only over HTTPS, or to a loopback address
+    /// like `localhost` (enabled by default).
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.https_only = enabled;
+        self
+    }
+
+    /// Set the authentication scheme of the netrc credentials (`Basic` by
+    /// default).
+    pub fn auth_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.auth_scheme = scheme;
+        self
+    }
+
+    /// `Authorization` header of a request to `url`.
+    fn authorization(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+        if self.https_only && !netrc::is_secure(url.scheme(), host) {
+            return None;
+        }
+        let auth = self
+            .source
+            .find(host, url.port_or_known_default(), Some(url.scheme()))?;
+        match self.auth_scheme {
+            AuthScheme::Basic => Some(
+                BasicAuth::new(&auth.login, &auth.password)
+                    .value()
+                    .to_string(),
+            ),
+            AuthScheme::Bearer => Some(format!("Bearer {}", auth.password)),
+        }
+    }
+}
+
+#[surf::utils::async_trait]
+impl<S: CredentialSource + Send + Sync + 'static> Middleware for NetrcMiddleware<S> {
+    async fn handle(
+        &self,
+        mut req: Request,
+        client: Client,
+        next: Next<'_>,
+    ) -> surf::Result<Response> {
+        if req.header(AUTHORIZATION).is_none() {
+            if let Some(value) = self.authorization(req.url()) {
+                req.insert_header(AUTHORIZATION, value);
+            }
+        }
+        next.run(req, client).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization() {
+        let nrc: Netrc = "\
+            machine host.domain.com login log password pass
+            machine localhost:8080 login local password pass
+            "
+        .parse()
+        .unwrap();
+        let middleware = NetrcMiddleware::from_source(nrc);
+        let authorization = |middleware: &NetrcMiddleware, url: &str| {
+            middleware.authorization(&Url::parse(url).unwrap())
+        };
+
+        assert_eq!(
+            authorization(&middleware, "https://host.domain.com/api"),
+            Some(String::from("Basic bG9nOnBhc3M="))
+        );
+        assert_eq!(
+            authorization(&middleware, "http://host.domain.com/api"),
+            None
+        );
+        assert_eq!(
+            authorization(&middleware, "http://localhost:8080/api"),
+            Some(String::from("Basic bG9jYWw6cGFzcw=="))
+        );
+        assert_eq!(authorization(&middleware, "https://other.com/api"), None);
+        assert_eq!(
+            authorization(&middleware, "ftp://host.domain.com/file"),
+            None
+        );
+
+        let middleware = middleware.auth_scheme(AuthScheme::Bearer);
+        assert_eq!(
+            authorization(&middleware, "https://host.domain.com/api"),
+            Some(String::from("Bearer pass"))
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use http::header::AUTHORIZATION;
+use http::uri::Uri;
+use http::{HeaderValue, Request};
+use netrc::http::{authorization, is_secure};
+use netrc::{CredentialSource, Netrc, Result};
+use std::path::Path;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Layer adding the netrc credentials to the requests of any
+/// `Service<http::Request<B>>`, like a hyper client.
+///
+/// The `Basic` credentials of the host of the request URI are added to the
+/// requests without `Authorization` header, over HTTPS or to a loopback host
+/// only unless [`https_only`](NetrcAuthLayer::https_only) is disabled. The
+/// URI of the requests must be absolute, like the ones of a client.
+///
+/// ```no_run
+/// use tower_layer::Layer;
+/// use tower_netrc::NetrcAuthLayer;
+///
+/// # fn wrap<S>(client: S) {
+/// let client = NetrcAuthLayer::new().unwrap().layer(client);
+/// # }
+/// ```
+pub struct NetrcAuthLayer<S = Netrc> {
+    source: Arc<S>,
+    https_only: bool,
+}
+
+impl<S> Clone for NetrcAuthLayer<S> {
+    fn clone(&self) -> Self {
+        NetrcAuthLayer {
+            source: self.source.clone(),
+            https_only: self.https_only,
+        }
+    }
+}
+
+impl NetrcAuthLayer {
+    pub fn new() -> Result<Self> {
+        Netrc::new().map(NetrcAuthLayer::from_source)
+    }
+
+    pub fn from_file(file: &Path) -> Result<Self> {
+        Netrc::from_file(file).map(NetrcAuthLayer::from_source)
+    }
+}
+
+impl<S: CredentialSource> NetrcAuthLayer<S> {
+    /// Create a layer finding the credentials in any source, like a chain of
+    /// a netrc and other backends.
+    pub fn from_source(source: S) -> Self {
+        NetrcAuthLayer::from_arc(Arc::new(source))
+    }
+
+    /// Create a layer sharing its source with the rest of the application.
+    pub fn from_arc(source: Arc<S>) -> Self {
+        NetrcAuthLayer {
+            source,
+            https_only: true,
+        }
+    }
+
+    /// Send the netrc credentials only over HTTPS, or to a loopback address
+    /// like `localhost` (enabled by default).
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.https_only = enabled;
+        self
+    }
+}
+
+impl<Svc, S> Layer<Svc> for NetrcAuthLayer<S> {
+    type Service = NetrcAuth<Svc, S>;
+
+    fn layer(&self, inner: Svc) -> Self::Service {
+        NetrcAuth {
+            inner,
+            source: self.source.clone(),
+            https_only: self.https_only,
+        }
+    }
+}
+
+/// Service adding the netrc credentials to the requests, created by
+/// [`NetrcAuthLayer`].
+pub struct NetrcAuth<Svc, S = Netrc> {
+    inner: Svc,
+    source: Arc<S>,
+    https_only: bool,
+}
+
+impl<Svc: Clone, S> Clone for NetrcAuth<Svc, S> {
+    fn clone(&self) -> Self {
+        NetrcAuth {
+            inner: self.inner.clone(),
+            source: self.source.clone(),
+            https_only: self.https_only,
+        }
+    }
+}
+
+impl<Svc, S> NetrcAuth<Svc, S> {
+    /// Wrapped service.
+    pub fn get_ref(&self) -> &Svc {
+        &self.inner
+    }
+
+    /// Wrapped service, unwrapped.
+    pub fn into_inner(self) -> Svc {
+        self.inner
+    }
+}
+
+impl<Svc, S: CredentialSource> NetrcAuth<Svc, S> {
+    /// `Authorization` header of a request to `uri`.
+    fn authorization(&self, uri: &Uri) -> Option<HeaderValue> {
+        if self.https_only && !is_secure(uri) {
+            return None;
+        }
+        authorization(self.source.as_ref(), uri)
+    }
+}
+
+impl<Svc, S, B> Service<Request<B>> for NetrcAuth<Svc, S>
+where
+    Svc: Service<Request<B>>,
+    S: CredentialSource,
+{
+    type Response = Svc::Response;
+    type Error = Svc::Error;
+    type Future = Svc::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        if !req.headers().contains_key(AUTHORIZATION) {
+            if let Some(value) = self.authorization(req.uri()) {
+                req.headers_mut().insert(AUTHORIZATION, value);
+            }
+        }
+        self.inner.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::{ready, Ready};
+
+    /// Service keeping the last request.
+    #[derive(Default)]
+    struct Capture(Option<Request<()>>);
+
+    impl Service<Request<()>> for Capture {
+        type Response = ();
+        type Error = ();
+        type Future = Ready<std::result::Result<(), ()>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<std::result::Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<()>) -> Self::Future {
+            self.0 = Some(req);
+            ready(Ok(()))
+        }
+    }
+
+    fn authorization(service: &mut NetrcAuth<Capture>, req: Request<()>) -> Option<String> {
+        assert!(service.call(req).into_inner().is_ok());
+        service.inner.0.take().and_then(|req| {
+            req.headers()
+                .get(AUTHORIZATION)
+                .map(|v| v.to_str().unwrap().to_owned())
+        })
+    }
+
+    #[test]
+    fn test_layer() {
+        let nrc: Netrc = "\
+            machine host.domain.com login log password pass
+            machine localhost:8080 login local password pass
+            "
+        .parse()
+        .unwrap();
+        let mut service = NetrcAuthLayer::from_source(nrc).layer(Capture::default());
+        let get = |uri: &str| Request::get(uri).body(()).unwrap();
+
+        assert_eq!(
+            authorization(&mut service, get("https://host.domain.com/api")),
+            Some(String::from("Basic bG9nOnBhc3M="))
+        );
+        assert_eq!(
+            authorization(&mut service, get("http://host.domain.com/api")),
+            None
+        );
+        assert_eq!(
+            authorization(&mut service, get("http://localhost:8080/api")),
+            Some(String::from("Basic bG9jYWw6cGFzcw=="))
+        );
+        assert_eq!(authorization(&mut service, get("/api")), None);
+
+        let req = Request::get("https://host.domain.com/api")
+            .header(AUTHORIZATION, "Bearer token")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            authorization(&mut service, req),
+            Some(String::from("Bearer token"))
+        );
+    }
+}
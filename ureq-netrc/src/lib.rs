@@ -0,0 +1,144 @@
+use netrc::http::{authenticator, basic_auth, bearer_auth, is_secure};
+use netrc::{CredentialSource, Netrc, Result};
+use std::path::Path;
+use std::sync::Arc;
+use ureq::http::header::AUTHORIZATION;
+use ureq::http::{HeaderValue, Request, Response, Uri};
+use ureq::middleware::{Middleware, MiddlewareNext};
+use ureq::{Body, SendBody};
+
+/// Middleware of `ureq` adding the netrc credentials to the requests without
+/// `Authorization` header.
+///
+/// The credentials are sent over HTTPS or to a loopback host only, unless
+/// [`https_only`](NetrcMiddleware::https_only) is disabled.
+///
+/// ```no_run
+/// use ureq::Agent;
+/// use ureq_netrc::NetrcMiddleware;
+///
+/// let agent: Agent = Agent::config_builder()
+///     .middleware(NetrcMiddleware::new().unwrap())
+///     .build()
+///     .into();
+/// let res = agent.get("https://domain.com/api").call();
+/// ```
+pub struct NetrcMiddleware<S = Netrc> {
+    source: Arc<S>,
+    https_only: bool,
+    auth_scheme: AuthScheme,
+}
+
+/// Authentication scheme of the netrc credentials.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum AuthScheme {
+    /// `Basic` authentication with the login and the password.
+    #[default]
+    Basic,
+
+    /// `Bearer` authentication with the password as token.
+    Bearer,
+}
+
+impl NetrcMiddleware {
+    pub fn new() -> Result<Self> {
+        Netrc::new().map(NetrcMiddleware::from_source)
+    }
+
+    pub fn from_file(file: &Path) -> Result<Self> {
+        Netrc::from_file(file).map(NetrcMiddleware::from_source)
+    }
+}
+
+impl<S: CredentialSource> NetrcMiddleware<S> {
+    /// Create a middleware finding the credentials in any source, like a
+    /// chain of a netrc and other backends.
+    pub fn from_source(source: S) -> Self {
+        NetrcMiddleware::from_arc(Arc::new(source))
+    }
+
+    /// Create a middleware sharing its source with the rest of the
+    /// application.
+    pub fn from_arc(source: Arc<S>) -> Self {
+        NetrcMiddleware {
+            source,
+            https_only: true,
+            auth_scheme: AuthScheme::default(),
+        }
+    }
+
+    /// Send the netrc credentials only over HTTPS, or to a loopback address
+    /// like `localhost` (enabled by default).
+    pub fn https_only(mut self, enabled: bool) -> Self {
+        self.https_only = enabled;
+        self
+    }
+
+    /// Set the authentication scheme of the netrc credentials (`Basic` by
+    /// default).
+    pub fn auth_scheme(mut self, scheme: AuthScheme) -> Self {
+        self.auth_scheme = scheme;
+        self
+    }
+
+    /// `Authorization` header of a request to `uri`.
+    fn authorization(&self, uri: &Uri) -> Option<HeaderValue> {
+        if self.https_only && !is_secure(uri) {
+            return None;
+        }
+        let auth = authenticator(self.source.as_ref(), uri)?;
+        match self.auth_scheme {
+            AuthScheme::Basic => basic_auth(&auth),
+            AuthScheme::Bearer => bearer_auth(&auth),
+        }
+    }
+}
+
+impl<S: CredentialSource + Send + Sync + 'static> Middleware for NetrcMiddleware<S> {
+    fn handle(
+        &self,
+        mut request: Request<SendBody>,
+        next: MiddlewareNext,
+    ) -> std::result::Result<Response<Body>, ureq::Error> {
+        if !request.headers().contains_key(AUTHORIZATION) {
+            if let Some(value) = self.authorization(request.uri()) {
+                request.headers_mut().insert(AUTHORIZATION, value);
+            }
+        }
+        next.handle(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization() {
+        let nrc: Netrc = "machine host.domain.com login log password pass"
+            .parse()
+            .unwrap();
+        let middleware = NetrcMiddleware::from_source(nrc);
+        let authorization = |middleware: &NetrcMiddleware, uri: &str| {
+            middleware
+                .authorization(&uri.parse().unwrap())
+                .map(|v| v.to_str().unwrap().to_owned())
+        };
+
+        assert_eq!(
+            authorization(&middleware, "https://host.domain.com/api"),
+            Some(String::from("Basic bG9nOnBhc3M="))
+        );
+        assert_eq!(
+            authorization(&middleware, "http://host.domain.com/api"),
+            None
+        );
+        assert_eq!(authorization(&middleware, "https://other.com/api"), None);
+
+        let middleware = middleware.auth_scheme(AuthScheme::Bearer);
+        assert_eq!(
+            authorization(&middleware, "https://host.domain.com/api"),
+            Some(String::from("Bearer pass"))
+        );
+    }
+}